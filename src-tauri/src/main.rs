@@ -1,11 +1,12 @@
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
-use regex::Regex;
+use chrono::Timelike;
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sha2::{Digest, Sha256};
 use std::{
-    collections::VecDeque,
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
     fs,
     io::{BufRead, BufReader, Read, Write},
     net::{TcpListener, TcpStream},
@@ -29,6 +30,38 @@ fn default_cut_at_timestamp_enabled() -> bool {
     true
 }
 
+fn default_resume_queue_on_startup() -> bool {
+    true
+}
+
+fn default_max_concurrent_downloads() -> usize {
+    1
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_seconds() -> u64 {
+    15
+}
+
+fn default_retry_max_delay_seconds() -> u64 {
+    600
+}
+
+fn default_download_window_start() -> String {
+    "23:00".to_string()
+}
+
+fn default_download_window_end() -> String {
+    "07:00".to_string()
+}
+
+fn default_output_template() -> String {
+    DEFAULT_OUTPUT_TEMPLATE.to_string()
+}
+
 const LEGACY_CONFIG_MIGRATION_KEY: &str = "legacy_config_json_migrated";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +76,35 @@ struct AppConfig {
     cut_at_timestamp_enabled: bool,
     #[serde(default)]
     last_download_url: Option<String>,
+    #[serde(default = "default_resume_queue_on_startup")]
+    resume_queue_on_startup: bool,
+    #[serde(default = "default_max_concurrent_downloads")]
+    max_concurrent_downloads: usize,
+    #[serde(default = "default_retry_max_attempts")]
+    retry_max_attempts: u32,
+    #[serde(default = "default_retry_base_delay_seconds")]
+    retry_base_delay_seconds: u64,
+    #[serde(default = "default_retry_max_delay_seconds")]
+    retry_max_delay_seconds: u64,
+    // New jobs only start inside this local "HH:MM" window while it is enabled.
+    #[serde(default)]
+    download_window_enabled: bool,
+    #[serde(default = "default_download_window_start")]
+    download_window_start: String,
+    #[serde(default = "default_download_window_end")]
+    download_window_end: String,
+    // Rate caps are in KiB/s and shared by all running downloads.
+    #[serde(default)]
+    rate_limit_kib: Option<u64>,
+    #[serde(default)]
+    download_window_rate_limit_kib: Option<u64>,
+    #[serde(default)]
+    throttled_rate_kib: Option<u64>,
+    #[serde(default)]
+    cut_precision: CutPrecision,
+    // File name template below the output folder; unset uses the default.
+    #[serde(default)]
+    output_template: Option<String>,
 }
 
 impl Default for AppConfig {
@@ -54,10 +116,120 @@ impl Default for AppConfig {
             magic_import_enabled: default_magic_import_enabled(),
             cut_at_timestamp_enabled: default_cut_at_timestamp_enabled(),
             last_download_url: None,
+            resume_queue_on_startup: default_resume_queue_on_startup(),
+            max_concurrent_downloads: default_max_concurrent_downloads(),
+            retry_max_attempts: default_retry_max_attempts(),
+            retry_base_delay_seconds: default_retry_base_delay_seconds(),
+            retry_max_delay_seconds: default_retry_max_delay_seconds(),
+            download_window_enabled: false,
+            download_window_start: default_download_window_start(),
+            download_window_end: default_download_window_end(),
+            rate_limit_kib: None,
+            download_window_rate_limit_kib: None,
+            throttled_rate_kib: None,
+            cut_precision: CutPrecision::default(),
+            output_template: None,
+        }
+    }
+}
+
+// Empty `languages` leaves the choice to yt-dlp, which defaults to English.
+// Entries may be regexes such as `en.*`, as accepted by `--sub-langs`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct SubtitleOptions {
+    write_subs: bool,
+    write_auto_subs: bool,
+    languages: Vec<String>,
+    convert_format: Option<String>,
+    embed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct EmbedOptions {
+    metadata: bool,
+    thumbnail: bool,
+    chapters: bool,
+}
+
+impl EmbedOptions {
+    const NONE: Self = Self {
+        metadata: false,
+        thumbnail: false,
+        chapters: false,
+    };
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SponsorBlockMode {
+    #[default]
+    Off,
+    Mark,
+    Remove,
+}
+
+// Empty `categories` uses yt-dlp's `default` set (every category but filler).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct SponsorBlockOptions {
+    mode: SponsorBlockMode,
+    categories: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SponsorBlockSegment {
+    category: String,
+    start_time: f64,
+    end_time: f64,
+}
+
+// `Range` asks yt-dlp for just the cut range; `Local` downloads the full file
+// and cuts it with ffmpeg afterwards, which also is the fallback for
+// extractors that cannot download partial ranges.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CutMode {
+    #[default]
+    Range,
+    Local,
+}
+
+// `Fast` stream-copies and may start at the keyframe before the cut;
+// `Accurate` re-encodes with the source codecs to land on the exact frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CutPrecision {
+    #[default]
+    Fast,
+    Accurate,
+}
+
+impl CutPrecision {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Fast => "fast",
+            Self::Accurate => "accurate",
+        }
+    }
+
+    fn from_db_value(value: &str) -> Self {
+        match value {
+            "accurate" => Self::Accurate,
+            _ => Self::Fast,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct ClipRange {
+    #[serde(default)]
+    start: f64,
+    #[serde(default)]
+    end: Option<f64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct DownloadRequest {
     url: String,
@@ -71,7 +243,31 @@ struct DownloadRequest {
     #[serde(default)]
     cut_start_time: Option<f64>,
     #[serde(default)]
+    cut_end_time: Option<f64>,
+    // Each range becomes its own queue item; overrides the single cut above.
+    #[serde(default)]
+    ranges: Vec<ClipRange>,
+    #[serde(default)]
+    cut_mode: CutMode,
+    // Falls back to the global setting when unset.
+    #[serde(default)]
+    cut_precision: Option<CutPrecision>,
+    // Stream ids from `load_info`; when set they replace `format`.
+    #[serde(default)]
+    video_format_id: Option<String>,
+    #[serde(default)]
+    audio_format_id: Option<String>,
+    // Falls back to the global template when unset.
+    #[serde(default)]
+    output_template: Option<String>,
+    #[serde(default)]
     filename_suffix: Option<String>,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default)]
+    not_before: Option<u64>,
+    #[serde(default)]
+    rate_limit_kib: Option<u64>,
     title: Option<String>,
     #[serde(default)]
     uploader: Option<String>,
@@ -82,6 +278,16 @@ struct DownloadRequest {
     timestamp: Option<i64>,
     #[serde(default)]
     duration_seconds: Option<i64>,
+    #[serde(default)]
+    force_download: bool,
+    #[serde(default)]
+    subtitles: SubtitleOptions,
+    #[serde(default)]
+    embed: EmbedOptions,
+    #[serde(default)]
+    sponsorblock: SponsorBlockOptions,
+    #[serde(default)]
+    split_chapters: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,24 +306,228 @@ struct DownloadJob {
     timestamp: Option<i64>,
     duration_seconds: Option<i64>,
     cut_start_time: Option<f64>,
+    #[serde(default)]
+    cut_end_time: Option<f64>,
+    #[serde(default)]
+    cut_mode: CutMode,
+    #[serde(default)]
+    cut_precision: CutPrecision,
+    #[serde(default = "default_output_template")]
+    output_template: String,
     filename_suffix: Option<String>,
+    #[serde(default)]
+    paused: bool,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default)]
+    retry_count: u32,
+    #[serde(default)]
+    retry_at: Option<u64>,
+    #[serde(default)]
+    not_before: Option<u64>,
+    #[serde(default)]
+    rate_limit_kib: Option<u64>,
+    #[serde(default)]
+    batch_id: Option<String>,
+    #[serde(default)]
+    playlist_title: Option<String>,
+    #[serde(default)]
+    playlist_index: Option<u32>,
+    #[serde(default)]
+    playlist_in_output_template: bool,
+    #[serde(default)]
+    force_download: bool,
+    #[serde(default)]
+    subtitles: SubtitleOptions,
+    #[serde(default)]
+    embed: EmbedOptions,
+    #[serde(default)]
+    sponsorblock: SponsorBlockOptions,
+    #[serde(default)]
+    split_chapters: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Byte and fragment counts come straight from yt-dlp's progress hook; `percent`
+// falls back to fragments when the size is unknown.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct DownloadProgress {
     id: String,
     percent: Option<f32>,
     speed: Option<String>,
     eta: Option<String>,
+    #[serde(default)]
+    downloaded_bytes: Option<u64>,
+    #[serde(default)]
+    total_bytes: Option<u64>,
+    #[serde(default)]
+    total_bytes_estimate: Option<u64>,
+    #[serde(default)]
+    fragment_index: Option<u64>,
+    #[serde(default)]
+    fragment_count: Option<u64>,
+    #[serde(default)]
+    stream: Option<DownloadStream>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DownloadStream {
+    Video,
+    Audio,
+    Merge,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct DownloadStateEvent {
     id: String,
-    state: String,
+    #[serde(flatten)]
+    state: JobState,
+}
+
+// Timestamps are Unix milliseconds. The `state` tag is part of the public event
+// and `get_job` contract, so variants may only be added, never renamed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "kebab-case")]
+enum JobState {
+    Queued {
+        queued_at: u64,
+    },
+    Paused {
+        paused_at: u64,
+    },
+    Retrying {
+        attempt: u32,
+        failed_at: u64,
+        next_retry_at: u64,
+        error: JobError,
+    },
+    FetchingMetadata {
+        started_at: u64,
+    },
+    Downloading {
+        started_at: u64,
+    },
+    Merging {
+        started_at: u64,
+    },
+    Cutting {
+        started_at: u64,
+    },
+    Transcribing {
+        started_at: u64,
+    },
+    Pausing {
+        requested_at: u64,
+    },
+    Cancelling {
+        requested_at: u64,
+    },
+    Done {
+        finished_at: u64,
+        output_path: Option<String>,
+        // Every file the job produced, including split chapter files.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        output_paths: Vec<String>,
+    },
+    // yt-dlp found the video in the download archive and did not download it.
+    Skipped {
+        skipped_at: u64,
+    },
+    Failed {
+        failed_at: u64,
+        error: JobError,
+    },
+    Cancelled {
+        cancelled_at: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct JobError {
+    kind: JobErrorKind,
+    message: String,
+    #[serde(default)]
     exit_code: Option<i32>,
-    error: Option<String>,
-    output_path: Option<String>,
+    #[serde(default)]
+    stderr_lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum JobErrorKind {
+    YtDlp,
+    Transcription,
+    Internal,
+}
+
+impl JobError {
+    fn new(kind: JobErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            exit_code: None,
+            stderr_lines: Vec::new(),
+        }
+    }
+
+    fn yt_dlp(exit_code: i32, reason: Option<&str>, stderr_lines: &[String]) -> Self {
+        let message = match reason {
+            Some(reason) => format!("yt-dlp exited with error: {reason}"),
+            None => "yt-dlp exited with error".to_string(),
+        };
+        Self {
+            kind: JobErrorKind::YtDlp,
+            message,
+            exit_code: Some(exit_code),
+            stderr_lines: stderr_lines.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JobLifecycle {
+    id: String,
+    state: JobState,
+    transitions: Vec<JobState>,
+}
+
+// Finished lifecycles are only kept for the most recent jobs, oldest first in
+// `finished`, so a long session does not grow the map without bound.
+#[derive(Debug, Default)]
+struct JobLifecycles {
+    entries: HashMap<String, JobLifecycle>,
+    finished: VecDeque<String>,
+}
+
+impl JobLifecycles {
+    fn record(&mut self, id: &str, job_state: JobState) {
+        let terminal = is_terminal_job_state(&job_state);
+        let lifecycle = self
+            .entries
+            .entry(id.to_string())
+            .or_insert_with(|| JobLifecycle {
+                id: id.to_string(),
+                state: job_state.clone(),
+                transitions: Vec::new(),
+            });
+        lifecycle.state = job_state.clone();
+        lifecycle.transitions.push(job_state);
+
+        // A requeued job is live again and must not be evicted as finished.
+        self.finished.retain(|finished| finished != id);
+        if terminal {
+            self.finished.push_back(id.to_string());
+            while self.finished.len() > FINISHED_JOB_LIFECYCLES_LIMIT {
+                if let Some(evicted) = self.finished.pop_front() {
+                    self.entries.remove(&evicted);
+                }
+            }
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<&JobLifecycle> {
+        self.entries.get(id)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -136,6 +546,32 @@ struct InfoFormat {
     height: Option<i64>,
     width: Option<i64>,
     fps: Option<f64>,
+    #[serde(default)]
+    filesize: Option<u64>,
+    #[serde(default)]
+    filesize_approx: Option<u64>,
+    // Bitrates in KBit/s as reported by yt-dlp.
+    #[serde(default)]
+    tbr: Option<f64>,
+    #[serde(default)]
+    abr: Option<f64>,
+    #[serde(default)]
+    vbr: Option<f64>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    dynamic_range: Option<String>,
+    #[serde(default)]
+    protocol: Option<String>,
+    #[serde(default)]
+    format_note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct InfoChapter {
+    title: Option<String>,
+    start_time: f64,
+    end_time: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -149,6 +585,159 @@ struct InfoResponse {
     formats: Option<Vec<InfoFormat>>,
     description: Option<String>,
     id: Option<String>,
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    extractor_key: Option<String>,
+    #[serde(default)]
+    subtitle_languages: Vec<String>,
+    #[serde(default)]
+    automatic_caption_languages: Vec<String>,
+    #[serde(default)]
+    chapters: Vec<InfoChapter>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OutputFilenamePreviewRequest {
+    info: InfoResponse,
+    #[serde(default)]
+    preset_key: Option<String>,
+    #[serde(default)]
+    video_format_id: Option<String>,
+    #[serde(default)]
+    audio_format_id: Option<String>,
+    #[serde(default)]
+    output_template: Option<String>,
+    #[serde(default)]
+    output_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct OutputFilenamePreview {
+    // The file name template that was used, without folder and extension.
+    template: String,
+    path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PlaylistEntry {
+    index: u32,
+    id: Option<String>,
+    url: String,
+    title: Option<String>,
+    uploader: Option<String>,
+    thumbnail: Option<String>,
+    duration: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PlaylistResponse {
+    id: Option<String>,
+    title: Option<String>,
+    uploader: Option<String>,
+    entries: Vec<PlaylistEntry>,
+}
+
+// `start` and `end` are 1-based playlist indices and both inclusive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PlaylistEnqueueOptions {
+    #[serde(default)]
+    start: Option<u32>,
+    #[serde(default)]
+    end: Option<u32>,
+    #[serde(default)]
+    include_playlist_in_filename: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PlaylistEnqueueResult {
+    batch_id: String,
+    playlist_title: Option<String>,
+    job_ids: Vec<String>,
+}
+
+// Title filters are case-insensitive substrings. Duration bounds only apply
+// to entries whose duration is listed by the extractor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct SubscriptionFilters {
+    title_includes: Option<String>,
+    title_excludes: Option<String>,
+    min_duration_seconds: Option<i64>,
+    max_duration_seconds: Option<i64>,
+    max_items_per_check: u32,
+}
+
+impl Default for SubscriptionFilters {
+    fn default() -> Self {
+        Self {
+            title_includes: None,
+            title_excludes: None,
+            min_duration_seconds: None,
+            max_duration_seconds: None,
+            max_items_per_check: SUBSCRIPTION_DEFAULT_MAX_ITEMS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Subscription {
+    id: String,
+    url: String,
+    title: Option<String>,
+    preset_key: String,
+    output_dir: Option<String>,
+    filters: SubscriptionFilters,
+    paused: bool,
+    last_checked_at: Option<u64>,
+    last_error: Option<String>,
+    created_at: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SubscriptionRequest {
+    url: String,
+    #[serde(default)]
+    preset_key: Option<String>,
+    #[serde(default)]
+    output_dir: Option<String>,
+    #[serde(default)]
+    filters: SubscriptionFilters,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SubscriptionCheckResult {
+    subscription_id: String,
+    found: usize,
+    added: usize,
+    skipped: usize,
+}
+
+// Host patterns match the host itself and all of its subdomains; a leading
+// `*.` or `.` is accepted and ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CookieProfile {
+    id: String,
+    name: String,
+    cookies_path: String,
+    host_patterns: Vec<String>,
+    created_at: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CookieProfileRequest {
+    #[serde(default)]
+    id: Option<String>,
+    name: String,
+    cookies_path: String,
+    #[serde(default)]
+    host_patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CookieFileSummary {
+    cookie_count: usize,
+    domains: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -179,6 +768,16 @@ struct HistoryEntry {
     platform: Option<String>,
     #[serde(default)]
     output_path: Option<String>,
+    #[serde(default)]
+    subtitle_paths: Vec<String>,
+    #[serde(default)]
+    removed_segments: Vec<SponsorBlockSegment>,
+    #[serde(default)]
+    parent_id: Option<String>,
+    // Archive file of the profile a full download was made with; None for
+    // clips and for entries recorded before archives were tracked.
+    #[serde(default)]
+    download_archive: Option<String>,
     created_at: u64,
     #[serde(default)]
     completed_at: Option<u64>,
@@ -206,7 +805,11 @@ struct InstalledYtDlpVersion {
 #[derive(Debug, Clone, Serialize)]
 struct QueueStatus {
     auto_start: bool,
+    paused: bool,
+    window_open: bool,
     worker_running: bool,
+    active_slots: usize,
+    max_slots: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -317,12 +920,16 @@ impl Default for LinkDumpServerRuntime {
 struct AddYoutubeLinkRequestBody {
     url: Option<String>,
     secret: Option<String>,
+    #[serde(default)]
+    preset: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct AddYoutubeLinksRequestBody {
     urls: Option<Vec<String>>,
     secret: Option<String>,
+    #[serde(default)]
+    preset: Option<String>,
 }
 
 #[derive(Debug)]
@@ -336,8 +943,114 @@ struct HttpRequest {
 struct DownloadRunResult {
     exit_code: i32,
     output_path: Option<String>,
+    error_lines: Vec<String>,
+    already_archived: bool,
+    subtitle_paths: Vec<String>,
+    removed_segments: Vec<SponsorBlockSegment>,
+    chapter_files: Vec<ChapterFile>,
+}
+
+impl DownloadRunResult {
+    fn output_paths(&self) -> Vec<String> {
+        self.output_path
+            .iter()
+            .cloned()
+            .chain(self.chapter_files.iter().map(|file| file.path.clone()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ChapterFile {
+    number: u32,
+    path: String,
+    chapter: Option<InfoChapter>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FailedJob {
+    id: String,
+    url: String,
+    // Flattened so `state`, `failed_at` and `cancelled_at` stay top-level.
+    #[serde(flatten)]
+    state: JobState,
+    job: DownloadJob,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum YtDlpFailure {
+    Retryable(&'static str),
+    Permanent(&'static str),
+    Unknown,
+}
+
+const YT_DLP_ERROR_LINES_LIMIT: usize = 50;
+const CLIP_RANGES_LIMIT: usize = 50;
+const LOCAL_CUT_SOURCE_MARKER: &str = ".source";
+const SUBTITLE_CONVERT_FORMATS: &[&str] = &["srt", "vtt"];
+const SUBTITLE_FILE_EXTENSIONS: &[&str] = &[
+    "srt", "vtt", "ass", "ssa", "ttml", "srv1", "srv2", "srv3", "json3", "lrc",
+];
+const SPONSORBLOCK_CATEGORIES: &[&str] = &[
+    "sponsor",
+    "intro",
+    "outro",
+    "selfpromo",
+    "preview",
+    "filler",
+    "interaction",
+    "music_offtopic",
+    "poi_highlight",
+    "chapter",
+    "all",
+    "default",
+];
+const YT_DLP_CHAPTERS_PREFIX: &str = "pinefetch-chapters ";
+const YT_DLP_SPONSORBLOCK_PREFIX: &str = "pinefetch-sponsorblock ";
+const AUDIO_TAG_MARKER: &str = "pinefetch-tag ";
+const YT_DLP_PROGRESS_PREFIX: &str = "pinefetch-progress ";
+const YT_DLP_PROGRESS_TEMPLATE: &str =
+    "download:pinefetch-progress %(info.vcodec)s %(info.acodec)s %(progress)j";
+const MAX_RETRY_ATTEMPTS_LIMIT: u32 = 10;
+const MAX_RETRY_DELAY_SECONDS_LIMIT: u64 = 24 * 60 * 60;
+
+const YT_DLP_PERMANENT_FAILURES: &[(&str, &str)] = &[
+    ("private video", "video is private"),
+    ("video unavailable", "video is unavailable"),
+    ("has been removed", "video has been removed"),
+    (
+        "account associated with this video has been terminated",
+        "video has been removed",
+    ),
+    ("members-only", "video is members-only"),
+    ("sign in to confirm your age", "video is age-restricted"),
+    ("unsupported url", "URL is not supported"),
+    (
+        "requested format is not available",
+        "requested format is not available",
+    ),
+];
+
+const YT_DLP_RETRYABLE_FAILURES: &[(&str, &str)] = &[
+    ("http error 429", "rate limited"),
+    ("too many requests", "rate limited"),
+    ("connection reset", "connection reset"),
+    ("connection aborted", "connection aborted"),
+    ("timed out", "connection timed out"),
+    (
+        "temporary failure in name resolution",
+        "network unavailable",
+    ),
+    ("incompleteread", "incomplete read"),
+    // yt-dlp's own wording when a fragment cannot be fetched after its retries.
+    ("not found, unable to continue", "fragment download failed"),
+    ("fragment retries", "fragment download failed"),
+    ("http error 500", "server error"),
+    ("http error 502", "server error"),
+    ("http error 503", "server error"),
+    ("http error 504", "server error"),
+];
+
 const FASTER_WHISPER_TRANSCRIBE_SNIPPET: &str = r#"
 import sys
 from pathlib import Path
@@ -372,70 +1085,358 @@ const LINK_DUMP_DEFAULT_PORT: u16 = 2255;
 const LINK_DUMP_MAX_BATCH_SIZE: usize = 500;
 const LINK_DUMP_MAX_BODY_BYTES: usize = 1024 * 1024;
 const DEFAULT_DOWNLOAD_PRESET_KEY: &str = "best";
+const DEFAULT_DOWNLOAD_ARCHIVE_FILE: &str = "download-archive.txt";
+const DEFAULT_DOWNLOAD_ARCHIVE_FORMAT: &str = "bestvideo+bestaudio/best";
+const MAX_CONCURRENT_DOWNLOADS_LIMIT: usize = 8;
+const RATE_LIMIT_REBALANCE_MIN_CHANGE_PERCENT: u64 = 25;
+const FINISHED_JOB_LIFECYCLES_LIMIT: usize = 200;
+const QUEUE_SCHEDULER_INTERVAL: Duration = Duration::from_secs(15);
+const SUBSCRIPTION_POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const SUBSCRIPTION_CHECK_INTERVAL_MILLIS: u64 = 60 * 60 * 1000;
+const SUBSCRIPTION_DEFAULT_MAX_ITEMS: u32 = 10;
+const SUBSCRIPTION_MAX_ITEMS_LIMIT: u32 = 100;
+
+const PRESET_KEY_MAX_LENGTH: usize = 40;
+const PRESET_KEY_RENAME_LIMIT: usize = 99;
+const PRESET_LABEL_MAX_LENGTH: usize = 80;
+const PRESET_FORMAT_MAX_LENGTH: usize = 200;
+const PRESET_BUNDLE_KIND: &str = "pinefetch-presets";
+const PRESET_BUNDLE_VERSION: u32 = 1;
+const DEFAULT_OUTPUT_TEMPLATE: &str = "%(title)s - %(uploader)s - %(id)s";
+const OUTPUT_TEMPLATE_MAX_LENGTH: usize = 200;
+// Fields `preview_output_filename` can fill from `load_info`, plus the ones
+// yt-dlp derives from the selected format.
+const OUTPUT_TEMPLATE_FIELDS: &[&str] = &[
+    "id",
+    "title",
+    "uploader",
+    "channel",
+    "upload_date",
+    "timestamp",
+    "duration",
+    "duration_string",
+    "extractor_key",
+    "format_id",
+    "resolution",
+    "height",
+    "width",
+    "fps",
+    "vcodec",
+    "acodec",
+    "language",
+];
+// Only these accept a date format such as `%(upload_date>%Y)s`.
+const OUTPUT_TEMPLATE_DATE_FIELDS: &[&str] = &["upload_date", "timestamp"];
+const PRESET_AUDIO_FORMATS: &[&str] = &[
+    "best", "aac", "alac", "flac", "m4a", "mp3", "opus", "vorbis", "wav",
+];
 
+// Seed rows for the `presets` table; they are rewritten on every start and
+// cannot be edited or deleted, only cloned.
 #[derive(Debug, Clone, Copy)]
-struct DownloadPreset {
+struct BuiltinPreset {
     key: &'static str,
+    label: &'static str,
     format: &'static str,
     extract_audio: bool,
     audio_format: Option<&'static str>,
     transcribe_text: bool,
     filename_suffix: Option<&'static str>,
+    subtitles: PresetSubtitles,
+    embed: EmbedOptions,
+    sponsorblock: PresetSponsorBlock,
 }
 
-const DOWNLOAD_PRESETS: &[DownloadPreset] = &[
-    DownloadPreset {
-        key: "best",
+#[derive(Debug, Clone, Copy)]
+struct PresetSponsorBlock {
+    mode: SponsorBlockMode,
+    categories: &'static [&'static str],
+}
+
+impl PresetSponsorBlock {
+    const OFF: Self = Self {
+        mode: SponsorBlockMode::Off,
+        categories: &[],
+    };
+
+    fn to_options(self) -> SponsorBlockOptions {
+        SponsorBlockOptions {
+            mode: self.mode,
+            categories: self
+                .categories
+                .iter()
+                .map(|category| category.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PresetSubtitles {
+    write_subs: bool,
+    write_auto_subs: bool,
+    languages: &'static [&'static str],
+    convert_format: Option<&'static str>,
+    embed: bool,
+}
+
+impl PresetSubtitles {
+    const NONE: Self = Self {
+        write_subs: false,
+        write_auto_subs: false,
+        languages: &[],
+        convert_format: None,
+        embed: false,
+    };
+
+    fn to_options(self) -> SubtitleOptions {
+        SubtitleOptions {
+            write_subs: self.write_subs,
+            write_auto_subs: self.write_auto_subs,
+            languages: self.languages.iter().map(|lang| lang.to_string()).collect(),
+            convert_format: self.convert_format.map(str::to_string),
+            embed: self.embed,
+        }
+    }
+}
+
+impl BuiltinPreset {
+    fn to_preset(self) -> Preset {
+        Preset {
+            key: self.key.to_string(),
+            label: self.label.to_string(),
+            format: self.format.to_string(),
+            extract_audio: self.extract_audio,
+            audio_format: self.audio_format.map(str::to_string),
+            transcribe_text: self.transcribe_text,
+            filename_suffix: self.filename_suffix.map(str::to_string),
+            subtitles: self.subtitles.to_options(),
+            embed: self.embed,
+            sponsorblock: self.sponsorblock.to_options(),
+            output_template: None,
+            builtin: true,
+        }
+    }
+}
+
+const BUILTIN_PRESETS: &[BuiltinPreset] = &[
+    BuiltinPreset {
+        key: "best",
+        label: "Best (bestvideo+bestaudio)",
+        format: "bestvideo+bestaudio/best",
+        extract_audio: false,
+        audio_format: None,
+        transcribe_text: false,
+        filename_suffix: Some("_best"),
+        subtitles: PresetSubtitles::NONE,
+        embed: EmbedOptions {
+            metadata: true,
+            thumbnail: false,
+            chapters: true,
+        },
+        sponsorblock: PresetSponsorBlock::OFF,
+    },
+    BuiltinPreset {
+        key: "best_subs",
+        label: "Best + subtitles (en, srt)",
+        format: "bestvideo+bestaudio/best",
+        extract_audio: false,
+        audio_format: None,
+        transcribe_text: false,
+        filename_suffix: Some("_best"),
+        subtitles: PresetSubtitles {
+            write_subs: true,
+            write_auto_subs: true,
+            languages: &["en.*"],
+            convert_format: Some("srt"),
+            embed: true,
+        },
+        embed: EmbedOptions {
+            metadata: true,
+            thumbnail: false,
+            chapters: true,
+        },
+        sponsorblock: PresetSponsorBlock::OFF,
+    },
+    BuiltinPreset {
+        key: "best_sponsorblock",
+        label: "Best without sponsor segments",
         format: "bestvideo+bestaudio/best",
         extract_audio: false,
         audio_format: None,
         transcribe_text: false,
         filename_suffix: Some("_best"),
+        subtitles: PresetSubtitles::NONE,
+        embed: EmbedOptions {
+            metadata: true,
+            thumbnail: false,
+            chapters: true,
+        },
+        sponsorblock: PresetSponsorBlock {
+            mode: SponsorBlockMode::Remove,
+            categories: &["sponsor", "selfpromo", "interaction"],
+        },
     },
-    DownloadPreset {
+    BuiltinPreset {
         key: "1080",
+        label: "Max 1080p",
         format: "bv*[height<=1080]+ba/b[height<=1080]",
         extract_audio: false,
         audio_format: None,
         transcribe_text: false,
         filename_suffix: Some("__max"),
+        subtitles: PresetSubtitles::NONE,
+        embed: EmbedOptions {
+            metadata: true,
+            thumbnail: false,
+            chapters: true,
+        },
+        sponsorblock: PresetSponsorBlock::OFF,
     },
-    DownloadPreset {
+    BuiltinPreset {
         key: "audio_mp3",
+        label: "Audio only (mp3)",
         format: "ba/b",
         extract_audio: true,
         audio_format: Some("mp3"),
         transcribe_text: false,
         filename_suffix: None,
+        subtitles: PresetSubtitles::NONE,
+        embed: EmbedOptions {
+            metadata: true,
+            thumbnail: true,
+            chapters: true,
+        },
+        sponsorblock: PresetSponsorBlock::OFF,
     },
-    DownloadPreset {
+    BuiltinPreset {
         key: "audio_opus",
+        label: "Audio only (opus)",
         format: "ba/b",
         extract_audio: true,
         audio_format: Some("opus"),
         transcribe_text: false,
         filename_suffix: None,
+        subtitles: PresetSubtitles::NONE,
+        embed: EmbedOptions {
+            metadata: true,
+            thumbnail: true,
+            chapters: true,
+        },
+        sponsorblock: PresetSponsorBlock::OFF,
     },
-    DownloadPreset {
+    BuiltinPreset {
         key: "text",
+        label: "Text (fast-whisper)",
         format: "ba/b",
         extract_audio: true,
         audio_format: Some("mp3"),
         transcribe_text: true,
         filename_suffix: None,
+        subtitles: PresetSubtitles::NONE,
+        embed: EmbedOptions::NONE,
+        sponsorblock: PresetSponsorBlock::OFF,
     },
 ];
 
+// A row of the `presets` table. Requests copy these fields, so editing or
+// deleting a preset never changes jobs that are already queued.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Preset {
+    key: String,
+    #[serde(default)]
+    label: String,
+    format: String,
+    #[serde(default)]
+    extract_audio: bool,
+    #[serde(default)]
+    audio_format: Option<String>,
+    #[serde(default)]
+    transcribe_text: bool,
+    #[serde(default)]
+    filename_suffix: Option<String>,
+    #[serde(default)]
+    subtitles: SubtitleOptions,
+    #[serde(default)]
+    embed: EmbedOptions,
+    #[serde(default)]
+    sponsorblock: SponsorBlockOptions,
+    #[serde(default)]
+    output_template: Option<String>,
+    #[serde(default)]
+    builtin: bool,
+}
+
+// Entries stay raw JSON so one malformed preset is rejected on its own
+// instead of failing the whole bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PresetBundle {
+    kind: String,
+    version: u32,
+    #[serde(default)]
+    exported_at: Option<u64>,
+    #[serde(default)]
+    presets: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PresetConflictPolicy {
+    // Existing presets win; the imported one is reported as rejected.
+    #[default]
+    Skip,
+    Overwrite,
+    // Imports under the next free key such as `podcast-2`.
+    Rename,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+struct PresetImportReport {
+    added: Vec<String>,
+    updated: Vec<String>,
+    rejected: Vec<RejectedPreset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct RejectedPreset {
+    key: String,
+    reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PresetExportResult {
+    path: String,
+    count: usize,
+}
+
+struct RunningJob {
+    url: String,
+    child: Option<Arc<Mutex<Child>>>,
+    // Share of the shared bandwidth cap passed to yt-dlp; None when the job has
+    // its own limit or no cap applied.
+    shared_rate_limit_kib: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseRequest {
+    Job,
+    Queue,
+    Rebalance,
+}
+
 struct AppState {
     config: Mutex<AppConfig>,
     db: Mutex<Connection>,
     link_dump_server: Mutex<LinkDumpServerRuntime>,
     queue: Mutex<VecDeque<DownloadJob>>,
     queue_auto_start: Mutex<bool>,
-    worker_running: Mutex<bool>,
-    current_job_id: Mutex<Option<String>>,
-    current_child: Mutex<Option<Arc<Mutex<Child>>>>,
-    cancel_requested: Mutex<Option<String>>,
+    queue_paused: Mutex<bool>,
+    active_workers: Mutex<usize>,
+    running_jobs: Mutex<HashMap<String, RunningJob>>,
+    cancel_requested: Mutex<HashSet<String>>,
+    pause_requested: Mutex<HashMap<String, PauseRequest>>,
+    job_lifecycles: Mutex<JobLifecycles>,
+    subscription_checks: Mutex<HashSet<String>>,
 }
 
 impl AppState {
@@ -446,34 +1447,148 @@ impl AppState {
             link_dump_server: Mutex::new(LinkDumpServerRuntime::default()),
             queue: Mutex::new(VecDeque::new()),
             queue_auto_start: Mutex::new(true),
-            worker_running: Mutex::new(false),
-            current_job_id: Mutex::new(None),
-            current_child: Mutex::new(None),
-            cancel_requested: Mutex::new(None),
+            queue_paused: Mutex::new(false),
+            active_workers: Mutex::new(0),
+            running_jobs: Mutex::new(HashMap::new()),
+            cancel_requested: Mutex::new(HashSet::new()),
+            pause_requested: Mutex::new(HashMap::new()),
+            job_lifecycles: Mutex::new(JobLifecycles::default()),
+            subscription_checks: Mutex::new(HashSet::new()),
         }
     }
 }
 
-fn download_preset_for_key(preset_key: Option<&str>) -> &'static DownloadPreset {
+fn download_preset_for_key(state: &AppState, preset_key: Option<&str>) -> Result<Preset, String> {
     let key = preset_key
         .map(str::trim)
         .filter(|key| !key.is_empty())
         .unwrap_or(DEFAULT_DOWNLOAD_PRESET_KEY);
 
-    DOWNLOAD_PRESETS
-        .iter()
-        .find(|preset| preset.key == key)
-        .unwrap_or(&DOWNLOAD_PRESETS[0])
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    get_preset_from_conn(&conn, key)
+        .map_err(|e| format!("Preset read failed: {e}"))?
+        .ok_or_else(|| format!("Unknown preset: {key}"))
+}
+
+// Only the stored config falls back to the default preset, for example after
+// the selected preset was deleted.
+fn normalize_selected_preset_key_in_conn(
+    conn: &Connection,
+    preset_key: Option<&str>,
+) -> rusqlite::Result<String> {
+    let key = preset_key.map(str::trim).filter(|key| !key.is_empty());
+    match key {
+        Some(key) if get_preset_from_conn(conn, key)?.is_some() => Ok(key.to_string()),
+        _ => Ok(DEFAULT_DOWNLOAD_PRESET_KEY.to_string()),
+    }
 }
 
-fn normalize_download_preset_key(preset_key: Option<&str>) -> String {
-    download_preset_for_key(preset_key).key.to_string()
+fn normalize_selected_preset_key(
+    state: &AppState,
+    preset_key: Option<&str>,
+) -> Result<String, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    normalize_selected_preset_key_in_conn(&conn, preset_key)
+        .map_err(|e| format!("Preset read failed: {e}"))
+}
+
+fn normalize_preset(preset: Preset) -> Result<Preset, String> {
+    let key = preset.key.trim().to_string();
+    if key.is_empty()
+        || key.len() > PRESET_KEY_MAX_LENGTH
+        || !key.chars().all(|character| {
+            character.is_ascii_lowercase()
+                || character.is_ascii_digit()
+                || character == '_'
+                || character == '-'
+        })
+    {
+        return Err(format!(
+            "Preset key must be 1-{PRESET_KEY_MAX_LENGTH} lowercase letters, digits, '-' or '_'"
+        ));
+    }
+
+    let label = preset.label.trim();
+    let label = if label.is_empty() {
+        key.as_str()
+    } else {
+        label
+    };
+    if label.chars().count() > PRESET_LABEL_MAX_LENGTH {
+        return Err(format!(
+            "Preset label must be at most {PRESET_LABEL_MAX_LENGTH} characters"
+        ));
+    }
+
+    let format = preset.format.trim();
+    if format.is_empty()
+        || format.len() > PRESET_FORMAT_MAX_LENGTH
+        || format.chars().any(char::is_control)
+    {
+        return Err("Preset format must be a yt-dlp format selector such as ba/b".to_string());
+    }
+
+    let audio_format = match trim_optional_string(preset.audio_format) {
+        Some(_) if !preset.extract_audio => None,
+        Some(audio_format) if PRESET_AUDIO_FORMATS.contains(&audio_format.as_str()) => {
+            Some(audio_format)
+        }
+        Some(audio_format) => {
+            return Err(format!("Unsupported audio format: {audio_format}"));
+        }
+        None => None,
+    };
+    if preset.transcribe_text && !preset.extract_audio {
+        return Err("Transcribing presets must extract audio".to_string());
+    }
+
+    let filename_suffix = match trim_optional_string(preset.filename_suffix) {
+        Some(raw) => Some(normalize_filename_suffix(Some(&raw)).ok_or_else(|| {
+            "Filename suffix may only contain letters, digits, '-' and '_' (max 32)".to_string()
+        })?),
+        None => None,
+    };
+
+    Ok(Preset {
+        label: label.to_string(),
+        format: format.to_string(),
+        audio_format,
+        filename_suffix,
+        subtitles: normalize_subtitle_options(preset.subtitles)?,
+        sponsorblock: normalize_sponsorblock_options(preset.sponsorblock)?,
+        output_template: normalize_output_template(preset.output_template.as_deref())?,
+        builtin: false,
+        key,
+        ..preset
+    })
 }
 
 fn normalize_app_config(mut config: AppConfig) -> AppConfig {
-    config.selected_preset_key = Some(normalize_download_preset_key(
-        config.selected_preset_key.as_deref(),
-    ));
+    config.max_concurrent_downloads = config
+        .max_concurrent_downloads
+        .clamp(1, MAX_CONCURRENT_DOWNLOADS_LIMIT);
+    config.retry_max_attempts = config.retry_max_attempts.min(MAX_RETRY_ATTEMPTS_LIMIT);
+    config.retry_base_delay_seconds = config
+        .retry_base_delay_seconds
+        .clamp(1, MAX_RETRY_DELAY_SECONDS_LIMIT);
+    config.retry_max_delay_seconds = config.retry_max_delay_seconds.clamp(
+        config.retry_base_delay_seconds,
+        MAX_RETRY_DELAY_SECONDS_LIMIT,
+    );
+    if parse_clock_minutes(&config.download_window_start).is_none() {
+        config.download_window_start = default_download_window_start();
+    }
+    if parse_clock_minutes(&config.download_window_end).is_none() {
+        config.download_window_end = default_download_window_end();
+    }
+    config.rate_limit_kib = config.rate_limit_kib.filter(|limit| *limit > 0);
+    config.download_window_rate_limit_kib = config
+        .download_window_rate_limit_kib
+        .filter(|limit| *limit > 0);
+    config.throttled_rate_kib = config.throttled_rate_kib.filter(|limit| *limit > 0);
+    config.output_template = normalize_output_template(config.output_template.as_deref())
+        .ok()
+        .flatten();
     config
 }
 
@@ -484,13 +1599,24 @@ fn get_config(state: State<AppState>) -> Result<AppConfig, String> {
 }
 
 #[tauri::command]
-fn set_config(state: State<AppState>, config: AppConfig) -> Result<(), String> {
-    let config = normalize_app_config(config);
+fn set_config(app: AppHandle, state: State<AppState>, config: AppConfig) -> Result<(), String> {
+    // Stored configs drop a broken template; a new one is reported instead.
+    normalize_output_template(config.output_template.as_deref())?;
+    let mut config = normalize_app_config(config);
+    config.selected_preset_key = Some(normalize_selected_preset_key(
+        state.inner(),
+        config.selected_preset_key.as_deref(),
+    )?);
     save_config_to_db(state.inner(), &config)?;
     {
         let mut cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
         *cfg = config.clone();
     }
+
+    // A raised concurrency limit should pick up waiting jobs right away.
+    if is_queue_auto_start_enabled(state.inner())? {
+        ensure_worker(&app, state.inner())?;
+    }
     Ok(())
 }
 
@@ -499,7 +1625,7 @@ fn set_selected_preset_key(
     state: State<AppState>,
     preset_key: String,
 ) -> Result<AppConfig, String> {
-    let selected_preset_key = normalize_download_preset_key(Some(&preset_key));
+    let selected_preset_key = download_preset_for_key(state.inner(), Some(&preset_key))?.key;
     let next_config = {
         let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
         let mut cfg = cfg.clone();
@@ -515,6 +1641,264 @@ fn set_selected_preset_key(
     Ok(next_config)
 }
 
+#[tauri::command]
+fn list_presets(state: State<AppState>) -> Result<Vec<Preset>, String> {
+    list_presets_from_db(state.inner())
+}
+
+#[tauri::command]
+fn create_preset(app: AppHandle, state: State<AppState>, preset: Preset) -> Result<Preset, String> {
+    let preset = save_user_preset_in_db(state.inner(), preset, false)?;
+    emit_presets(&app, state.inner());
+    Ok(preset)
+}
+
+#[tauri::command]
+fn update_preset(app: AppHandle, state: State<AppState>, preset: Preset) -> Result<Preset, String> {
+    let preset = save_user_preset_in_db(state.inner(), preset, true)?;
+    emit_presets(&app, state.inner());
+    Ok(preset)
+}
+
+#[tauri::command]
+fn clone_preset(
+    app: AppHandle,
+    state: State<AppState>,
+    key: String,
+    new_key: String,
+    label: Option<String>,
+) -> Result<Preset, String> {
+    let source = download_preset_for_key(state.inner(), Some(&key))?;
+    let preset = Preset {
+        key: new_key,
+        label: label.unwrap_or_else(|| format!("{} (copy)", source.label)),
+        ..source
+    };
+    let preset = save_user_preset_in_db(state.inner(), preset, false)?;
+    emit_presets(&app, state.inner());
+    Ok(preset)
+}
+
+#[tauri::command]
+fn delete_preset(app: AppHandle, state: State<AppState>, key: String) -> Result<AppConfig, String> {
+    delete_user_preset_in_db(state.inner(), &key)?;
+    let next_config = {
+        let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
+        let mut cfg = cfg.clone();
+        if cfg.selected_preset_key.as_deref() == Some(key.as_str()) {
+            cfg.selected_preset_key = Some(DEFAULT_DOWNLOAD_PRESET_KEY.to_string());
+        }
+        cfg
+    };
+    save_config_to_db(state.inner(), &next_config)?;
+    {
+        let mut cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
+        *cfg = next_config.clone();
+    }
+    emit_presets(&app, state.inner());
+    Ok(next_config)
+}
+
+#[tauri::command]
+async fn pick_preset_bundle_file() -> Result<Option<String>, String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    tauri::api::dialog::FileDialogBuilder::new()
+        .add_filter("Preset bundle", &["json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path.map(|p| p.to_string_lossy().to_string()));
+        });
+    tauri::async_runtime::spawn_blocking(move || rx.recv())
+        .await
+        .map_err(|_| "Dialog task failed".to_string())?
+        .map_err(|_| "Dialog closed".to_string())
+}
+
+#[tauri::command]
+async fn pick_preset_export_path() -> Result<Option<String>, String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    tauri::api::dialog::FileDialogBuilder::new()
+        .add_filter("Preset bundle", &["json"])
+        .set_file_name("pinefetch-presets.json")
+        .save_file(move |path| {
+            let _ = tx.send(path.map(|p| p.to_string_lossy().to_string()));
+        });
+    tauri::async_runtime::spawn_blocking(move || rx.recv())
+        .await
+        .map_err(|_| "Dialog task failed".to_string())?
+        .map_err(|_| "Dialog closed".to_string())
+}
+
+// Without `keys` every user preset is exported; built-ins ship with the app.
+#[tauri::command]
+fn export_presets(
+    state: State<AppState>,
+    path: String,
+    keys: Option<Vec<String>>,
+) -> Result<PresetExportResult, String> {
+    let bundle = build_preset_bundle(state.inner(), keys.as_deref())?;
+    let count = bundle.presets.len();
+    let json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Preset bundle encode failed: {e}"))?;
+    fs::write(&path, format!("{json}\n"))
+        .map_err(|e| format!("Preset bundle write failed: {e}"))?;
+    Ok(PresetExportResult { path, count })
+}
+
+#[tauri::command]
+fn import_presets(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+    conflict: Option<PresetConflictPolicy>,
+) -> Result<PresetImportReport, String> {
+    let raw =
+        fs::read_to_string(&path).map_err(|e| format!("Preset bundle could not be read: {e}"))?;
+    let report = import_preset_bundle(state.inner(), &raw, conflict.unwrap_or_default())?;
+    emit_presets(&app, state.inner());
+    Ok(report)
+}
+
+fn build_preset_bundle(state: &AppState, keys: Option<&[String]>) -> Result<PresetBundle, String> {
+    let user_presets = list_presets_from_db(state)?
+        .into_iter()
+        .filter(|preset| !preset.builtin)
+        .collect::<Vec<_>>();
+    let presets = match keys {
+        None => user_presets,
+        Some(keys) => keys
+            .iter()
+            .map(|key| {
+                user_presets
+                    .iter()
+                    .find(|preset| &preset.key == key)
+                    .cloned()
+                    .ok_or_else(|| format!("Unknown user preset: {key}"))
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+    };
+
+    Ok(PresetBundle {
+        kind: PRESET_BUNDLE_KIND.to_string(),
+        version: PRESET_BUNDLE_VERSION,
+        exported_at: Some(current_timestamp_millis()),
+        presets: presets
+            .into_iter()
+            .map(|preset| {
+                let mut value = serde_json::to_value(preset)
+                    .map_err(|e| format!("Preset bundle encode failed: {e}"))?;
+                if let Some(object) = value.as_object_mut() {
+                    object.remove("builtin");
+                }
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>, String>>()?,
+    })
+}
+
+fn import_preset_bundle(
+    state: &AppState,
+    raw: &str,
+    conflict: PresetConflictPolicy,
+) -> Result<PresetImportReport, String> {
+    let bundle = serde_json::from_str::<PresetBundle>(raw.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Not a PineFetch preset bundle: {e}"))?;
+    if bundle.kind != PRESET_BUNDLE_KIND {
+        return Err("Not a PineFetch preset bundle".to_string());
+    }
+    if bundle.version == 0 || bundle.version > PRESET_BUNDLE_VERSION {
+        return Err(format!(
+            "Unsupported preset bundle version {} (expected {PRESET_BUNDLE_VERSION})",
+            bundle.version
+        ));
+    }
+
+    let mut report = PresetImportReport::default();
+    let mut seen_keys = HashSet::new();
+    for (index, value) in bundle.presets.into_iter().enumerate() {
+        let label_key = value
+            .get("key")
+            .and_then(|key| key.as_str())
+            .map(|key| key.trim().to_string())
+            .unwrap_or_else(|| format!("#{}", index + 1));
+        let mut reject = |reason: String| {
+            report.rejected.push(RejectedPreset {
+                key: label_key.clone(),
+                reason,
+            });
+        };
+
+        let preset = match serde_json::from_value::<Preset>(value).map_err(|e| e.to_string()) {
+            Ok(preset) => match normalize_preset(preset) {
+                Ok(preset) => preset,
+                Err(err) => {
+                    reject(err);
+                    continue;
+                }
+            },
+            Err(err) => {
+                reject(format!("Invalid preset: {err}"));
+                continue;
+            }
+        };
+        if !seen_keys.insert(preset.key.clone()) {
+            reject("Duplicate key in bundle".to_string());
+            continue;
+        }
+
+        let existing = {
+            let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+            get_preset_from_conn(&conn, &preset.key)
+                .map_err(|e| format!("Preset read failed: {e}"))?
+        };
+        let result = match (existing, conflict) {
+            (None, _) => save_user_preset_in_db(state, preset, false)
+                .map(|preset| report.added.push(preset.key)),
+            (Some(_), PresetConflictPolicy::Rename) => next_free_preset_key(state, &preset.key)
+                .and_then(|key| save_user_preset_in_db(state, Preset { key, ..preset }, false))
+                .map(|preset| report.added.push(preset.key)),
+            (Some(existing), _) if existing.builtin => {
+                Err(format!("Built-in preset {} is read-only", existing.key))
+            }
+            (Some(_), PresetConflictPolicy::Overwrite) => {
+                save_user_preset_in_db(state, preset, true)
+                    .map(|preset| report.updated.push(preset.key))
+            }
+            (Some(existing), PresetConflictPolicy::Skip) => {
+                Err(format!("Preset {} already exists", existing.key))
+            }
+        };
+        if let Err(err) = result {
+            reject(err);
+        }
+    }
+    Ok(report)
+}
+
+// Long keys are shortened so the numbered copy still fits PRESET_KEY_MAX_LENGTH.
+fn next_free_preset_key(state: &AppState, key: &str) -> Result<String, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    for number in 2..=PRESET_KEY_RENAME_LIMIT {
+        let suffix = format!("-{number}");
+        let base_length = PRESET_KEY_MAX_LENGTH.saturating_sub(suffix.len());
+        let candidate = format!("{}{suffix}", &key[..key.len().min(base_length)]);
+        let taken = get_preset_from_conn(&conn, &candidate)
+            .map_err(|e| format!("Preset read failed: {e}"))?
+            .is_some();
+        if !taken {
+            return Ok(candidate);
+        }
+    }
+    Err(format!(
+        "No free key for a copy of {key}; {PRESET_KEY_RENAME_LIMIT} copies already exist"
+    ))
+}
+
+fn emit_presets(app: &AppHandle, state: &AppState) {
+    if let Ok(presets) = list_presets_from_db(state) {
+        let _ = app.emit_all("presets:update", presets);
+    }
+}
+
 #[tauri::command]
 fn cache_last_download_url(state: State<AppState>, url: String) -> Result<(), String> {
     let trimmed = url.trim();
@@ -612,8 +1996,9 @@ async fn load_info(
     }
     let yt_dlp = resolve_yt_dlp(&app, &state)?;
     let deno = resolve_deno_executable(&app);
+    let cookies = resolve_cookies_path(&state, &url);
 
-    tauri::async_runtime::spawn_blocking(move || load_info_with_yt_dlp(yt_dlp, deno, url))
+    tauri::async_runtime::spawn_blocking(move || load_info_with_yt_dlp(yt_dlp, deno, cookies, url))
         .await
         .map_err(|e| format!("Info task failed: {e}"))?
 }
@@ -621,6 +2006,7 @@ async fn load_info(
 fn load_info_with_yt_dlp(
     yt_dlp: String,
     deno: Option<String>,
+    cookies: Option<String>,
     url: String,
 ) -> Result<InfoResponse, String> {
     let mut command = Command::new(&yt_dlp);
@@ -629,6 +2015,11 @@ fn load_info_with_yt_dlp(
         command.arg("--js-runtimes");
         command.arg(format!("deno:{deno}"));
     }
+    let cookies = cookies.as_deref().map(CookiesCopy::create).transpose()?;
+    if let Some(cookies) = cookies.as_ref() {
+        command.arg("--cookies");
+        command.arg(cookies.path());
+    }
 
     let output = command
         .arg(&url)
@@ -645,28 +2036,10 @@ fn load_info_with_yt_dlp(
     let value: serde_json::Value =
         serde_json::from_str(&raw).map_err(|e| format!("Invalid JSON from yt-dlp: {e}"))?;
 
-    let formats = value.get("formats").and_then(|v| v.as_array()).map(|arr| {
-        arr.iter()
-            .map(|f| InfoFormat {
-                format_id: f
-                    .get("format_id")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string()),
-                ext: f.get("ext").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                vcodec: f
-                    .get("vcodec")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string()),
-                acodec: f
-                    .get("acodec")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string()),
-                height: f.get("height").and_then(|v| v.as_i64()),
-                width: f.get("width").and_then(|v| v.as_i64()),
-                fps: f.get("fps").and_then(|v| v.as_f64()),
-            })
-            .collect::<Vec<_>>()
-    });
+    let formats = value
+        .get("formats")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().map(parse_info_format).collect::<Vec<_>>());
 
     Ok(InfoResponse {
         title: value
@@ -701,29 +2074,402 @@ fn load_info_with_yt_dlp(
             .get("id")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string()),
+        channel: value
+            .get("channel")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        extractor_key: value
+            .get("extractor_key")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        subtitle_languages: json_object_keys(&value, "subtitles"),
+        automatic_caption_languages: json_object_keys(&value, "automatic_captions"),
+        chapters: value
+            .get("chapters")
+            .map(parse_info_chapters)
+            .unwrap_or_default(),
     })
 }
 
-fn json_value_to_i64(value: &serde_json::Value) -> Option<i64> {
-    value
-        .as_i64()
-        .or_else(|| value.as_u64().and_then(|value| i64::try_from(value).ok()))
-        .or_else(|| {
-            value
-                .as_f64()
-                .filter(|value| value.is_finite())
-                .map(|value| value.trunc() as i64)
-        })
-        .or_else(|| value.as_str()?.trim().parse::<i64>().ok())
-}
-
+// Renders the template against the info already loaded in the UI, so the
+// preview needs no second extraction. The template is resolved like a
+// download: explicit, then the preset's, then the global one.
 #[tauri::command]
-fn get_yt_dlp_installed_version(
+async fn preview_output_filename(
     app: AppHandle,
-    state: State<AppState>,
-    path: Option<String>,
-) -> Result<InstalledYtDlpVersion, String> {
-    let yt_dlp = resolve_yt_dlp_for_version(&app, &state, path)?;
+    state: State<'_, AppState>,
+    request: OutputFilenamePreviewRequest,
+) -> Result<OutputFilenamePreview, String> {
+    let OutputFilenamePreviewRequest {
+        info,
+        preset_key,
+        video_format_id,
+        audio_format_id,
+        output_template,
+        output_dir,
+    } = request;
+    let preset = download_preset_for_key(&state, preset_key.as_deref())?;
+    let template = match normalize_output_template(output_template.as_deref())? {
+        Some(template) => template,
+        None => match preset.output_template.clone() {
+            Some(template) => template,
+            None => configured_output_template(&state)?,
+        },
+    };
+    let output_dir = resolve_output_dir(&state, output_dir)?;
+    let full_template =
+        build_output_template(&output_dir, &template, preset.filename_suffix.as_deref());
+    let yt_dlp = resolve_yt_dlp(&app, &state)?;
+    let format = resolve_picked_format(
+        video_format_id.as_deref(),
+        audio_format_id.as_deref(),
+        &preset.format,
+    )?;
+
+    let path = tauri::async_runtime::spawn_blocking(move || {
+        render_output_filename_with_yt_dlp(&yt_dlp, &info, &format, &full_template)
+    })
+    .await
+    .map_err(|e| format!("Preview task failed: {e}"))??;
+    let path = match preset.audio_format.as_deref() {
+        Some(audio_format) if preset.extract_audio => {
+            extracted_audio_output_path(&path, audio_format)
+        }
+        _ => path,
+    };
+    Ok(OutputFilenamePreview { template, path })
+}
+
+// yt-dlp's audio extraction names the file after the container, not the
+// codec: vorbis lands in .ogg and aac/alac in .m4a. "best" keeps the source.
+fn extracted_audio_output_path(path: &str, audio_format: &str) -> String {
+    let extension = match audio_format {
+        "aac" | "alac" | "m4a" => "m4a",
+        "vorbis" => "ogg",
+        "flac" | "mp3" | "opus" | "wav" => audio_format,
+        _ => return path.to_string(),
+    };
+    Path::new(path)
+        .with_extension(extension)
+        .to_string_lossy()
+        .to_string()
+}
+
+fn render_output_filename_with_yt_dlp(
+    yt_dlp: &str,
+    info: &InfoResponse,
+    format: &str,
+    output_template: &str,
+) -> Result<String, String> {
+    let info_path =
+        std::env::temp_dir().join(format!("pinefetch-preview-{}.info.json", Uuid::new_v4()));
+    let info_json = serde_json::to_string(&build_preview_info_json(info))
+        .map_err(|e| format!("Preview info encode failed: {e}"))?;
+    fs::write(&info_path, info_json).map_err(|e| format!("Preview info write failed: {e}"))?;
+
+    let output = Command::new(yt_dlp)
+        .args(["--simulate", "--no-warnings", "--print", "filename", "-f"])
+        .arg(format)
+        .arg("-o")
+        .arg(output_template)
+        .arg("--load-info-json")
+        .arg(&info_path)
+        .output();
+    let _ = fs::remove_file(&info_path);
+    let output = output.map_err(|e| format!("Failed to run yt-dlp: {e}"))?;
+
+    if !output.status.success() {
+        let code = output.status.code().unwrap_or(-1);
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        return Err(format!("yt-dlp exited {code}: {stderr}"));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .rev()
+        .find_map(parse_yt_dlp_filepath)
+        .ok_or_else(|| "yt-dlp did not print a file name".to_string())
+}
+
+// yt-dlp wants a URL per format even when it only simulates, so formats get a
+// placeholder. `webpage_url` is left out on purpose: with it, yt-dlp would
+// fall back to extracting the page again if the info fails to process.
+fn build_preview_info_json(info: &InfoResponse) -> serde_json::Value {
+    const PLACEHOLDER_URL: &str = "https://example.invalid/pinefetch-preview";
+    let formats = info
+        .formats
+        .iter()
+        .flatten()
+        .filter(|format| format.format_id.is_some())
+        .filter_map(|format| {
+            let mut value = serde_json::to_value(format).ok()?;
+            value["url"] = json!(PLACEHOLDER_URL);
+            Some(without_null_fields(value))
+        })
+        .collect::<Vec<_>>();
+    let mut value = json!({
+        "_type": "video",
+        "id": info.id.as_deref().unwrap_or("preview"),
+        "title": info.title,
+        "uploader": info.uploader,
+        "channel": info.channel,
+        "upload_date": info.upload_date,
+        "timestamp": info.timestamp,
+        "duration": info.duration,
+        "extractor": info
+            .extractor_key
+            .as_deref()
+            .map(str::to_lowercase)
+            .unwrap_or_else(|| "generic".to_string()),
+        "extractor_key": info.extractor_key.as_deref().unwrap_or("Generic"),
+    });
+    if formats.is_empty() {
+        value["url"] = json!(PLACEHOLDER_URL);
+        value["ext"] = json!("mp4");
+    } else {
+        value["formats"] = json!(formats);
+    }
+    without_null_fields(value)
+}
+
+fn without_null_fields(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some(object) = value.as_object_mut() {
+        object.retain(|_, field| !field.is_null());
+    }
+    value
+}
+
+fn parse_info_chapters(value: &serde_json::Value) -> Vec<InfoChapter> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|chapter| {
+            Some(InfoChapter {
+                title: chapter
+                    .get("title")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                start_time: chapter.get("start_time")?.as_f64()?,
+                end_time: chapter.get("end_time")?.as_f64()?,
+            })
+        })
+        .collect()
+}
+
+fn json_object_keys(value: &serde_json::Value, key: &str) -> Vec<String> {
+    let mut keys = value
+        .get(key)
+        .and_then(|v| v.as_object())
+        .map(|object| object.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    keys.sort();
+    keys
+}
+
+#[tauri::command]
+async fn load_playlist(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    url: String,
+) -> Result<PlaylistResponse, String> {
+    if !is_valid_url(&url) {
+        return Err("URL must start with http:// or https://".to_string());
+    }
+    let yt_dlp = resolve_yt_dlp(&app, &state)?;
+    let deno = resolve_deno_executable(&app);
+    let cookies = resolve_cookies_path(&state, &url);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        load_playlist_with_yt_dlp(yt_dlp, deno, cookies, url, None)
+    })
+    .await
+    .map_err(|e| format!("Playlist task failed: {e}"))?
+}
+
+fn load_playlist_with_yt_dlp(
+    yt_dlp: String,
+    deno: Option<String>,
+    cookies: Option<String>,
+    url: String,
+    playlist_end: Option<u32>,
+) -> Result<PlaylistResponse, String> {
+    let mut command = Command::new(&yt_dlp);
+    command.args(["--flat-playlist", "-J", "--yes-playlist", "--no-warnings"]);
+    if let Some(playlist_end) = playlist_end {
+        command.arg("--playlist-end");
+        command.arg(playlist_end.to_string());
+    }
+    if let Some(deno) = deno {
+        command.arg("--js-runtimes");
+        command.arg(format!("deno:{deno}"));
+    }
+    let cookies = cookies.as_deref().map(CookiesCopy::create).transpose()?;
+    if let Some(cookies) = cookies.as_ref() {
+        command.arg("--cookies");
+        command.arg(cookies.path());
+    }
+
+    let output = command
+        .arg(&url)
+        .output()
+        .map_err(|e| format!("Failed to run yt-dlp: {e}"))?;
+
+    if !output.status.success() {
+        let code = output.status.code().unwrap_or(-1);
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        return Err(format!("yt-dlp exited {code}: {stderr}"));
+    }
+
+    let raw = String::from_utf8_lossy(&output.stdout).to_string();
+    let value: serde_json::Value =
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid JSON from yt-dlp: {e}"))?;
+    parse_playlist_response(&value)
+}
+
+fn parse_playlist_response(value: &serde_json::Value) -> Result<PlaylistResponse, String> {
+    let Some(raw_entries) = value.get("entries").and_then(|v| v.as_array()) else {
+        return Err("URL is not a playlist or channel".to_string());
+    };
+
+    let mut entries = Vec::new();
+    collect_playlist_entries(raw_entries, &mut entries);
+
+    Ok(PlaylistResponse {
+        id: json_string_field(value, "id"),
+        title: json_string_field(value, "title"),
+        uploader: json_string_field(value, "uploader")
+            .or_else(|| json_string_field(value, "channel")),
+        entries,
+    })
+}
+
+// Channels list their tabs as nested playlists, so their entries are flattened
+// into one continuous index.
+fn collect_playlist_entries(raw_entries: &[serde_json::Value], entries: &mut Vec<PlaylistEntry>) {
+    for raw in raw_entries {
+        if let Some(nested) = raw.get("entries").and_then(|v| v.as_array()) {
+            collect_playlist_entries(nested, entries);
+            continue;
+        }
+        let Some(url) = playlist_entry_url(raw) else {
+            continue;
+        };
+
+        entries.push(PlaylistEntry {
+            index: entries.len() as u32 + 1,
+            id: json_string_field(raw, "id"),
+            url,
+            title: json_string_field(raw, "title"),
+            uploader: json_string_field(raw, "uploader")
+                .or_else(|| json_string_field(raw, "channel")),
+            thumbnail: json_string_field(raw, "thumbnail").or_else(|| {
+                raw.get("thumbnails")
+                    .and_then(|v| v.as_array())
+                    .and_then(|thumbnails| {
+                        thumbnails
+                            .iter()
+                            .rev()
+                            .find_map(|thumbnail| json_string_field(thumbnail, "url"))
+                    })
+            }),
+            duration: raw.get("duration").and_then(json_value_to_i64),
+        });
+    }
+}
+
+fn playlist_entry_url(raw: &serde_json::Value) -> Option<String> {
+    let url = json_string_field(raw, "url")
+        .filter(|url| is_valid_url(url))
+        .or_else(|| json_string_field(raw, "webpage_url").filter(|url| is_valid_url(url)));
+    if url.is_some() {
+        return url;
+    }
+
+    let id = json_string_field(raw, "id")?;
+    match raw.get("ie_key").and_then(|v| v.as_str()) {
+        Some("Youtube") => Some(format!("https://www.youtube.com/watch?v={id}")),
+        _ => None,
+    }
+}
+
+fn json_string_field(value: &serde_json::Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+fn select_playlist_entries(
+    entries: Vec<PlaylistEntry>,
+    options: &PlaylistEnqueueOptions,
+) -> Result<Vec<PlaylistEntry>, String> {
+    let start = options.start.unwrap_or(1).max(1);
+    let end = options.end.unwrap_or(u32::MAX);
+    if start > end {
+        return Err("Playlist range start must not be after its end".to_string());
+    }
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| entry.index >= start && entry.index <= end)
+        .collect())
+}
+
+fn parse_info_format(f: &serde_json::Value) -> InfoFormat {
+    let string = |key: &str| f.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let size = |key: &str| {
+        f.get(key)
+            .and_then(json_value_to_i64)
+            .and_then(|size| u64::try_from(size).ok())
+    };
+    let rate = |key: &str| {
+        f.get(key)
+            .and_then(|v| v.as_f64())
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+    };
+    InfoFormat {
+        format_id: string("format_id"),
+        ext: string("ext"),
+        vcodec: string("vcodec"),
+        acodec: string("acodec"),
+        height: f.get("height").and_then(|v| v.as_i64()),
+        width: f.get("width").and_then(|v| v.as_i64()),
+        fps: f.get("fps").and_then(|v| v.as_f64()),
+        filesize: size("filesize"),
+        filesize_approx: size("filesize_approx"),
+        tbr: rate("tbr"),
+        abr: rate("abr"),
+        vbr: rate("vbr"),
+        language: string("language"),
+        dynamic_range: string("dynamic_range"),
+        protocol: string("protocol"),
+        format_note: string("format_note"),
+    }
+}
+
+fn json_value_to_i64(value: &serde_json::Value) -> Option<i64> {
+    value
+        .as_i64()
+        .or_else(|| value.as_u64().and_then(|value| i64::try_from(value).ok()))
+        .or_else(|| {
+            value
+                .as_f64()
+                .filter(|value| value.is_finite())
+                .map(|value| value.trunc() as i64)
+        })
+        .or_else(|| value.as_str()?.trim().parse::<i64>().ok())
+}
+
+#[tauri::command]
+fn get_yt_dlp_installed_version(
+    app: AppHandle,
+    state: State<AppState>,
+    path: Option<String>,
+) -> Result<InstalledYtDlpVersion, String> {
+    let yt_dlp = resolve_yt_dlp_for_version(&app, &state, path)?;
     let output = Command::new(&yt_dlp)
         .arg("--version")
         .output()
@@ -762,6 +2508,12 @@ fn get_queue_status(state: State<AppState>) -> Result<QueueStatus, String> {
     snapshot_queue_status(state.inner())
 }
 
+#[tauri::command]
+fn get_queue(state: State<AppState>) -> Result<Vec<DownloadJob>, String> {
+    let queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+    Ok(queue.iter().cloned().collect())
+}
+
 #[tauri::command]
 fn set_queue_auto_start(
     app: AppHandle,
@@ -792,3732 +2544,9658 @@ fn start_queue(app: AppHandle, state: State<AppState>) -> Result<QueueStatus, St
 }
 
 #[tauri::command]
-fn enqueue_download(
+fn set_queue_paused(
     app: AppHandle,
     state: State<AppState>,
-    request: DownloadRequest,
-) -> Result<String, String> {
-    enqueue_download_request(&app, state.inner(), request)
-}
-
-fn enqueue_download_request(
-    app: &AppHandle,
-    state: &AppState,
-    request: DownloadRequest,
-) -> Result<String, String> {
-    let job = build_download_job(state, request)?;
-    let id = job.id.clone();
-    enqueue_download_jobs(app, state, vec![job])?;
-    Ok(id)
-}
-
-fn build_download_job(state: &AppState, request: DownloadRequest) -> Result<DownloadJob, String> {
-    if !is_valid_url(&request.url) {
-        return Err("URL must start with http:// or https://".to_string());
-    }
-
-    let output_dir = resolve_output_dir(state, request.output_dir.clone())?;
-    let cut_start_time = resolve_cut_start_time(
-        request.cut_at_timestamp_enabled,
-        request.cut_start_time,
-        &request.url,
-    );
-    let id = Uuid::new_v4().to_string();
-    Ok(DownloadJob {
-        id: id.clone(),
-        url: request.url,
-        format: request.format,
-        output_dir,
-        extract_audio: request.extract_audio,
-        audio_format: request.audio_format,
-        transcribe_text: request.transcribe_text,
-        title: request.title,
-        uploader: request.uploader,
-        thumbnail: request.thumbnail,
-        upload_date: request.upload_date,
-        timestamp: request.timestamp,
-        duration_seconds: request.duration_seconds,
-        cut_start_time,
-        filename_suffix: normalize_filename_suffix(request.filename_suffix.as_deref()),
-    })
-}
-
-fn enqueue_download_jobs(
-    app: &AppHandle,
-    state: &AppState,
-    jobs: Vec<DownloadJob>,
-) -> Result<Vec<String>, String> {
-    if jobs.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let ids = jobs.iter().map(|job| job.id.clone()).collect::<Vec<_>>();
-
+    paused: bool,
+) -> Result<QueueStatus, String> {
     {
-        let mut queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
-        queue.extend(jobs);
+        let mut queue_paused = state
+            .queue_paused
+            .lock()
+            .map_err(|_| "Queue pause lock poisoned")?;
+        *queue_paused = paused;
     }
 
-    emit_queue(app, state)?;
-    if is_queue_auto_start_enabled(state)? {
-        ensure_worker(app, state)?;
+    if paused {
+        let running_ids = {
+            let running = state
+                .running_jobs
+                .lock()
+                .map_err(|_| "Running jobs lock poisoned")?;
+            running.keys().cloned().collect::<Vec<_>>()
+        };
+        for id in running_ids {
+            request_running_job_pause(&app, state.inner(), &id, PauseRequest::Queue)?;
+        }
+        emit_queue_status(&app, state.inner());
+    } else if is_queue_auto_start_enabled(state.inner())? {
+        ensure_worker(&app, state.inner())?;
+    } else {
+        emit_queue_status(&app, state.inner());
     }
-    Ok(ids)
+
+    snapshot_queue_status(state.inner())
 }
 
 #[tauri::command]
-fn cancel_download(app: AppHandle, state: State<AppState>, id: String) -> Result<(), String> {
-    let removed = {
+fn pause_download(app: AppHandle, state: State<AppState>, id: String) -> Result<(), String> {
+    let paused_job = {
         let mut queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
-        let before = queue.len();
-        queue.retain(|job| job.id != id);
-        before != queue.len()
+        queue.iter_mut().find(|job| job.id == id).map(|job| {
+            job.paused = true;
+            job.clone()
+        })
     };
 
-    if removed {
+    if let Some(job) = paused_job {
+        update_queue_job_in_db(state.inner(), &job, "paused")?;
         emit_queue(&app, &state)?;
-        emit_state(
+        set_job_state(
             &app,
-            DownloadStateEvent {
-                id,
-                state: "cancelled".to_string(),
-                exit_code: None,
-                error: None,
-                output_path: None,
+            state.inner(),
+            &id,
+            JobState::Paused {
+                paused_at: current_timestamp_millis(),
             },
         );
         return Ok(());
     }
 
-    let is_current = {
-        let current = state
-            .current_job_id
-            .lock()
-            .map_err(|_| "Current job lock poisoned")?;
-        current.as_deref() == Some(&id)
-    };
-
-    if !is_current {
-        return Err("Job not found in queue".to_string());
-    }
+    request_running_job_pause(&app, state.inner(), &id, PauseRequest::Job)
+}
 
-    {
-        let mut cancel = state
-            .cancel_requested
-            .lock()
-            .map_err(|_| "Cancel lock poisoned")?;
-        *cancel = Some(id.clone());
-    }
+#[tauri::command]
+fn resume_download(app: AppHandle, state: State<AppState>, id: String) -> Result<(), String> {
+    let resumed_job = {
+        let mut queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        queue
+            .iter_mut()
+            .find(|job| job.id == id && job.paused)
+            .map(|job| {
+                job.paused = false;
+                job.clone()
+            })
+    };
 
-    let child = {
-        let child_guard = state
-            .current_child
-            .lock()
-            .map_err(|_| "Child lock poisoned")?;
-        child_guard.clone()
+    let Some(job) = resumed_job else {
+        return Err("Job is not paused".to_string());
     };
 
-    if let Some(child) = child {
-        if let Ok(mut guard) = child.lock() {
-            let _ = guard.kill();
-        }
+    update_queue_job_in_db(state.inner(), &job, "queued")?;
+    emit_queue(&app, &state)?;
+    set_job_state(
+        &app,
+        state.inner(),
+        &id,
+        JobState::Queued {
+            queued_at: current_timestamp_millis(),
+        },
+    );
+    if is_queue_auto_start_enabled(state.inner())? {
+        ensure_worker(&app, state.inner())?;
     }
+    Ok(())
+}
 
-    emit_state(
-        &app,
-        DownloadStateEvent {
-            id,
-            state: "cancelling".to_string(),
-            exit_code: None,
-            error: None,
-            output_path: None,
-        },
-    );
-    Ok(())
+#[tauri::command]
+fn move_queue_job(
+    app: AppHandle,
+    state: State<AppState>,
+    id: String,
+    index: usize,
+) -> Result<(), String> {
+    reorder_queue_job(&app, state.inner(), &id, |_| index)
 }
 
 #[tauri::command]
-fn get_history(
-    state: State<AppState>,
-    limit: Option<u32>,
-    offset: Option<u32>,
-) -> Result<HistoryPage, String> {
-    let limit = limit.unwrap_or(50).clamp(1, 100);
-    let offset = offset.unwrap_or(0);
-    list_history_page_from_db(state.inner(), limit, offset)
+fn move_queue_job_to_top(app: AppHandle, state: State<AppState>, id: String) -> Result<(), String> {
+    reorder_queue_job(&app, state.inner(), &id, |_| 0)
 }
 
 #[tauri::command]
-fn get_history_stats(state: State<AppState>) -> Result<HistoryStats, String> {
-    get_history_stats_from_db(state.inner())
+fn move_queue_job_to_bottom(
+    app: AppHandle,
+    state: State<AppState>,
+    id: String,
+) -> Result<(), String> {
+    reorder_queue_job(&app, state.inner(), &id, |len| len)
 }
 
-fn detect_platform(url: &str) -> Option<String> {
-    if let Ok(parsed) = url::Url::parse(url) {
-        let host = parsed.host_str().unwrap_or("").to_lowercase();
-        let host = host.strip_prefix("www.").unwrap_or(&host);
+#[tauri::command]
+fn set_queue_job_priority(
+    app: AppHandle,
+    state: State<AppState>,
+    id: String,
+    priority: i32,
+) -> Result<(), String> {
+    let job = {
+        let mut queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        let Some(job) = queue.iter_mut().find(|job| job.id == id) else {
+            return Err("Job not found in queue".to_string());
+        };
+        job.priority = priority;
+        job.clone()
+    };
 
-        if host == "youtu.be" || host.ends_with("youtube.com") {
-            return Some("youtube".to_string());
-        }
-        if host.ends_with("facebook.com") || host == "fb.watch" {
-            return Some("facebook".to_string());
-        }
-        if host.ends_with("twitch.tv") {
-            return Some("twitch".to_string());
-        }
-        if host == "x.com" || host.ends_with(".x.com") || host.ends_with("twitter.com") {
-            return Some("x".to_string());
-        }
-        if host.ends_with("tiktok.com") {
-            return Some("tiktok".to_string());
-        }
-        if host.ends_with("instagram.com") || host.ends_with("instagr.am") {
-            return Some("instagram".to_string());
+    let job_state = if job.paused { "paused" } else { "queued" };
+    update_queue_job_in_db(state.inner(), &job, job_state)?;
+    emit_queue(&app, &state)
+}
+
+fn reorder_queue_job(
+    app: &AppHandle,
+    state: &AppState,
+    id: &str,
+    target_index: impl FnOnce(usize) -> usize,
+) -> Result<(), String> {
+    {
+        let mut queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        let index = target_index(queue.len());
+        if !move_job_in_queue(&mut queue, id, index) {
+            return Err("Job not found in queue".to_string());
         }
     }
-    None
+
+    sync_queue_positions_in_db(state)?;
+    emit_queue(app, state)
 }
 
-fn source_from_url(url: &str) -> Option<String> {
-    let parsed = url::Url::parse(url).ok()?;
-    let host = parsed.domain()?.trim_end_matches('.').to_ascii_lowercase();
+fn move_job_in_queue(queue: &mut VecDeque<DownloadJob>, id: &str, index: usize) -> bool {
+    let Some(current) = queue.iter().position(|job| job.id == id) else {
+        return false;
+    };
+    let Some(job) = queue.remove(current) else {
+        return false;
+    };
+    let index = index.min(queue.len());
+    queue.insert(index, job);
+    true
+}
 
-    let canonical_short_domain = match host.as_str() {
-        "youtu.be" => Some("youtube"),
-        "fb.watch" => Some("facebook"),
-        "instagr.am" => Some("instagram"),
-        "lnkd.in" => Some("linkedin"),
-        "t.co" => Some("x"),
-        _ => None,
+fn request_running_job_pause(
+    app: &AppHandle,
+    state: &AppState,
+    id: &str,
+    pause_request: PauseRequest,
+) -> Result<(), String> {
+    let child = {
+        let running = state
+            .running_jobs
+            .lock()
+            .map_err(|_| "Running jobs lock poisoned")?;
+        let Some(running_job) = running.get(id) else {
+            return Err("Job not found in queue".to_string());
+        };
+        running_job.child.clone()
     };
-    if let Some(source) = canonical_short_domain {
-        return Some(source.to_string());
-    }
 
-    let labels = host
-        .split('.')
-        .filter(|label| !label.is_empty())
-        .collect::<Vec<_>>();
-    if labels.is_empty() {
-        return None;
+    {
+        let mut pause = state
+            .pause_requested
+            .lock()
+            .map_err(|_| "Pause lock poisoned")?;
+        pause.insert(id.to_string(), pause_request);
     }
-    if labels.len() == 1 {
-        return Some(labels[0].to_string());
+
+    // yt-dlp keeps the `.part` file when killed, so the next run on the same
+    // output template continues where this one stopped.
+    if let Some(child) = child {
+        if let Ok(mut guard) = child.lock() {
+            let _ = guard.kill();
+        }
     }
 
-    let common_second_level_tld = matches!(
-        labels[labels.len() - 2],
-        "ac" | "co" | "com" | "edu" | "gov" | "net" | "org"
+    set_job_state(
+        app,
+        state,
+        id,
+        JobState::Pausing {
+            requested_at: current_timestamp_millis(),
+        },
     );
-    let source_index = if labels.len() >= 3
-        && labels.last().is_some_and(|tld| tld.len() == 2)
-        && common_second_level_tld
-    {
-        labels.len() - 3
-    } else {
-        labels.len() - 2
-    };
-
-    Some(labels[source_index].to_string())
+    Ok(())
 }
 
-fn current_timestamp_millis() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64
+#[tauri::command]
+fn enqueue_download(
+    app: AppHandle,
+    state: State<AppState>,
+    request: DownloadRequest,
+) -> Result<String, String> {
+    enqueue_download_request(&app, state.inner(), request)
 }
 
-fn filename_from_path(path: Option<&str>) -> Option<String> {
-    let filename = Path::new(path?)
-        .file_name()
-        .and_then(|value| value.to_str())?
-        .trim();
-    if filename.is_empty() {
-        None
-    } else {
-        Some(filename.to_string())
-    }
+// With several clip ranges the returned id is the first clip's; the others
+// share its `batch_id`.
+fn enqueue_download_request(
+    app: &AppHandle,
+    state: &AppState,
+    request: DownloadRequest,
+) -> Result<String, String> {
+    let jobs = build_download_jobs(state, request)?;
+    let id = jobs[0].id.clone();
+    enqueue_download_jobs(app, state, jobs)?;
+    Ok(id)
 }
 
-fn title_from_filename(filename: Option<&str>) -> Option<String> {
-    let stem = Path::new(filename?)
-        .file_stem()
-        .and_then(|value| value.to_str())
-        .unwrap_or(filename?)
-        .trim();
-    if stem.is_empty() {
-        None
-    } else {
-        Some(stem.to_string())
+fn build_download_jobs(
+    state: &AppState,
+    request: DownloadRequest,
+) -> Result<Vec<DownloadJob>, String> {
+    let ranges = resolve_clip_ranges(&request)?;
+    if ranges.len() <= 1 {
+        return Ok(vec![build_download_job(state, request)?]);
     }
-}
 
-fn trim_optional_string(value: Option<String>) -> Option<String> {
-    value
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
+    let batch_id = Uuid::new_v4().to_string();
+    ranges
+        .into_iter()
+        .map(|range| {
+            let mut job = build_download_job(
+                state,
+                DownloadRequest {
+                    cut_start_time: None,
+                    cut_end_time: None,
+                    ranges: vec![range],
+                    ..request.clone()
+                },
+            )?;
+            job.batch_id = Some(batch_id.clone());
+            Ok(job)
+        })
+        .collect()
 }
 
-#[derive(Debug)]
-struct HydratedHistoryMetadata {
-    title: Option<String>,
-    uploader: Option<String>,
-    thumbnail: Option<String>,
-    upload_date: Option<String>,
-    timestamp: Option<i64>,
-    duration_seconds: Option<i64>,
+fn resolve_request_format(request: &DownloadRequest) -> Result<String, String> {
+    resolve_picked_format(
+        request.video_format_id.as_deref(),
+        request.audio_format_id.as_deref(),
+        &request.format,
+    )
 }
 
-fn hydrate_history_metadata(
-    app: &AppHandle,
-    state: &AppState,
-    job: &DownloadJob,
-    filename: Option<&str>,
-) -> HydratedHistoryMetadata {
-    let mut title = trim_optional_string(job.title.clone());
-    let mut uploader = trim_optional_string(job.uploader.clone());
-    let mut thumbnail = trim_optional_string(job.thumbnail.clone());
-    let mut upload_date = trim_optional_string(job.upload_date.clone());
-    let mut timestamp = job.timestamp;
-    let mut duration_seconds = job.duration_seconds;
+// A video id alone may be video-only, so the best audio is merged in; the
+// `/{video}` fallback covers sites that offer no separate audio stream.
+fn resolve_picked_format(
+    video_format_id: Option<&str>,
+    audio_format_id: Option<&str>,
+    fallback: &str,
+) -> Result<String, String> {
+    let video = normalize_format_id(video_format_id)?;
+    let audio = normalize_format_id(audio_format_id)?;
+    Ok(match (video, audio) {
+        (Some(video), Some(audio)) => format!("{video}+{audio}"),
+        (Some(video), None) => format!("{video}+ba/{video}"),
+        (None, Some(audio)) => audio,
+        (None, None) => fallback.to_string(),
+    })
+}
 
-    if title.is_none()
-        || uploader.is_none()
-        || thumbnail.is_none()
-        || upload_date.is_none()
-        || timestamp.is_none()
-        || duration_seconds.is_none()
+// A single id, never a selector: `+`, `/` and filters are not accepted.
+fn normalize_format_id(raw: Option<&str>) -> Result<Option<String>, String> {
+    let Some(format_id) = raw.map(str::trim).filter(|id| !id.is_empty()) else {
+        return Ok(None);
+    };
+    if format_id.len() > 64
+        || !format_id.chars().all(|character| {
+            character.is_ascii_alphanumeric() || matches!(character, '-' | '_' | '.' | '=')
+        })
     {
-        if let Ok(yt_dlp) = resolve_yt_dlp(app, state) {
-            let deno = resolve_deno_executable(app);
-            if let Ok(info) = load_info_with_yt_dlp(yt_dlp, deno, job.url.clone()) {
-                if title.is_none() {
-                    title = trim_optional_string(info.title);
-                }
-                if uploader.is_none() {
-                    uploader = trim_optional_string(info.uploader);
-                }
-                if thumbnail.is_none() {
-                    thumbnail = trim_optional_string(info.thumbnail);
-                }
-                if upload_date.is_none() {
-                    upload_date = trim_optional_string(info.upload_date);
-                }
-                if timestamp.is_none() {
-                    timestamp = info.timestamp;
-                }
-                if duration_seconds.is_none() {
-                    duration_seconds = info.duration;
-                }
-            }
-        }
-    }
-
-    if title.is_none() {
-        title = title_from_filename(filename);
-    }
-
-    HydratedHistoryMetadata {
-        title,
-        uploader,
-        thumbnail,
-        upload_date,
-        timestamp,
-        duration_seconds,
+        return Err(format!("Invalid format id: {format_id}"));
     }
+    Ok(Some(format_id.to_string()))
 }
 
-fn medium_for_job(job: &DownloadJob) -> &'static str {
-    if job.transcribe_text {
-        "transcript"
-    } else if job.extract_audio {
-        "audio"
-    } else {
-        "video"
+fn build_download_job(state: &AppState, request: DownloadRequest) -> Result<DownloadJob, String> {
+    if !is_valid_url(&request.url) {
+        return Err("URL must start with http:// or https://".to_string());
     }
-}
 
-fn file_size_bytes_from_path(path: Option<&str>) -> Option<i64> {
-    let size = fs::metadata(path?).ok()?.len();
-    i64::try_from(size).ok()
-}
+    let format = resolve_request_format(&request)?;
+    let output_dir = resolve_output_dir(state, request.output_dir.clone())?;
+    let range = match resolve_clip_ranges(&request)?.as_slice() {
+        [] => None,
+        [range] => Some(*range),
+        _ => return Err("Expected at most one clip range per job".to_string()),
+    };
+    let cut_start_time = range.map(|range| range.start);
+    let cut_end_time = range.and_then(|range| range.end);
+    let cut_precision = match request.cut_precision {
+        Some(cut_precision) => cut_precision,
+        None => {
+            state
+                .config
+                .lock()
+                .map_err(|_| "Config lock poisoned")?
+                .cut_precision
+        }
+    };
+    let output_template = match normalize_output_template(request.output_template.as_deref())? {
+        Some(output_template) => output_template,
+        None => configured_output_template(state)?,
+    };
+    let subtitles = normalize_subtitle_options(request.subtitles)?;
+    let sponsorblock = normalize_sponsorblock_options(request.sponsorblock)?;
+    let id = Uuid::new_v4().to_string();
+    Ok(DownloadJob {
+        id: id.clone(),
+        url: request.url,
+        format,
+        output_dir,
+        extract_audio: request.extract_audio,
+        audio_format: request.audio_format,
+        transcribe_text: request.transcribe_text,
+        title: request.title,
+        uploader: request.uploader,
+        thumbnail: request.thumbnail,
+        upload_date: request.upload_date,
+        timestamp: request.timestamp,
+        duration_seconds: request.duration_seconds,
+        cut_start_time,
+        cut_end_time,
+        cut_mode: request.cut_mode,
+        cut_precision,
+        output_template,
+        filename_suffix: normalize_filename_suffix(request.filename_suffix.as_deref()),
+        paused: false,
+        priority: request.priority,
+        retry_count: 0,
+        retry_at: None,
+        not_before: request.not_before,
+        rate_limit_kib: request.rate_limit_kib.filter(|limit| *limit > 0),
+        batch_id: None,
+        playlist_title: None,
+        playlist_index: None,
+        playlist_in_output_template: false,
+        force_download: request.force_download,
+        subtitles,
+        embed: request.embed,
+        sponsorblock,
+        split_chapters: request.split_chapters,
+    })
+}
 
-fn add_history_entry_on_success(
-    app: &AppHandle,
-    state: &AppState,
-    job: &DownloadJob,
-    output_path: Option<&str>,
-) {
-    let filename = filename_from_path(output_path);
-    let metadata = hydrate_history_metadata(app, state, job, filename.as_deref());
-    let file_size_bytes = file_size_bytes_from_path(output_path);
-    let now = current_timestamp_millis();
-    let entry = HistoryEntry {
-        id: Uuid::new_v4().to_string(),
-        url: job.url.clone(),
-        title: metadata.title,
-        uploader: metadata.uploader,
-        filename,
-        thumbnail: metadata.thumbnail,
-        upload_date: metadata.upload_date,
-        timestamp: metadata.timestamp,
-        duration_seconds: metadata.duration_seconds,
-        file_size_bytes,
-        medium: Some(medium_for_job(job).to_string()),
-        source: source_from_url(&job.url),
-        platform: detect_platform(&job.url),
-        output_path: output_path.map(|s| s.to_string()),
-        created_at: now,
-        completed_at: Some(now),
+fn normalize_subtitle_options(options: SubtitleOptions) -> Result<SubtitleOptions, String> {
+    let convert_format = match options
+        .convert_format
+        .as_deref()
+        .map(|format| format.trim().to_ascii_lowercase())
+        .filter(|format| !format.is_empty())
+    {
+        None => None,
+        Some(format) if SUBTITLE_CONVERT_FORMATS.contains(&format.as_str()) => Some(format),
+        Some(_) => return Err("Subtitle format must be srt or vtt".to_string()),
     };
 
-    let _ = insert_history_entry_in_db(state, &entry);
+    let mut languages = Vec::new();
+    for language in options.languages.iter().flat_map(|value| value.split(',')) {
+        let language = language.trim();
+        if language.is_empty() {
+            continue;
+        }
+        if language.chars().any(char::is_whitespace) {
+            return Err(format!("Invalid subtitle language: {language}"));
+        }
+        if !languages.iter().any(|known| known == language) {
+            languages.push(language.to_string());
+        }
+    }
+
+    Ok(SubtitleOptions {
+        languages,
+        convert_format,
+        ..options
+    })
 }
 
-#[tauri::command]
-fn remove_history_entry(state: State<AppState>, id: String) -> Result<(), String> {
-    delete_history_entry_from_db(state.inner(), &id)?;
-    Ok(())
+// Embedded subtitles are skipped for audio-only jobs; yt-dlp can only embed
+// them into video containers.
+fn subtitle_args(options: &SubtitleOptions, extract_audio: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    if options.write_subs {
+        args.push("--write-subs".to_string());
+    }
+    if options.write_auto_subs {
+        args.push("--write-auto-subs".to_string());
+    }
+    let embed = options.embed && !extract_audio;
+    if !options.write_subs && !options.write_auto_subs && !embed {
+        return args;
+    }
+    if !options.languages.is_empty() {
+        args.push("--sub-langs".to_string());
+        args.push(options.languages.join(","));
+    }
+    if let Some(format) = options.convert_format.as_ref() {
+        args.push("--convert-subs".to_string());
+        args.push(format.clone());
+    }
+    if embed {
+        args.push("--embed-subs".to_string());
+    }
+    args
 }
 
-#[tauri::command]
-fn clear_history(state: State<AppState>) -> Result<(), String> {
-    clear_history_entries_in_db(state.inner())?;
-    Ok(())
+fn embed_args(job: &DownloadJob) -> Vec<String> {
+    let mut args = Vec::new();
+    if job.embed.metadata {
+        args.push("--embed-metadata".to_string());
+        if job.extract_audio {
+            args.extend(audio_tag_args(job));
+        }
+    }
+    if job.embed.thumbnail {
+        args.push("--embed-thumbnail".to_string());
+    }
+    if job.embed.chapters {
+        args.push("--embed-chapters".to_string());
+    }
+    args
 }
 
-fn snapshot_queue_status(state: &AppState) -> Result<QueueStatus, String> {
-    let auto_start = *state
-        .queue_auto_start
-        .lock()
-        .map_err(|_| "Queue auto-start lock poisoned")?;
-    let worker_running = *state
-        .worker_running
-        .lock()
-        .map_err(|_| "Worker lock poisoned")?;
+// Audio tags come from the job rather than the extractor, so titles and
+// uploaders loaded in the app end up in the ID3/Vorbis tags. yt-dlp writes
+// `meta_*` fields verbatim; the marker prefix keeps single-word values from
+// being read as a field name by `--parse-metadata`.
+fn audio_tag_args(job: &DownloadJob) -> Vec<String> {
+    let upload_date = job.upload_date.as_deref().map(format_tag_date);
+    let tags = [
+        ("meta_title", job.title.as_deref()),
+        ("meta_artist", job.uploader.as_deref()),
+        ("meta_date", upload_date.as_deref()),
+    ];
 
-    Ok(QueueStatus {
-        auto_start,
-        worker_running,
-    })
+    let mut args = Vec::new();
+    for (field, value) in tags {
+        let Some(value) = value.map(str::trim).filter(|value| !value.is_empty()) else {
+            continue;
+        };
+        let template = value.replace('%', "%%").replace(':', "\\:");
+        args.push("--parse-metadata".to_string());
+        args.push(format!(
+            "{AUDIO_TAG_MARKER}{template}:{AUDIO_TAG_MARKER}(?P<{field}>.+)"
+        ));
+    }
+    args
 }
 
-fn emit_queue_status(app: &AppHandle, state: &AppState) {
-    if let Ok(status) = snapshot_queue_status(state) {
-        let _ = app.emit_all("queue:status", status);
+fn format_tag_date(upload_date: &str) -> String {
+    let upload_date = upload_date.trim();
+    if upload_date.len() == 8 && upload_date.chars().all(|c| c.is_ascii_digit()) {
+        format!(
+            "{}-{}-{}",
+            &upload_date[0..4],
+            &upload_date[4..6],
+            &upload_date[6..8]
+        )
+    } else {
+        upload_date.to_string()
     }
 }
 
-fn is_queue_auto_start_enabled(state: &AppState) -> Result<bool, String> {
-    let auto_start = state
-        .queue_auto_start
-        .lock()
-        .map_err(|_| "Queue auto-start lock poisoned")?;
-    Ok(*auto_start)
-}
+fn normalize_sponsorblock_options(
+    options: SponsorBlockOptions,
+) -> Result<SponsorBlockOptions, String> {
+    if options.mode == SponsorBlockMode::Off {
+        return Ok(SponsorBlockOptions::default());
+    }
 
-fn ensure_worker(app: &AppHandle, state: &AppState) -> Result<(), String> {
-    let mut running = state
-        .worker_running
-        .lock()
-        .map_err(|_| "Worker lock poisoned")?;
-    if *running {
-        return Ok(());
+    let mut categories = Vec::new();
+    for category in options.categories.iter().flat_map(|value| value.split(',')) {
+        let category = category.trim().to_ascii_lowercase();
+        if category.is_empty() || categories.contains(&category) {
+            continue;
+        }
+        let removable = !matches!(category.as_str(), "poi_highlight" | "chapter");
+        if !SPONSORBLOCK_CATEGORIES.contains(&category.as_str())
+            || (options.mode == SponsorBlockMode::Remove && !removable)
+        {
+            return Err(format!("Unsupported SponsorBlock category: {category}"));
+        }
+        categories.push(category);
     }
-    *running = true;
-    drop(running);
-    emit_queue_status(app, state);
 
-    let app_handle = app.clone();
+    Ok(SponsorBlockOptions {
+        mode: options.mode,
+        categories,
+    })
+}
 
-    thread::spawn(move || {
-        loop {
-            let state_handle = app_handle.state::<AppState>();
-            let job_opt = {
-                let mut queue = match state_handle.queue.lock() {
-                    Ok(queue) => queue,
-                    Err(_) => break,
-                };
-                queue.pop_front()
-            };
+fn sponsorblock_args(options: &SponsorBlockOptions) -> Vec<String> {
+    let flag = match options.mode {
+        SponsorBlockMode::Off => return Vec::new(),
+        SponsorBlockMode::Mark => "--sponsorblock-mark",
+        SponsorBlockMode::Remove => "--sponsorblock-remove",
+    };
+    let categories = if options.categories.is_empty() {
+        "default".to_string()
+    } else {
+        options.categories.join(",")
+    };
 
-            let job = match job_opt {
-                Some(job) => job,
-                None => {
-                    if let Ok(mut running) = state_handle.worker_running.lock() {
-                        *running = false;
-                    }
-                    let _ = emit_queue(&app_handle, &state_handle);
-                    emit_queue_status(&app_handle, &state_handle);
-                    break;
-                }
-            };
+    let mut args = vec![flag.to_string(), categories];
+    if options.mode == SponsorBlockMode::Remove {
+        args.push("--print".to_string());
+        args.push(format!(
+            "after_move:{YT_DLP_SPONSORBLOCK_PREFIX}%(sponsorblock_chapters)j"
+        ));
+    }
+    args
+}
 
-            if let Ok(mut current) = state_handle.current_job_id.lock() {
-                *current = Some(job.id.clone());
-            }
+// Only `skip` segments are cut; highlights are zero-length chapter markers.
+fn parse_sponsorblock_segments_line(line: &str) -> Option<Vec<SponsorBlockSegment>> {
+    let json = line.strip_prefix(YT_DLP_SPONSORBLOCK_PREFIX)?;
+    let chapters = serde_json::from_str::<serde_json::Value>(json.trim())
+        .ok()
+        .and_then(|value| value.as_array().cloned())
+        .unwrap_or_default();
 
-            emit_state(
-                &app_handle,
-                DownloadStateEvent {
-                    id: job.id.clone(),
-                    state: "downloading".to_string(),
-                    exit_code: None,
-                    error: None,
-                    output_path: None,
-                },
-            );
+    Some(
+        chapters
+            .iter()
+            .filter(|chapter| {
+                chapter
+                    .get("type")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("skip")
+                    == "skip"
+            })
+            .filter_map(|chapter| {
+                let start_time = chapter.get("start_time")?.as_f64()?;
+                let end_time = chapter.get("end_time")?.as_f64()?;
+                (end_time > start_time).then(|| SponsorBlockSegment {
+                    category: chapter
+                        .get("category")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("sponsor")
+                        .to_string(),
+                    start_time,
+                    end_time,
+                })
+            })
+            .collect(),
+    )
+}
 
-            let result = run_download_job(&app_handle, &state_handle, &job);
+// Overlapping segments are only cut once, so they are merged before summing.
+fn removed_segments_duration_seconds(segments: &[SponsorBlockSegment]) -> i64 {
+    let mut ranges = segments
+        .iter()
+        .map(|segment| (segment.start_time, segment.end_time))
+        .collect::<Vec<_>>();
+    ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
 
-            if let Ok(mut current) = state_handle.current_job_id.lock() {
-                *current = None;
+    let mut total = 0.0;
+    let mut current: Option<(f64, f64)> = None;
+    for (start, end) in ranges {
+        match current.as_mut() {
+            Some((_, current_end)) if start <= *current_end => {
+                *current_end = current_end.max(end);
+            }
+            _ => {
+                if let Some((current_start, current_end)) = current {
+                    total += current_end - current_start;
+                }
+                current = Some((start, end));
             }
+        }
+    }
+    if let Some((current_start, current_end)) = current {
+        total += current_end - current_start;
+    }
+    total.round() as i64
+}
 
-            match result {
-                Ok(run_result) => {
-                    let cancelled = if let Ok(mut cancel) = state_handle.cancel_requested.lock() {
-                        if cancel.as_deref() == Some(job.id.as_str()) {
-                            *cancel = None;
-                            true
-                        } else {
-                            false
-                        }
-                    } else {
-                        false
-                    };
+// Chapter files go into a folder named after the full download, e.g.
+// `<title> - <uploader> - <id>/003 - <chapter>.<ext>`.
+fn build_chapter_output_template(output_template: &str) -> String {
+    let base = output_template
+        .strip_suffix(".%(ext)s")
+        .unwrap_or(output_template);
+    let mut path = PathBuf::from(base);
+    path.push("%(section_number)03d - %(section_title)s.%(ext)s");
+    path.to_string_lossy().to_string()
+}
 
-                    if cancelled {
-                        emit_state(
-                            &app_handle,
-                            DownloadStateEvent {
-                                id: job.id.clone(),
-                                state: "cancelled".to_string(),
-                                exit_code: Some(run_result.exit_code),
-                                error: None,
-                                output_path: None,
-                            },
-                        );
-                    } else if run_result.exit_code != 0 {
-                        emit_state(
-                            &app_handle,
-                            DownloadStateEvent {
-                                id: job.id.clone(),
-                                state: "error".to_string(),
-                                exit_code: Some(run_result.exit_code),
-                                error: Some("yt-dlp exited with error".to_string()),
-                                output_path: None,
-                            },
-                        );
-                    } else if job.transcribe_text {
-                        emit_state(
-                            &app_handle,
-                            DownloadStateEvent {
-                                id: job.id.clone(),
-                                state: "transcribing".to_string(),
-                                exit_code: Some(run_result.exit_code),
-                                error: None,
-                                output_path: None,
-                            },
-                        );
+fn split_chapters_args(output_template: &str) -> Vec<String> {
+    vec![
+        "--split-chapters".to_string(),
+        "-o".to_string(),
+        format!("chapter:{}", build_chapter_output_template(output_template)),
+        "--print".to_string(),
+        format!("after_move:{YT_DLP_CHAPTERS_PREFIX}%(chapters)j"),
+    ]
+}
+
+#[derive(Debug, Default)]
+struct SplitChaptersCapture {
+    files: Vec<ChapterFile>,
+    chapters: Vec<InfoChapter>,
+}
+
+impl SplitChaptersCapture {
+    fn chapter_files(&self) -> Vec<ChapterFile> {
+        let mut files = self
+            .files
+            .iter()
+            .map(|file| ChapterFile {
+                chapter: usize::try_from(file.number)
+                    .ok()
+                    .and_then(|number| number.checked_sub(1))
+                    .and_then(|index| self.chapters.get(index).cloned()),
+                ..file.clone()
+            })
+            .collect::<Vec<_>>();
+        files.sort_by_key(|file| file.number);
+        files
+    }
+}
 
-                        match run_faster_whisper_transcription(
-                            &app_handle,
-                            &job,
-                            run_result.output_path.as_deref(),
-                        ) {
-                            Ok(transcript_path) => {
-                                emit_log(
-                                    &app_handle,
-                                    LogEvent {
-                                        id: job.id.clone(),
-                                        line: format!("[transcript] saved: {transcript_path}"),
-                                        is_error: false,
-                                    },
-                                );
-                                emit_state(
-                                    &app_handle,
-                                    DownloadStateEvent {
-                                        id: job.id.clone(),
-                                        state: "success".to_string(),
-                                        exit_code: Some(run_result.exit_code),
-                                        error: None,
-                                        output_path: Some(transcript_path.clone()),
-                                    },
-                                );
-                                // Add to history on success
-                                add_history_entry_on_success(
-                                    &app_handle,
-                                    &state_handle,
-                                    &job,
-                                    run_result.output_path.as_deref(),
-                                );
-                            }
-                            Err(err) => {
-                                emit_state(
-                                    &app_handle,
-                                    DownloadStateEvent {
-                                        id: job.id.clone(),
-                                        state: "error".to_string(),
-                                        exit_code: Some(run_result.exit_code),
-                                        error: Some(err),
-                                        output_path: None,
-                                    },
-                                );
-                            }
-                        }
-                    } else {
-                        emit_state(
-                            &app_handle,
-                            DownloadStateEvent {
-                                id: job.id.clone(),
-                                state: "success".to_string(),
-                                exit_code: Some(run_result.exit_code),
-                                error: None,
-                                output_path: run_result.output_path.clone(),
-                            },
-                        );
-                        // Add to history on success
-                        add_history_entry_on_success(
-                            &app_handle,
-                            &state_handle,
-                            &job,
-                            run_result.output_path.as_deref(),
-                        );
-                    }
-                }
-                Err(err) => {
-                    emit_state(
-                        &app_handle,
-                        DownloadStateEvent {
-                            id: job.id.clone(),
-                            state: "error".to_string(),
-                            exit_code: None,
-                            error: Some(err),
-                            output_path: None,
-                        },
-                    );
-                }
-            }
+fn parse_split_chapter_line(line: &str) -> Option<ChapterFile> {
+    let rest = line.trim().strip_prefix("[SplitChapters] Chapter ")?;
+    let (number, destination) = rest.split_once("; Destination: ")?;
+    Some(ChapterFile {
+        number: number.trim().parse().ok()?,
+        path: normalize_filepath_candidate(destination)?,
+        chapter: None,
+    })
+}
 
-            let _ = emit_queue(&app_handle, &state_handle);
-        }
-    });
+fn is_subtitle_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| SUBTITLE_FILE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
 
-    Ok(())
+// yt-dlp writes subtitles next to the media as `<stem>.<lang>.<ext>`.
+fn find_subtitle_files(output_path: &Path) -> Vec<String> {
+    let Some(parent) = output_path.parent() else {
+        return Vec::new();
+    };
+    let Some(stem) = output_path.file_stem().and_then(|value| value.to_str()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{stem}.");
+
+    let mut paths = fs::read_dir(parent)
+        .ok()
+        .into_iter()
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_subtitle_path(path))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|value| value.to_str())
+                .map(|name| name.starts_with(&prefix))
+                .unwrap_or(false)
+        })
+        .map(|path| path.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
 }
 
-fn run_download_job(
+fn enqueue_download_jobs(
     app: &AppHandle,
     state: &AppState,
-    job: &DownloadJob,
-) -> Result<DownloadRunResult, String> {
-    let yt_dlp = resolve_yt_dlp(app, state)?;
-    let ffmpeg_location = resolve_ffmpeg_location(app, &yt_dlp);
-    let deno_path = resolve_deno_executable(app);
-    let output_template = build_output_template(&job.output_dir, job.filename_suffix.as_deref());
-    let output_template_for_fallback = output_template.clone();
+    jobs: Vec<DownloadJob>,
+) -> Result<Vec<String>, String> {
+    if jobs.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    let mut args = vec![
-        "--no-playlist".to_string(),
-        "--newline".to_string(),
-        "--progress".to_string(),
-        "--no-color".to_string(),
-        "--print".to_string(),
-        "after_move:filepath".to_string(),
-        "--print".to_string(),
-        "after_video:filepath".to_string(),
-        "-f".to_string(),
-        job.format.clone(),
-        "-o".to_string(),
-        output_template,
-    ];
+    let ids = jobs.iter().map(|job| job.id.clone()).collect::<Vec<_>>();
 
-    let needs_ffmpeg = job.extract_audio
-        || job.transcribe_text
-        || job.format.contains('+')
-        || job.cut_start_time.is_some();
-    if let Some(location) = ffmpeg_location.as_ref() {
-        args.push("--ffmpeg-location".to_string());
-        args.push(location.clone());
-    } else if needs_ffmpeg {
-        return Err(
-            "ffmpeg and ffprobe not found. Install ffmpeg (or make sure it is in the same directory as yt-dlp) and try again."
-                .to_string(),
-        );
+    insert_queue_jobs_in_db(state, &jobs)?;
+    let queued_at = current_timestamp_millis();
+    for job in &jobs {
+        record_job_state(state, &job.id, JobState::Queued { queued_at });
     }
-
-    if let Some(deno) = deno_path.as_ref() {
-        args.push("--js-runtimes".to_string());
-        args.push(format!("deno:{deno}"));
+    {
+        let mut queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        queue.extend(jobs);
     }
 
-    if job.extract_audio {
-        args.push("--extract-audio".to_string());
-        if let Some(fmt) = job.audio_format.as_ref() {
-            args.push("--audio-format".to_string());
-            args.push(fmt.to_string());
-        }
+    emit_queue(app, state)?;
+    if is_queue_auto_start_enabled(state)? {
+        ensure_worker(app, state)?;
     }
+    Ok(ids)
+}
 
-    if let Some(cut_start_time) = job.cut_start_time {
-        let cut_timestamp = format_yt_dlp_timestamp(cut_start_time);
-        emit_log(
-            app,
-            LogEvent {
-                id: job.id.clone(),
-                line: format!("[cut] URL timestamp detected; downloading full file before local cut at {cut_timestamp}s"),
-                is_error: false,
-            },
-        );
+#[tauri::command]
+async fn enqueue_playlist(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    request: DownloadRequest,
+    options: Option<PlaylistEnqueueOptions>,
+) -> Result<PlaylistEnqueueResult, String> {
+    if !is_valid_url(&request.url) {
+        return Err("URL must start with http:// or https://".to_string());
+    }
+    let yt_dlp = resolve_yt_dlp(&app, &state)?;
+    let deno = resolve_deno_executable(&app);
+    let url = request.url.clone();
+    let cookies = resolve_cookies_path(&state, &url);
+
+    let playlist = tauri::async_runtime::spawn_blocking(move || {
+        load_playlist_with_yt_dlp(yt_dlp, deno, cookies, url, None)
+    })
+    .await
+    .map_err(|e| format!("Playlist task failed: {e}"))??;
+    let options = options.unwrap_or_default();
+    let jobs = build_playlist_jobs(state.inner(), &request, playlist.clone(), &options)?;
+    if jobs.is_empty() {
+        return Err("No playlist entries in the selected range".to_string());
     }
 
-    args.push(job.url.clone());
+    let batch_id = jobs[0].batch_id.clone().unwrap_or_default();
+    let job_ids = enqueue_download_jobs(&app, state.inner(), jobs)?;
+    Ok(PlaylistEnqueueResult {
+        batch_id,
+        playlist_title: playlist.title,
+        job_ids,
+    })
+}
 
-    let mut command = Command::new(&yt_dlp);
-    command.args(args);
-    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+fn build_playlist_jobs(
+    state: &AppState,
+    request: &DownloadRequest,
+    playlist: PlaylistResponse,
+    options: &PlaylistEnqueueOptions,
+) -> Result<Vec<DownloadJob>, String> {
+    if request.video_format_id.is_some() || request.audio_format_id.is_some() {
+        return Err("Format ids only apply to a single video, not to playlists".to_string());
+    }
+    let batch_id = Uuid::new_v4().to_string();
+    let entries = select_playlist_entries(playlist.entries, options)?;
 
-    let child = command.spawn().map_err(|e| format!("Spawn failed: {e}"))?;
-    let child = Arc::new(Mutex::new(child));
+    entries
+        .into_iter()
+        .map(|entry| {
+            let mut job = build_download_job(
+                state,
+                DownloadRequest {
+                    url: entry.url,
+                    cut_start_time: None,
+                    cut_end_time: None,
+                    ranges: Vec::new(),
+                    title: entry.title,
+                    uploader: entry.uploader.or_else(|| playlist.uploader.clone()),
+                    thumbnail: entry.thumbnail,
+                    upload_date: None,
+                    timestamp: None,
+                    duration_seconds: entry.duration,
+                    ..request.clone()
+                },
+            )?;
+            job.batch_id = Some(batch_id.clone());
+            job.playlist_title = playlist.title.clone();
+            job.playlist_index = Some(entry.index);
+            job.playlist_in_output_template = options.include_playlist_in_filename;
+            Ok(job)
+        })
+        .collect()
+}
 
+#[tauri::command]
+fn list_subscriptions(state: State<AppState>) -> Result<Vec<Subscription>, String> {
+    list_subscriptions_from_db(state.inner())
+}
+
+#[tauri::command]
+fn add_subscription(
+    app: AppHandle,
+    state: State<AppState>,
+    request: SubscriptionRequest,
+) -> Result<Subscription, String> {
+    let url = request.url.trim().to_string();
+    if !is_valid_url(&url) {
+        return Err("URL must start with http:// or https://".to_string());
+    }
+    let key = download_dedupe_key(&url);
+    if list_subscriptions_from_db(state.inner())?
+        .iter()
+        .any(|subscription| download_dedupe_key(&subscription.url) == key)
     {
-        let mut child_guard = state
-            .current_child
-            .lock()
-            .map_err(|_| "Child lock poisoned")?;
-        *child_guard = Some(child.clone());
+        return Err("Already subscribed to this URL".to_string());
     }
 
-    let (stdout, stderr) = {
-        let mut guard = child.lock().map_err(|_| "Child lock poisoned")?;
-        (guard.stdout.take(), guard.stderr.take())
+    let subscription = Subscription {
+        id: Uuid::new_v4().to_string(),
+        url,
+        title: None,
+        preset_key: download_preset_for_key(state.inner(), request.preset_key.as_deref())?.key,
+        output_dir: trim_optional_string(request.output_dir),
+        filters: normalize_subscription_filters(request.filters),
+        paused: false,
+        last_checked_at: None,
+        last_error: None,
+        created_at: current_timestamp_millis(),
     };
+    insert_subscription_in_db(state.inner(), &subscription)?;
+    emit_subscriptions(&app, state.inner());
 
-    let progress_re = Regex::new(r"\[download\]\s+([\d\.]+)%.*?at\s+([^\s]+).*?ETA\s+([^\s]+)")
-        .map_err(|e| format!("Regex error: {e}"))?;
-    let output_path_capture: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let id = subscription.id.clone();
+    let app_handle = app.clone();
+    thread::spawn(move || {
+        let state = app_handle.state::<AppState>();
+        if let Err(err) = check_subscription(&app_handle, &state, &id) {
+            eprintln!("Subscription check failed: {err}");
+        }
+    });
+    Ok(subscription)
+}
 
-    let app_stdout = app.clone();
-    let id_stdout = job.id.clone();
-    let output_path_for_stdout = output_path_capture.clone();
-    let handle_out = thread::spawn(move || {
-        if let Some(out) = stdout {
-            let reader = BufReader::new(out);
-            for line in reader.lines().flatten() {
-                emit_log(
-                    &app_stdout,
-                    LogEvent {
-                        id: id_stdout.clone(),
-                        line: line.clone(),
-                        is_error: false,
-                    },
-                );
+#[tauri::command]
+fn remove_subscription(app: AppHandle, state: State<AppState>, id: String) -> Result<(), String> {
+    delete_subscription_from_db(state.inner(), &id)?;
+    emit_subscriptions(&app, state.inner());
+    Ok(())
+}
 
-                if let Some(caps) = progress_re.captures(&line) {
-                    let percent = caps.get(1).and_then(|m| m.as_str().parse::<f32>().ok());
-                    let speed = caps.get(2).map(|m| m.as_str().to_string());
-                    let eta = caps.get(3).map(|m| m.as_str().to_string());
-                    emit_progress(
-                        &app_stdout,
-                        DownloadProgress {
-                            id: id_stdout.clone(),
-                            percent,
-                            speed,
-                            eta,
-                        },
-                    );
-                }
+#[tauri::command]
+fn set_subscription_paused(
+    app: AppHandle,
+    state: State<AppState>,
+    id: String,
+    paused: bool,
+) -> Result<Subscription, String> {
+    update_subscription_paused_in_db(state.inner(), &id, paused)?;
+    emit_subscriptions(&app, state.inner());
+    get_subscription_from_db(state.inner(), &id)
+}
 
-                if let Some(path_line) = parse_yt_dlp_filepath(&line) {
-                    if let Ok(mut slot) = output_path_for_stdout.lock() {
-                        slot.push(path_line);
-                    }
-                }
+#[tauri::command]
+async fn check_subscription_now(
+    app: AppHandle,
+    id: String,
+) -> Result<SubscriptionCheckResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        check_subscription(&app, &state, &id)
+    })
+    .await
+    .map_err(|e| format!("Subscription task failed: {e}"))?
+}
+
+fn spawn_subscription_poller(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(SUBSCRIPTION_POLL_INTERVAL);
+        let state = app.state::<AppState>();
+        let subscriptions = match list_subscriptions_from_db(&state) {
+            Ok(subscriptions) => subscriptions,
+            Err(err) => {
+                eprintln!("Subscription poll failed: {err}");
+                continue;
             }
-        }
-    });
-
-    let app_stderr = app.clone();
-    let id_stderr = job.id.clone();
-    let handle_err = thread::spawn(move || {
-        if let Some(err) = stderr {
-            let reader = BufReader::new(err);
-            for line in reader.lines().flatten() {
-                emit_log(
-                    &app_stderr,
-                    LogEvent {
-                        id: id_stderr.clone(),
-                        line,
-                        is_error: true,
-                    },
-                );
+        };
+        let now = current_timestamp_millis();
+        for subscription in subscriptions {
+            if !is_subscription_due(&subscription, now) {
+                continue;
+            }
+            if let Err(err) = check_subscription(&app, &state, &subscription.id) {
+                eprintln!("Subscription check failed: {err}");
             }
         }
     });
+}
 
-    let status = loop {
-        let maybe_status = {
-            let mut guard = child.lock().map_err(|_| "Child lock poisoned")?;
-            guard.try_wait().map_err(|e| format!("Wait failed: {e}"))?
-        };
-
-        if let Some(status) = maybe_status {
-            break status;
-        }
+fn is_subscription_due(subscription: &Subscription, now_millis: u64) -> bool {
+    !subscription.paused
+        && subscription.last_checked_at.is_none_or(|checked_at| {
+            now_millis.saturating_sub(checked_at) >= SUBSCRIPTION_CHECK_INTERVAL_MILLIS
+        })
+}
 
-        thread::sleep(Duration::from_millis(100));
-    };
+// Lists the newest entries of the subscribed playlist or channel and enqueues
+// the ones that are neither downloaded, queued nor recorded as failed.
+fn check_subscription(
+    app: &AppHandle,
+    state: &AppState,
+    id: &str,
+) -> Result<SubscriptionCheckResult, String> {
     {
-        let mut child_guard = state
-            .current_child
+        let mut checks = state
+            .subscription_checks
             .lock()
-            .map_err(|_| "Child lock poisoned")?;
-        *child_guard = None;
-    }
-    let _ = handle_out.join();
-    let _ = handle_err.join();
-
-    let mut output_path = output_path_capture
-        .lock()
-        .ok()
-        .and_then(|guard| select_existing_output_path(&guard));
-
-    if status.success() {
-        if output_path.is_none() {
-            output_path = resolve_existing_output_path_fallback(
-                job,
-                &yt_dlp,
-                deno_path.as_deref(),
-                &output_template_for_fallback,
-            );
+            .map_err(|_| "Subscription lock poisoned")?;
+        if !checks.insert(id.to_string()) {
+            return Err("Subscription check already running".to_string());
         }
+    }
+    let result = run_subscription_check(app, state, id);
+    if let Ok(mut checks) = state.subscription_checks.lock() {
+        checks.remove(id);
+    }
 
-        if let Some(cut_start_time) = job.cut_start_time {
-            let trimmed_path = trim_downloaded_file(
-                app,
-                job,
-                output_path.as_deref(),
-                ffmpeg_location.as_deref(),
-                cut_start_time,
-            )?;
-            output_path = Some(trimmed_path);
+    let checked_at = current_timestamp_millis();
+    let recorded = match &result {
+        Ok((_, title)) => {
+            record_subscription_check_in_db(state, id, checked_at, title.as_deref(), None)
         }
+        Err(err) => record_subscription_check_in_db(state, id, checked_at, None, Some(err)),
+    };
+    if let Err(err) = recorded {
+        eprintln!("{err}");
     }
-
-    Ok(DownloadRunResult {
-        exit_code: status.code().unwrap_or(-1),
-        output_path,
-    })
+    emit_subscriptions(app, state);
+    result.map(|(result, _)| result)
 }
 
-fn trim_downloaded_file(
+fn run_subscription_check(
     app: &AppHandle,
-    job: &DownloadJob,
-    output_path: Option<&str>,
-    ffmpeg_location: Option<&str>,
-    cut_start_time: f64,
-) -> Result<String, String> {
-    let input_path = output_path
-        .ok_or_else(|| "Could not determine downloaded file path for timestamp cut".to_string())?;
-    let input_path = Path::new(input_path);
-    if !input_path.exists() {
-        return Err(format!(
-            "Downloaded file not found for timestamp cut: {}",
-            input_path.to_string_lossy()
-        ));
-    }
+    state: &AppState,
+    id: &str,
+) -> Result<(SubscriptionCheckResult, Option<String>), String> {
+    let subscription = get_subscription_from_db(state, id)?;
+    let preset = download_preset_for_key(state, Some(&subscription.preset_key))?;
+    let yt_dlp = resolve_yt_dlp(app, state)?;
+    let deno = resolve_deno_executable(app);
+    let cookies = resolve_cookies_path(state, &subscription.url);
+    let playlist = load_playlist_with_yt_dlp(
+        yt_dlp,
+        deno,
+        cookies,
+        subscription.url.clone(),
+        Some(subscription.filters.max_items_per_check),
+    )?;
+    let found = playlist.entries.len();
 
-    let ffmpeg_location =
-        ffmpeg_location.ok_or_else(|| "ffmpeg not available for timestamp cut".to_string())?;
-    let ffmpeg_path = Path::new(ffmpeg_location).join(ffmpeg_tool_name());
-    if !ffmpeg_path.exists() {
-        return Err(format!(
-            "ffmpeg executable not found for timestamp cut: {}",
-            ffmpeg_path.to_string_lossy()
-        ));
-    }
+    let mut known_keys = known_download_keys(state)?;
+    let entries =
+        select_subscription_entries(playlist.entries, &subscription.filters, &mut known_keys);
+    let jobs = entries
+        .into_iter()
+        .map(|entry| {
+            build_download_job(
+                state,
+                build_subscription_download_request(
+                    &subscription,
+                    &preset,
+                    entry,
+                    playlist.uploader.as_deref(),
+                ),
+            )
+        })
+        .collect::<Result<Vec<_>, String>>()?;
 
-    let cut_timestamp = format_yt_dlp_timestamp(cut_start_time);
-    emit_progress(
-        app,
-        DownloadProgress {
-            id: job.id.clone(),
-            percent: Some(100.0),
-            speed: Some("cutting".to_string()),
-            eta: Some("-".to_string()),
-        },
-    );
-    emit_log(
-        app,
-        LogEvent {
-            id: job.id.clone(),
-            line: format!("[cut] trimming local file from {cut_timestamp}s"),
-            is_error: false,
+    let added = jobs.len();
+    enqueue_download_jobs(app, state, jobs)?;
+    Ok((
+        SubscriptionCheckResult {
+            subscription_id: subscription.id,
+            found,
+            added,
+            skipped: found - added,
         },
-    );
-
-    let final_path = build_timestamp_cut_output_path(input_path, cut_start_time)?;
-    let temp_path = build_cut_sidecar_path(input_path, "cut")?;
-    let backup_path = build_cut_sidecar_path(input_path, "original")?;
-
-    let input_path_str = input_path.to_string_lossy().to_string();
-    let temp_path_str = temp_path.to_string_lossy().to_string();
+        playlist.title.or(playlist.uploader),
+    ))
+}
 
-    let output = Command::new(&ffmpeg_path)
-        .args([
-            "-hide_banner",
-            "-y",
-            "-ss",
-            cut_timestamp.as_str(),
-            "-i",
-            input_path_str.as_str(),
-            "-map",
-            "0",
-            "-c",
-            "copy",
-            "-avoid_negative_ts",
-            "make_zero",
-            temp_path_str.as_str(),
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run ffmpeg timestamp cut: {e}"))?;
+fn select_subscription_entries(
+    entries: Vec<PlaylistEntry>,
+    filters: &SubscriptionFilters,
+    known_keys: &mut HashSet<String>,
+) -> Vec<PlaylistEntry> {
+    entries
+        .into_iter()
+        .take(filters.max_items_per_check as usize)
+        .filter(|entry| subscription_entry_matches(entry, filters))
+        .filter(|entry| known_keys.insert(download_dedupe_key(&entry.url)))
+        .collect()
+}
 
-    for line in String::from_utf8_lossy(&output.stdout).lines() {
-        emit_log(
-            app,
-            LogEvent {
-                id: job.id.clone(),
-                line: format!("[ffmpeg] {line}"),
-                is_error: false,
-            },
-        );
+fn subscription_entry_matches(entry: &PlaylistEntry, filters: &SubscriptionFilters) -> bool {
+    let title = entry.title.as_deref().unwrap_or_default().to_lowercase();
+    if let Some(includes) = filters.title_includes.as_deref() {
+        if !title.contains(&includes.to_lowercase()) {
+            return false;
+        }
     }
-    for line in String::from_utf8_lossy(&output.stderr).lines() {
-        emit_log(
-            app,
-            LogEvent {
-                id: job.id.clone(),
-                line: format!("[ffmpeg] {line}"),
-                is_error: !output.status.success(),
-            },
-        );
+    if let Some(excludes) = filters.title_excludes.as_deref() {
+        if title.contains(&excludes.to_lowercase()) {
+            return false;
+        }
     }
+    match entry.duration {
+        Some(duration) => {
+            filters
+                .min_duration_seconds
+                .is_none_or(|min| duration >= min)
+                && filters
+                    .max_duration_seconds
+                    .is_none_or(|max| duration <= max)
+        }
+        None => true,
+    }
+}
+
+fn normalize_subscription_filters(mut filters: SubscriptionFilters) -> SubscriptionFilters {
+    filters.title_includes = trim_optional_string(filters.title_includes);
+    filters.title_excludes = trim_optional_string(filters.title_excludes);
+    filters.min_duration_seconds = filters.min_duration_seconds.filter(|value| *value > 0);
+    filters.max_duration_seconds = filters.max_duration_seconds.filter(|value| *value > 0);
+    filters.max_items_per_check = filters
+        .max_items_per_check
+        .clamp(1, SUBSCRIPTION_MAX_ITEMS_LIMIT);
+    filters
+}
+
+fn build_subscription_download_request(
+    subscription: &Subscription,
+    preset: &Preset,
+    entry: PlaylistEntry,
+    playlist_uploader: Option<&str>,
+) -> DownloadRequest {
+    DownloadRequest {
+        url: entry.url,
+        format: preset.format.clone(),
+        output_dir: subscription.output_dir.clone(),
+        extract_audio: preset.extract_audio,
+        audio_format: preset.audio_format.clone(),
+        transcribe_text: preset.transcribe_text,
+        cut_at_timestamp_enabled: false,
+        cut_start_time: None,
+        cut_end_time: None,
+        ranges: Vec::new(),
+        cut_mode: CutMode::default(),
+        cut_precision: None,
+        video_format_id: None,
+        audio_format_id: None,
+        output_template: preset.output_template.clone(),
+        filename_suffix: preset.filename_suffix.clone(),
+        priority: 0,
+        not_before: None,
+        rate_limit_kib: None,
+        title: entry.title,
+        uploader: entry
+            .uploader
+            .or_else(|| playlist_uploader.map(str::to_string)),
+        thumbnail: entry.thumbnail,
+        upload_date: None,
+        timestamp: None,
+        duration_seconds: entry.duration,
+        force_download: false,
+        subtitles: preset.subtitles.clone(),
+        embed: preset.embed,
+        sponsorblock: preset.sponsorblock.clone(),
+        split_chapters: false,
+    }
+}
+
+// YouTube links share one key regardless of the URL form they were added with.
+fn download_dedupe_key(url: &str) -> String {
+    normalize_youtube_url(url)
+        .map(|normalized| normalized.key)
+        .unwrap_or_else(|| url.trim().to_string())
+}
+
+// Failed and cancelled jobs are not "seen", so a later check can pick them up
+// again. Running jobs have already left the queue but still count.
+fn known_download_keys(state: &AppState) -> Result<HashSet<String>, String> {
+    let mut keys = {
+        let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+        list_history_urls_from_conn(&conn)
+            .map_err(|e| format!("History read failed: {e}"))?
+            .iter()
+            .map(|url| download_dedupe_key(url))
+            .collect::<HashSet<_>>()
+    };
+    {
+        let queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        keys.extend(queue.iter().map(|job| download_dedupe_key(&job.url)));
+    }
+    let running = state
+        .running_jobs
+        .lock()
+        .map_err(|_| "Running jobs lock poisoned")?;
+    keys.extend(running.values().map(|job| download_dedupe_key(&job.url)));
+    Ok(keys)
+}
 
-    if !output.status.success() {
-        let _ = fs::remove_file(&temp_path);
-        let code = output.status.code().unwrap_or(-1);
-        return Err(format!("ffmpeg timestamp cut failed with exit code {code}"));
+fn emit_subscriptions(app: &AppHandle, state: &AppState) {
+    if let Ok(subscriptions) = list_subscriptions_from_db(state) {
+        let _ = app.emit_all("subscriptions:update", subscriptions);
     }
+}
 
-    if !temp_path.exists() {
-        return Err("ffmpeg finished but no cut file was created".to_string());
-    }
+#[tauri::command]
+fn list_cookie_profiles(state: State<AppState>) -> Result<Vec<CookieProfile>, String> {
+    list_cookie_profiles_from_db(state.inner())
+}
 
-    fs::rename(input_path, &backup_path)
-        .map_err(|e| format!("Could not back up full file before cut replace: {e}"))?;
-    if final_path.exists() {
-        let _ = fs::remove_file(&final_path);
-    }
-    if let Err(err) = fs::rename(&temp_path, &final_path) {
-        let _ = fs::rename(&backup_path, input_path);
-        return Err(format!("Could not move cut file into final path: {err}"));
-    }
-    let _ = fs::remove_file(&backup_path);
+#[tauri::command]
+fn save_cookie_profile(
+    state: State<AppState>,
+    request: CookieProfileRequest,
+) -> Result<Vec<CookieProfile>, String> {
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Cookie profile name is required".to_string());
+    }
+    let cookies_path = canonical_existing_local_path(&request.cookies_path)?
+        .ok_or_else(|| "Cookies file not found".to_string())?;
+    let summary = read_netscape_cookies_file(&cookies_path)?;
+
+    let mut host_patterns = request
+        .host_patterns
+        .iter()
+        .filter_map(|pattern| normalize_cookie_host_pattern(pattern))
+        .collect::<Vec<_>>();
+    if host_patterns.is_empty() {
+        host_patterns = summary.domains;
+    }
+    let mut seen_patterns = HashSet::new();
+    host_patterns.retain(|pattern| seen_patterns.insert(pattern.clone()));
+
+    let profile = CookieProfile {
+        id: request.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        name,
+        cookies_path,
+        host_patterns,
+        created_at: current_timestamp_millis(),
+    };
+    upsert_cookie_profile_in_db(state.inner(), &profile)?;
+    list_cookie_profiles_from_db(state.inner())
+}
 
-    emit_log(
-        app,
-        LogEvent {
-            id: job.id.clone(),
-            line: format!("[cut] saved: {}", final_path.to_string_lossy()),
-            is_error: false,
-        },
-    );
+#[tauri::command]
+fn delete_cookie_profile(state: State<AppState>, id: String) -> Result<Vec<CookieProfile>, String> {
+    delete_cookie_profile_from_db(state.inner(), &id)?;
+    list_cookie_profiles_from_db(state.inner())
+}
 
-    Ok(final_path.to_string_lossy().to_string())
+#[tauri::command]
+fn validate_cookies_file(path: String) -> Result<CookieFileSummary, String> {
+    let path = canonical_existing_local_path(&path)?
+        .ok_or_else(|| "Cookies file not found".to_string())?;
+    read_netscape_cookies_file(&path)
 }
 
-fn build_cut_sidecar_path(input_path: &Path, label: &str) -> Result<PathBuf, String> {
-    let parent = input_path
-        .parent()
-        .ok_or_else(|| "Downloaded file has no parent directory".to_string())?;
-    let extension = input_path
-        .extension()
-        .and_then(|value| value.to_str())
-        .filter(|value| !value.is_empty())
-        .unwrap_or("tmp");
-    Ok(parent.join(format!(
-        ".pinefetch-{label}-{}.{}",
-        Uuid::new_v4(),
-        extension
-    )))
+#[tauri::command]
+async fn pick_cookies_file() -> Result<Option<String>, String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    tauri::api::dialog::FileDialogBuilder::new()
+        .add_filter("Cookies", &["txt"])
+        .pick_file(move |path| {
+            let _ = tx.send(path.map(|p| p.to_string_lossy().to_string()));
+        });
+    tauri::async_runtime::spawn_blocking(move || rx.recv())
+        .await
+        .map_err(|_| "Dialog task failed".to_string())?
+        .map_err(|_| "Dialog closed".to_string())
 }
 
-fn build_timestamp_cut_output_path(
-    input_path: &Path,
-    cut_start_time: f64,
-) -> Result<PathBuf, String> {
-    let parent = input_path
-        .parent()
-        .ok_or_else(|| "Downloaded file has no parent directory".to_string())?;
-    let stem = input_path
-        .file_stem()
-        .and_then(|value| value.to_str())
-        .ok_or_else(|| "Downloaded file has no usable file name".to_string())?;
-    let cut_suffix = format_timestamp_filename_suffix(cut_start_time);
+fn read_netscape_cookies_file(path: &str) -> Result<CookieFileSummary, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Cookies file could not be read: {e}"))?;
+    parse_netscape_cookies(&content)
+}
 
-    let mut filename = format!("{stem}{cut_suffix}");
-    if let Some(extension) = input_path.extension().and_then(|value| value.to_str()) {
-        if !extension.is_empty() {
-            filename.push('.');
-            filename.push_str(extension);
-        }
+// Mirrors the checks yt-dlp applies when loading `--cookies`: the Netscape
+// header, then seven tab-separated fields per cookie line.
+fn parse_netscape_cookies(content: &str) -> Result<CookieFileSummary, String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let header = content.lines().next().unwrap_or_default().trim();
+    if !(header.starts_with("# Netscape HTTP Cookie File")
+        || header.starts_with("# HTTP Cookie File"))
+    {
+        return Err(
+            "Not a Netscape cookies file: missing \"# Netscape HTTP Cookie File\" header"
+                .to_string(),
+        );
     }
 
-    Ok(parent.join(filename))
-}
+    let mut cookie_count = 0;
+    let mut domains = Vec::new();
+    for (index, raw_line) in content.lines().enumerate().skip(1) {
+        let line = raw_line.trim_end_matches('\r');
+        let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
 
-fn format_timestamp_filename_suffix(seconds: f64) -> String {
-    let mut timestamp = format_yt_dlp_timestamp(seconds);
-    timestamp = timestamp
-        .chars()
-        .map(|character| {
-            if character.is_ascii_alphanumeric() {
-                character
-            } else {
-                '_'
-            }
-        })
-        .collect();
-    format!("_t{timestamp}")
-}
+        let fields = line.split('\t').collect::<Vec<_>>();
+        let line_number = index + 1;
+        if fields.len() != 7 {
+            return Err(format!(
+                "Line {line_number}: expected 7 tab-separated fields, found {}",
+                fields.len()
+            ));
+        }
+        if !matches!(fields[1], "TRUE" | "FALSE") || !matches!(fields[3], "TRUE" | "FALSE") {
+            return Err(format!("Line {line_number}: flags must be TRUE or FALSE"));
+        }
+        if !fields[4].is_empty() && fields[4].parse::<i64>().is_err() {
+            return Err(format!(
+                "Line {line_number}: invalid expiry \"{}\"",
+                fields[4]
+            ));
+        }
 
-fn select_existing_output_path(candidates: &[String]) -> Option<String> {
-    let existing = candidates
-        .iter()
-        .enumerate()
-        .filter_map(|(index, candidate)| {
-            let path = Path::new(candidate.as_str());
-            let metadata = path.metadata().ok()?;
-            if !metadata.is_file() {
-                return None;
+        cookie_count += 1;
+        if let Some(domain) = normalize_cookie_host_pattern(fields[0]) {
+            if !domains.contains(&domain) {
+                domains.push(domain);
             }
-            Some((index, candidate, metadata.len(), is_format_part_path(path)))
-        })
-        .collect::<Vec<_>>();
-
-    if let Some((_, candidate, _, _)) = existing.iter().rev().find(|(_, _, _, is_part)| !*is_part) {
-        return Some((*candidate).clone());
+        }
     }
 
-    existing
-        .into_iter()
-        .max_by(|left, right| left.2.cmp(&right.2).then_with(|| left.0.cmp(&right.0)))
-        .map(|(_, candidate, _, _)| candidate.clone())
+    if cookie_count == 0 {
+        return Err("Cookies file contains no cookies".to_string());
+    }
+    domains.sort();
+    Ok(CookieFileSummary {
+        cookie_count,
+        domains,
+    })
 }
 
-fn is_format_part_path(path: &Path) -> bool {
-    path.file_stem()
-        .and_then(|value| value.to_str())
-        .and_then(|stem| stem.rsplit_once(".f"))
-        .map(|(_, suffix)| !suffix.is_empty() && suffix.chars().all(|ch| ch.is_ascii_digit()))
-        .unwrap_or(false)
+fn normalize_cookie_host_pattern(raw: &str) -> Option<String> {
+    let pattern = raw
+        .trim()
+        .trim_start_matches("*.")
+        .trim_start_matches('.')
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    let pattern = pattern.strip_prefix("www.").unwrap_or(&pattern);
+    (!pattern.is_empty() && !pattern.contains(['/', ' ', '*'])).then(|| pattern.to_string())
 }
 
-fn parse_yt_dlp_filepath(line: &str) -> Option<String> {
-    let trimmed = line.trim();
-    if trimmed.is_empty() {
-        return None;
-    }
+// The most specific matching pattern wins, so a profile for
+// `music.youtube.com` takes precedence over one for `youtube.com`.
+fn select_cookie_profile(profiles: Vec<CookieProfile>, url: &str) -> Option<CookieProfile> {
+    let host = url::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+    profiles
+        .into_iter()
+        .filter_map(|profile| {
+            let best_match = profile
+                .host_patterns
+                .iter()
+                .filter(|pattern| {
+                    host == **pattern
+                        || host
+                            .strip_suffix(pattern.as_str())
+                            .is_some_and(|prefix| prefix.ends_with('.'))
+                })
+                .map(String::len)
+                .max()?;
+            Some((best_match, profile))
+        })
+        .max_by_key(|(best_match, _)| *best_match)
+        .map(|(_, profile)| profile)
+}
 
-    for prefix in [
-        "[download] Destination:",
-        "[ExtractAudio] Destination:",
-        "[Metadata] Writing metadata to:",
-    ] {
-        if let Some(candidate) = trimmed.strip_prefix(prefix) {
-            return normalize_filepath_candidate(candidate);
-        }
-    }
+fn cookie_profile_for_url(state: &AppState, url: &str) -> Result<Option<CookieProfile>, String> {
+    Ok(select_cookie_profile(
+        list_cookie_profiles_from_db(state)?,
+        url,
+    ))
+}
 
-    if let Some(candidate) = trimmed.strip_prefix("[Merger] Merging formats into ") {
-        return normalize_filepath_candidate(candidate);
-    }
+// yt-dlp saves the cookie jar back to `--cookies` when it exits, so every run
+// works on its own copy and parallel runs never write to the profile's file.
+struct CookiesCopy {
+    path: PathBuf,
+}
 
-    if let Some(candidate) = trimmed
-        .strip_prefix("[download] ")
-        .and_then(|value| value.strip_suffix(" has already been downloaded"))
-    {
-        return normalize_filepath_candidate(candidate);
+impl CookiesCopy {
+    fn create(source: &str) -> Result<Self, String> {
+        let path = std::env::temp_dir().join(format!("pinefetch-cookies-{}.txt", Uuid::new_v4()));
+        fs::copy(source, &path).map_err(|e| format!("Cookies copy failed: {e}"))?;
+        Ok(Self { path })
     }
 
-    if trimmed.starts_with('[') {
-        return None;
+    fn path(&self) -> String {
+        self.path.to_string_lossy().to_string()
     }
-
-    normalize_filepath_candidate(trimmed)
 }
 
-fn normalize_filepath_candidate(raw: &str) -> Option<String> {
-    let mut candidate = raw.trim();
-    if candidate.len() >= 2 {
-        let bytes = candidate.as_bytes();
-        if (bytes[0] == b'"' && bytes[candidate.len() - 1] == b'"')
-            || (bytes[0] == b'\'' && bytes[candidate.len() - 1] == b'\'')
-        {
-            candidate = &candidate[1..candidate.len() - 1];
-        }
-    }
-    let candidate = candidate.trim();
-    if matches!(candidate, "" | "NA" | "N/A" | "None" | "null") {
-        return None;
-    }
-    if candidate.starts_with("http://") || candidate.starts_with("https://") {
-        return None;
+impl Drop for CookiesCopy {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
     }
-    Some(candidate.to_string())
 }
 
-fn resolve_existing_output_path_fallback(
-    job: &DownloadJob,
-    yt_dlp: &str,
-    deno_path: Option<&str>,
-    output_template: &str,
-) -> Option<String> {
-    let expected_path =
-        probe_expected_output_filename(job, yt_dlp, deno_path, output_template).ok()??;
-    let candidates = existing_output_candidates_from_expected(&expected_path, job);
-    select_existing_output_path(&candidates)
+fn resolve_cookies_path(state: &AppState, url: &str) -> Option<String> {
+    cookie_profile_for_url(state, url)
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            None
+        })
+        .map(|profile| profile.cookies_path)
 }
 
-fn probe_expected_output_filename(
-    job: &DownloadJob,
-    yt_dlp: &str,
-    deno_path: Option<&str>,
-    output_template: &str,
-) -> Result<Option<String>, String> {
-    let mut command = Command::new(yt_dlp);
-    command.args([
-        "--simulate",
-        "--no-playlist",
-        "--no-warnings",
-        "--print",
-        "filename",
-        "-f",
-    ]);
-    command.arg(&job.format);
-    command.arg("-o");
-    command.arg(output_template);
+#[tauri::command]
+fn cancel_download(app: AppHandle, state: State<AppState>, id: String) -> Result<(), String> {
+    if let Some(cancelled) = cancel_queued_job(state.inner(), &id)? {
+        emit_queue(&app, &state)?;
+        set_job_state(&app, state.inner(), &id, cancelled);
+        return Ok(());
+    }
 
-    if let Some(deno) = deno_path {
-        command.arg("--js-runtimes");
-        command.arg(format!("deno:{deno}"));
+    let child = {
+        let running = state
+            .running_jobs
+            .lock()
+            .map_err(|_| "Running jobs lock poisoned")?;
+        let Some(running_job) = running.get(&id) else {
+            return Err("Job not found in queue".to_string());
+        };
+        running_job.child.clone()
+    };
+
+    {
+        let mut cancel = state
+            .cancel_requested
+            .lock()
+            .map_err(|_| "Cancel lock poisoned")?;
+        cancel.insert(id.clone());
     }
 
-    if job.extract_audio {
-        command.arg("--extract-audio");
-        if let Some(fmt) = job.audio_format.as_ref() {
-            command.arg("--audio-format");
-            command.arg(fmt);
+    if let Some(child) = child {
+        if let Ok(mut guard) = child.lock() {
+            let _ = guard.kill();
         }
     }
 
-    command.arg(&job.url);
-
-    let output = command
-        .output()
-        .map_err(|e| format!("Failed to probe output filename with yt-dlp: {e}"))?;
+    set_job_state(
+        &app,
+        state.inner(),
+        &id,
+        JobState::Cancelling {
+            requested_at: current_timestamp_millis(),
+        },
+    );
+    Ok(())
+}
 
-    if !output.status.success() {
+// Queued and retry-waiting jobs never reach a worker, so they are recorded as
+// cancelled here to stay requeueable.
+fn cancel_queued_job(state: &AppState, id: &str) -> Result<Option<JobState>, String> {
+    let removed = {
+        let mut queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        queue
+            .iter()
+            .position(|job| job.id == id)
+            .and_then(|index| queue.remove(index))
+    };
+    let Some(job) = removed else {
         return Ok(None);
-    }
+    };
 
-    Ok(String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .rev()
-        .find_map(parse_yt_dlp_filepath))
+    delete_queue_job_from_db(state, id)?;
+    let cancelled = JobState::Cancelled {
+        cancelled_at: current_timestamp_millis(),
+    };
+    record_failed_job_in_db(state, &job, &cancelled)?;
+    Ok(Some(cancelled))
 }
 
-fn existing_output_candidates_from_expected(expected_path: &str, job: &DownloadJob) -> Vec<String> {
-    let mut candidates = vec![expected_path.to_string()];
-    let expected = Path::new(expected_path);
-
-    if job.extract_audio {
-        if let Some(audio_format) = job.audio_format.as_deref() {
-            candidates.push(
-                expected
-                    .with_extension(audio_format)
-                    .to_string_lossy()
-                    .to_string(),
-            );
-        }
-    }
+#[tauri::command]
+fn list_failed_jobs(state: State<AppState>) -> Result<Vec<FailedJob>, String> {
+    list_failed_jobs_from_db(state.inner(), None)
+}
 
-    if let Some(cut_start_time) = job.cut_start_time {
-        for candidate in candidates.clone() {
-            if let Ok(cut_path) =
-                build_timestamp_cut_output_path(Path::new(&candidate), cut_start_time)
-            {
-                candidates.push(cut_path.to_string_lossy().to_string());
-            }
-        }
+#[tauri::command]
+fn requeue_failed_job(app: AppHandle, state: State<AppState>, id: String) -> Result<(), String> {
+    let failed = list_failed_jobs_from_db(state.inner(), Some(&id))?;
+    if failed.is_empty() {
+        return Err("Failed job not found".to_string());
     }
+    requeue_failed_jobs(&app, state.inner(), failed)?;
+    Ok(())
+}
 
-    candidates.extend(related_existing_output_paths(expected));
-    candidates.sort();
-    candidates.dedup();
-    candidates
+#[tauri::command]
+fn requeue_all_failed(app: AppHandle, state: State<AppState>) -> Result<Vec<String>, String> {
+    let failed = list_failed_jobs_from_db(state.inner(), None)?;
+    requeue_failed_jobs(&app, state.inner(), failed)
 }
 
-fn related_existing_output_paths(expected: &Path) -> Vec<String> {
-    let Some(parent) = expected.parent() else {
-        return Vec::new();
-    };
-    let Some(expected_stem) = expected.file_stem().and_then(|value| value.to_str()) else {
-        return Vec::new();
-    };
-    let format_part_prefix = format!("{expected_stem}.f");
+#[tauri::command]
+fn delete_failed_job(state: State<AppState>, id: String) -> Result<(), String> {
+    delete_failed_jobs_from_db(state.inner(), &[id])
+}
 
-    fs::read_dir(parent)
-        .ok()
+// The rows are only removed once the jobs are back in the queue, so a failed
+// enqueue leaves them requeueable.
+fn requeue_failed_jobs(
+    app: &AppHandle,
+    state: &AppState,
+    failed: Vec<FailedJob>,
+) -> Result<Vec<String>, String> {
+    let ids = failed
+        .iter()
+        .map(|failed| failed.id.clone())
+        .collect::<Vec<_>>();
+    let jobs = failed
         .into_iter()
-        .flat_map(|entries| entries.flatten())
-        .filter_map(|entry| {
-            let path = entry.path();
-            if !path.is_file() {
-                return None;
-            }
-            let stem = path.file_stem().and_then(|value| value.to_str())?;
-            if stem == expected_stem || stem.starts_with(&format_part_prefix) {
-                Some(path.to_string_lossy().to_string())
-            } else {
-                None
-            }
+        .map(|failed| DownloadJob {
+            paused: false,
+            retry_count: 0,
+            retry_at: None,
+            ..failed.job
         })
-        .collect()
-}
-
-fn ffmpeg_tool_name() -> &'static str {
-    if cfg!(windows) {
-        "ffmpeg.exe"
-    } else {
-        "ffmpeg"
-    }
+        .collect::<Vec<_>>();
+    let queued = enqueue_download_jobs(app, state, jobs)?;
+    delete_failed_jobs_from_db(state, &ids)?;
+    Ok(queued)
 }
 
-fn ffprobe_tool_name() -> &'static str {
-    if cfg!(windows) {
-        "ffprobe.exe"
-    } else {
-        "ffprobe"
-    }
+#[tauri::command]
+fn get_history(
+    state: State<AppState>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<HistoryPage, String> {
+    let limit = limit.unwrap_or(50).clamp(1, 100);
+    let offset = offset.unwrap_or(0);
+    list_history_page_from_db(state.inner(), limit, offset)
 }
 
-fn ffmpeg_tool_is_usable(path: &Path) -> bool {
-    if !path.is_file() {
-        return false;
-    }
-
-    Command::new(path)
-        .arg("-version")
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map(|status| status.success())
-        .unwrap_or(false)
+#[tauri::command]
+fn get_history_stats(state: State<AppState>) -> Result<HistoryStats, String> {
+    get_history_stats_from_db(state.inner())
 }
 
-fn has_usable_ffmpeg_tools_in_dir(dir: &Path) -> bool {
-    ffmpeg_tool_is_usable(&dir.join(ffmpeg_tool_name()))
-        && ffmpeg_tool_is_usable(&dir.join(ffprobe_tool_name()))
-}
+fn detect_platform(url: &str) -> Option<String> {
+    if let Ok(parsed) = url::Url::parse(url) {
+        let host = parsed.host_str().unwrap_or("").to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
 
-fn normalize_ffmpeg_location(path: &Path) -> Option<String> {
-    if path.is_dir() {
-        if has_usable_ffmpeg_tools_in_dir(path) {
-            return Some(path.to_string_lossy().to_string());
+        if host == "youtu.be" || host.ends_with("youtube.com") {
+            return Some("youtube".to_string());
         }
-        return None;
-    }
-
-    if path.is_file() {
-        if let Some(parent) = path.parent() {
-            if has_usable_ffmpeg_tools_in_dir(parent) {
-                return Some(parent.to_string_lossy().to_string());
-            }
+        if host.ends_with("facebook.com") || host == "fb.watch" {
+            return Some("facebook".to_string());
         }
-    }
-
-    None
-}
-
-fn resolve_bundled_ffmpeg_location(app: &AppHandle) -> Option<String> {
-    for relative in [
-        "ffmpeg-runtime/bin",
-        "ffmpeg-runtime",
-        "resources/ffmpeg-runtime/bin",
-        "resources/ffmpeg-runtime",
-    ] {
-        if let Some(path) = app.path_resolver().resolve_resource(relative) {
-            if let Some(location) = normalize_ffmpeg_location(&path) {
-                return Some(location);
-            }
+        if host.ends_with("twitch.tv") {
+            return Some("twitch".to_string());
+        }
+        if host == "x.com" || host.ends_with(".x.com") || host.ends_with("twitter.com") {
+            return Some("x".to_string());
+        }
+        if host.ends_with("tiktok.com") {
+            return Some("tiktok".to_string());
+        }
+        if host.ends_with("instagram.com") || host.ends_with("instagr.am") {
+            return Some("instagram".to_string());
         }
     }
     None
 }
 
-fn resolve_ffmpeg_location(app: &AppHandle, yt_dlp_path: &str) -> Option<String> {
-    if let Ok(raw) = std::env::var("PINEFETCH_FFMPEG_LOCATION") {
-        let trimmed = raw.trim();
-        if !trimmed.is_empty() {
-            if let Some(location) = normalize_ffmpeg_location(Path::new(trimmed)) {
-                return Some(location);
-            }
-        }
-    }
-
-    if let Some(location) = resolve_bundled_ffmpeg_location(app) {
-        return Some(location);
-    }
+fn source_from_url(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let host = parsed.domain()?.trim_end_matches('.').to_ascii_lowercase();
 
-    if let Some(location) = normalize_ffmpeg_location(Path::new(yt_dlp_path)) {
-        return Some(location);
+    let canonical_short_domain = match host.as_str() {
+        "youtu.be" => Some("youtube"),
+        "fb.watch" => Some("facebook"),
+        "instagr.am" => Some("instagram"),
+        "lnkd.in" => Some("linkedin"),
+        "t.co" => Some("x"),
+        _ => None,
+    };
+    if let Some(source) = canonical_short_domain {
+        return Some(source.to_string());
     }
 
-    for candidate in ["/opt/homebrew/bin", "/usr/local/bin"] {
-        if let Some(location) = normalize_ffmpeg_location(Path::new(candidate)) {
-            return Some(location);
-        }
+    let labels = host
+        .split('.')
+        .filter(|label| !label.is_empty())
+        .collect::<Vec<_>>();
+    if labels.is_empty() {
+        return None;
     }
-
-    if let Some(ffmpeg_path) = find_in_path("ffmpeg") {
-        if let Some(location) = normalize_ffmpeg_location(Path::new(&ffmpeg_path)) {
-            return Some(location);
-        }
+    if labels.len() == 1 {
+        return Some(labels[0].to_string());
     }
 
-    if let Some(ffprobe_path) = find_in_path("ffprobe") {
-        if let Some(location) = normalize_ffmpeg_location(Path::new(&ffprobe_path)) {
-            return Some(location);
-        }
-    }
+    let common_second_level_tld = matches!(
+        labels[labels.len() - 2],
+        "ac" | "co" | "com" | "edu" | "gov" | "net" | "org"
+    );
+    let source_index = if labels.len() >= 3
+        && labels.last().is_some_and(|tld| tld.len() == 2)
+        && common_second_level_tld
+    {
+        labels.len() - 3
+    } else {
+        labels.len() - 2
+    };
 
-    None
+    Some(labels[source_index].to_string())
 }
 
-fn resolve_bundled_python(app: &AppHandle) -> Option<String> {
-    #[cfg(target_os = "windows")]
-    let candidates = vec![
-        "whisper-runtime/Scripts/python.exe",
-        "resources/whisper-runtime/Scripts/python.exe",
-    ];
-
-    #[cfg(not(target_os = "windows"))]
-    let candidates = vec![
-        "whisper-runtime/bin/python3.12",
-        "whisper-runtime/bin/python3.11",
-        "whisper-runtime/bin/python3.10",
-        "whisper-runtime/bin/python3",
-        "whisper-runtime/bin/python",
-        "resources/whisper-runtime/bin/python3.12",
-        "resources/whisper-runtime/bin/python3.11",
-        "resources/whisper-runtime/bin/python3.10",
-        "resources/whisper-runtime/bin/python3",
-        "resources/whisper-runtime/bin/python",
-    ];
+fn current_timestamp_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
 
-    for relative in candidates {
-        if let Some(path) = app.path_resolver().resolve_resource(relative) {
-            if path.exists() {
-                return Some(path.to_string_lossy().to_string());
-            }
-        }
+fn filename_from_path(path: Option<&str>) -> Option<String> {
+    let filename = Path::new(path?)
+        .file_name()
+        .and_then(|value| value.to_str())?
+        .trim();
+    if filename.is_empty() {
+        None
+    } else {
+        Some(filename.to_string())
     }
-
-    None
 }
 
-fn resolve_bundled_deno(app: &AppHandle) -> Option<String> {
-    #[cfg(target_os = "windows")]
-    let candidates = vec![
-        "deno-runtime/bin/deno.exe",
-        "resources/deno-runtime/bin/deno.exe",
-    ];
-
-    #[cfg(not(target_os = "windows"))]
-    let candidates = vec!["deno-runtime/bin/deno", "resources/deno-runtime/bin/deno"];
-
-    for relative in candidates {
-        if let Some(path) = app.path_resolver().resolve_resource(relative) {
-            if path.exists() {
-                return Some(path.to_string_lossy().to_string());
-            }
-        }
+fn title_from_filename(filename: Option<&str>) -> Option<String> {
+    let stem = Path::new(filename?)
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .unwrap_or(filename?)
+        .trim();
+    if stem.is_empty() {
+        None
+    } else {
+        Some(stem.to_string())
     }
-
-    None
 }
 
-fn resolve_deno_executable(app: &AppHandle) -> Option<String> {
-    if let Ok(raw) = std::env::var("PINEFETCH_DENO_PATH") {
-        let trimmed = raw.trim();
-        if !trimmed.is_empty() && Path::new(trimmed).exists() {
-            return Some(trimmed.to_string());
-        }
-    }
-
-    if let Some(path) = resolve_bundled_deno(app) {
-        return Some(path);
-    }
+fn trim_optional_string(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
 
-    find_in_path("deno")
+#[derive(Debug)]
+struct HydratedHistoryMetadata {
+    title: Option<String>,
+    uploader: Option<String>,
+    thumbnail: Option<String>,
+    upload_date: Option<String>,
+    timestamp: Option<i64>,
+    duration_seconds: Option<i64>,
 }
 
-fn resolve_python_executable(app: &AppHandle) -> Option<String> {
-    if let Ok(raw) = std::env::var("PINEFETCH_FASTER_WHISPER_PYTHON") {
-        let trimmed = raw.trim();
-        if !trimmed.is_empty() && Path::new(trimmed).exists() {
-            return Some(trimmed.to_string());
+fn hydrate_history_metadata(
+    app: &AppHandle,
+    state: &AppState,
+    job: &DownloadJob,
+    filename: Option<&str>,
+) -> HydratedHistoryMetadata {
+    let mut title = trim_optional_string(job.title.clone());
+    let mut uploader = trim_optional_string(job.uploader.clone());
+    let mut thumbnail = trim_optional_string(job.thumbnail.clone());
+    let mut upload_date = trim_optional_string(job.upload_date.clone());
+    let mut timestamp = job.timestamp;
+    let mut duration_seconds = job.duration_seconds;
+
+    if title.is_none()
+        || uploader.is_none()
+        || thumbnail.is_none()
+        || upload_date.is_none()
+        || timestamp.is_none()
+        || duration_seconds.is_none()
+    {
+        if let Ok(yt_dlp) = resolve_yt_dlp(app, state) {
+            let deno = resolve_deno_executable(app);
+            let cookies = resolve_cookies_path(state, &job.url);
+            if let Ok(info) = load_info_with_yt_dlp(yt_dlp, deno, cookies, job.url.clone()) {
+                if title.is_none() {
+                    title = trim_optional_string(info.title);
+                }
+                if uploader.is_none() {
+                    uploader = trim_optional_string(info.uploader);
+                }
+                if thumbnail.is_none() {
+                    thumbnail = trim_optional_string(info.thumbnail);
+                }
+                if upload_date.is_none() {
+                    upload_date = trim_optional_string(info.upload_date);
+                }
+                if timestamp.is_none() {
+                    timestamp = info.timestamp;
+                }
+                if duration_seconds.is_none() {
+                    duration_seconds = info.duration;
+                }
+            }
         }
     }
 
-    if let Some(path) = resolve_bundled_python(app) {
-        return Some(path);
+    if title.is_none() {
+        title = title_from_filename(filename);
     }
 
-    for candidate in [
-        "python3.12",
-        "python3.11",
-        "python3.10",
-        "python3",
-        "python",
-    ] {
-        if let Some(path) = find_in_path(candidate) {
-            return Some(path);
-        }
+    HydratedHistoryMetadata {
+        title,
+        uploader,
+        thumbnail,
+        upload_date,
+        timestamp,
+        duration_seconds,
     }
+}
 
-    None
+fn medium_for_job(job: &DownloadJob) -> &'static str {
+    if job.transcribe_text {
+        "transcript"
+    } else if job.extract_audio {
+        "audio"
+    } else {
+        "video"
+    }
 }
 
-fn run_faster_whisper_transcription(
+fn file_size_bytes_from_path(path: Option<&str>) -> Option<i64> {
+    let size = fs::metadata(path?).ok()?.len();
+    i64::try_from(size).ok()
+}
+
+fn add_history_entry_on_success(
     app: &AppHandle,
+    state: &AppState,
     job: &DownloadJob,
-    output_path: Option<&str>,
-) -> Result<String, String> {
-    let audio_path = output_path
-        .ok_or_else(|| "Could not determine downloaded file path for transcription".to_string())?;
-    if !Path::new(audio_path).exists() {
-        return Err(format!(
-            "Downloaded file not found for transcription: {audio_path}"
-        ));
+    run_result: &DownloadRunResult,
+) {
+    let output_path = run_result.output_path.as_deref();
+    let filename = filename_from_path(output_path);
+    let metadata = hydrate_history_metadata(app, state, job, filename.as_deref());
+    let file_size_bytes = file_size_bytes_from_path(output_path);
+    let now = current_timestamp_millis();
+    let entry = HistoryEntry {
+        id: Uuid::new_v4().to_string(),
+        url: job.url.clone(),
+        title: metadata.title,
+        uploader: metadata.uploader,
+        filename,
+        thumbnail: metadata.thumbnail,
+        upload_date: metadata.upload_date,
+        timestamp: metadata.timestamp,
+        duration_seconds: metadata.duration_seconds,
+        file_size_bytes,
+        medium: Some(medium_for_job(job).to_string()),
+        source: source_from_url(&job.url),
+        platform: detect_platform(&job.url),
+        output_path: output_path.map(|s| s.to_string()),
+        subtitle_paths: run_result.subtitle_paths.clone(),
+        removed_segments: run_result.removed_segments.clone(),
+        parent_id: None,
+        download_archive: job
+            .cut_start_time
+            .is_none()
+            .then(|| download_archive_file_name(job)),
+        created_at: now,
+        completed_at: Some(now),
+    };
+
+    let _ = insert_history_entry_in_db(state, &entry);
+    for chapter_entry in chapter_history_entries(&entry, &run_result.chapter_files) {
+        let _ = insert_history_entry_in_db(state, &chapter_entry);
     }
+}
 
-    let python = resolve_python_executable(app).ok_or_else(|| {
-    "No Python runtime found for faster-whisper (bundled runtime missing and no compatible Python in PATH)"
-      .to_string()
-  })?;
-    emit_log(
-        app,
-        LogEvent {
-            id: job.id.clone(),
-            line: format!("[faster-whisper] using python: {python}"),
-            is_error: false,
-        },
-    );
+// Chapter files share the parent's source metadata; only the title, file and
+// duration are their own.
+fn chapter_history_entries(parent: &HistoryEntry, files: &[ChapterFile]) -> Vec<HistoryEntry> {
+    files
+        .iter()
+        .map(|file| {
+            let chapter_title = file
+                .chapter
+                .as_ref()
+                .and_then(|chapter| chapter.title.clone())
+                .filter(|title| !title.trim().is_empty());
+            let title = match (chapter_title, parent.title.as_deref()) {
+                (Some(title), _) => Some(title),
+                (None, Some(parent_title)) => {
+                    Some(format!("{parent_title} (chapter {})", file.number))
+                }
+                (None, None) => None,
+            };
+            HistoryEntry {
+                id: Uuid::new_v4().to_string(),
+                title,
+                filename: filename_from_path(Some(&file.path)),
+                duration_seconds: file
+                    .chapter
+                    .as_ref()
+                    .map(|chapter| (chapter.end_time - chapter.start_time).max(0.0).round() as i64),
+                file_size_bytes: file_size_bytes_from_path(Some(&file.path)),
+                output_path: Some(file.path.clone()),
+                subtitle_paths: Vec::new(),
+                removed_segments: Vec::new(),
+                parent_id: Some(parent.id.clone()),
+                ..parent.clone()
+            }
+        })
+        .collect()
+}
 
-    let transcript_path = Path::new(audio_path).with_extension("txt");
-    let transcript_path_str = transcript_path.to_string_lossy().to_string();
-    let model_name = std::env::var("PINEFETCH_FASTER_WHISPER_MODEL")
-        .ok()
-        .filter(|value| !value.trim().is_empty())
-        .unwrap_or_else(|| "base".to_string());
+#[tauri::command]
+fn remove_history_entry(state: State<AppState>, id: String) -> Result<(), String> {
+    delete_history_entry_from_db(state.inner(), &id)?;
+    Ok(())
+}
 
-    let mut command = Command::new(python);
-    command
-        .arg("-c")
-        .arg(FASTER_WHISPER_TRANSCRIBE_SNIPPET)
-        .arg(audio_path)
-        .arg(&transcript_path_str)
-        .arg(&model_name)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+#[tauri::command]
+fn clear_history(state: State<AppState>) -> Result<(), String> {
+    clear_history_entries_in_db(state.inner())?;
+    Ok(())
+}
 
-    let mut child = command
-        .spawn()
-        .map_err(|e| format!("Failed to start faster-whisper transcription: {e}"))?;
+fn snapshot_queue_status(state: &AppState) -> Result<QueueStatus, String> {
+    let auto_start = *state
+        .queue_auto_start
+        .lock()
+        .map_err(|_| "Queue auto-start lock poisoned")?;
+    let paused = is_queue_paused(state)?;
+    let window_open = is_download_window_open(state);
+    let active_slots = *state
+        .active_workers
+        .lock()
+        .map_err(|_| "Worker lock poisoned")?;
+    let max_slots = max_concurrent_downloads(state)?;
 
-    let stdout = child.stdout.take();
-    let stderr = child.stderr.take();
-    let app_stdout = app.clone();
-    let job_id_stdout = job.id.clone();
-    let handle_out = thread::spawn(move || {
-        if let Some(out) = stdout {
-            let reader = BufReader::new(out);
-            for line in reader.lines().flatten() {
-                emit_log(
-                    &app_stdout,
-                    LogEvent {
-                        id: job_id_stdout.clone(),
-                        line: format!("[faster-whisper] {line}"),
-                        is_error: false,
-                    },
-                );
-            }
-        }
-    });
+    Ok(QueueStatus {
+        auto_start,
+        paused,
+        window_open,
+        worker_running: active_slots > 0,
+        active_slots,
+        max_slots,
+    })
+}
 
-    let app_stderr = app.clone();
-    let job_id_stderr = job.id.clone();
-    let handle_err = thread::spawn(move || {
-        if let Some(err) = stderr {
-            let reader = BufReader::new(err);
-            for line in reader.lines().flatten() {
-                emit_log(
-                    &app_stderr,
-                    LogEvent {
-                        id: job_id_stderr.clone(),
-                        line: format!("[faster-whisper] {line}"),
-                        is_error: true,
-                    },
-                );
-            }
-        }
-    });
-
-    let status = child
-        .wait()
-        .map_err(|e| format!("Failed while waiting for faster-whisper: {e}"))?;
-    let _ = handle_out.join();
-    let _ = handle_err.join();
+fn is_queue_paused(state: &AppState) -> Result<bool, String> {
+    let paused = state
+        .queue_paused
+        .lock()
+        .map_err(|_| "Queue pause lock poisoned")?;
+    Ok(*paused)
+}
 
-    if !status.success() {
-        let code = status.code().unwrap_or(-1);
-        return Err(format!(
-      "faster-whisper failed (exit code {code}). Ensure Python deps are installed (`pip install faster-whisper`)."
-    ));
-    }
+fn max_concurrent_downloads(state: &AppState) -> Result<usize, String> {
+    let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
+    Ok(cfg
+        .max_concurrent_downloads
+        .clamp(1, MAX_CONCURRENT_DOWNLOADS_LIMIT))
+}
 
-    if !transcript_path.exists() {
-        return Err("faster-whisper finished but no transcript file was created".to_string());
+fn emit_queue_status(app: &AppHandle, state: &AppState) {
+    if let Ok(status) = snapshot_queue_status(state) {
+        let _ = app.emit_all("queue:status", status);
     }
-
-    Ok(transcript_path_str)
 }
 
-fn build_output_template(output_dir: &str, filename_suffix: Option<&str>) -> String {
-    let mut path = PathBuf::from(output_dir);
-    // Use title, but fallback to uploader and id for platforms where title might be missing or duplicate
-    // %(title)s - video title
-    // %(uploader)s - uploader name
-    // %(id)s - unique video ID (ensures uniqueness for Instagram posts from same creator)
-    let suffix = filename_suffix.unwrap_or("");
-    path.push(format!("%(title)s - %(uploader)s - %(id)s{suffix}.%(ext)s"));
-    path.to_string_lossy().to_string()
+fn is_queue_auto_start_enabled(state: &AppState) -> Result<bool, String> {
+    let auto_start = state
+        .queue_auto_start
+        .lock()
+        .map_err(|_| "Queue auto-start lock poisoned")?;
+    Ok(*auto_start)
 }
 
-fn normalize_filename_suffix(raw: Option<&str>) -> Option<String> {
-    let suffix = raw?.trim();
-    if suffix.is_empty()
-        || suffix.len() > 32
-        || !suffix.chars().all(|character| {
-            character.is_ascii_alphanumeric() || character == '_' || character == '-'
-        })
+fn restore_persisted_queue(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let jobs = restore_queue_jobs_from_db(state)?;
+    if jobs.is_empty() {
+        return Ok(());
+    }
+
+    let resume = {
+        let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
+        cfg.resume_queue_on_startup
+    };
+    println!("Restored {} queued downloads", jobs.len());
+    let mut retry_times = jobs
+        .iter()
+        .filter_map(|job| job.retry_at)
+        .collect::<Vec<_>>();
+    retry_times.sort_unstable();
+    retry_times.dedup();
+    let restored_at = current_timestamp_millis();
+    for job in &jobs {
+        let job_state = if job.paused {
+            JobState::Paused {
+                paused_at: restored_at,
+            }
+        } else {
+            JobState::Queued {
+                queued_at: restored_at,
+            }
+        };
+        record_job_state(state, &job.id, job_state);
+    }
+
     {
-        return None;
+        let mut queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        queue.extend(jobs);
+    }
+    if !resume {
+        let mut auto_start = state
+            .queue_auto_start
+            .lock()
+            .map_err(|_| "Queue auto-start lock poisoned")?;
+        *auto_start = false;
     }
 
-    Some(suffix.to_string())
+    emit_queue(app, state)?;
+    if resume {
+        ensure_worker(app, state)?;
+        // Each backoff ends at its own time; one wake-up at the latest would leave
+        // the earlier retries waiting for it.
+        for retry_at in retry_times {
+            schedule_queue_wake(app, retry_at);
+        }
+    } else {
+        emit_queue_status(app, state);
+    }
+    Ok(())
 }
 
-fn emit_queue(app: &AppHandle, state: &AppState) -> Result<(), String> {
-    let queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
-    app.emit_all("queue:update", queue.clone())
-        .map_err(|e| format!("Emit queue failed: {e}"))
+fn ensure_worker(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let max_slots = if is_queue_paused(state)? || !is_download_window_open(state) {
+        0
+    } else {
+        max_concurrent_downloads(state)?
+    };
+    let spawn_count = {
+        // Lock order matters: workers also take `queue` before `active_workers` when they exit,
+        // so a job enqueued while the last worker is shutting down is never stranded.
+        let queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        let mut active = state
+            .active_workers
+            .lock()
+            .map_err(|_| "Worker lock poisoned")?;
+        let spawn_count = max_slots
+            .saturating_sub(*active)
+            .min(runnable_job_count(&queue, current_timestamp_millis()));
+        *active += spawn_count;
+        spawn_count
+    };
+    emit_queue_status(app, state);
+
+    for _ in 0..spawn_count {
+        spawn_download_worker(app.clone());
+    }
+    Ok(())
 }
 
-fn emit_progress(app: &AppHandle, progress: DownloadProgress) {
-    let _ = app.emit_all("download:progress", progress);
+fn spawn_download_worker(app_handle: AppHandle) {
+    thread::spawn(move || loop {
+        let state_handle = app_handle.state::<AppState>();
+        let window_open = is_download_window_open(&state_handle);
+        let job_opt = {
+            let mut queue = match state_handle.queue.lock() {
+                Ok(queue) => queue,
+                Err(_) => break,
+            };
+            let job = if window_open && worker_slot_available(&state_handle) {
+                next_runnable_job_index(&queue, current_timestamp_millis())
+                    .and_then(|index| queue.remove(index))
+            } else {
+                None
+            };
+            if job.is_none() {
+                if let Ok(mut active) = state_handle.active_workers.lock() {
+                    *active = active.saturating_sub(1);
+                }
+            }
+            job
+        };
+
+        let Some(job) = job_opt else {
+            let _ = emit_queue(&app_handle, &state_handle);
+            emit_queue_status(&app_handle, &state_handle);
+            break;
+        };
+
+        process_download_job(&app_handle, &state_handle, job);
+        rebalance_rate_limits(&app_handle, &state_handle);
+    });
 }
 
-fn emit_state(app: &AppHandle, state: DownloadStateEvent) {
-    let _ = app.emit_all("download:state", state);
+fn worker_slot_available(state: &AppState) -> bool {
+    if is_queue_paused(state).unwrap_or(true) {
+        return false;
+    }
+    let Ok(max_slots) = max_concurrent_downloads(state) else {
+        return false;
+    };
+    state
+        .active_workers
+        .lock()
+        .map(|active| *active <= max_slots)
+        .unwrap_or(false)
 }
 
-fn emit_log(app: &AppHandle, log: LogEvent) {
-    let _ = app.emit_all("download:log", log);
+fn is_job_runnable(job: &DownloadJob, now_millis: u64) -> bool {
+    !job.paused
+        && job.retry_at.is_none_or(|retry_at| retry_at <= now_millis)
+        && job
+            .not_before
+            .is_none_or(|not_before| not_before <= now_millis)
 }
 
-fn is_valid_url(url: &str) -> bool {
-    url.starts_with("http://") || url.starts_with("https://")
+fn spawn_queue_scheduler(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(QUEUE_SCHEDULER_INTERVAL);
+        let state = app.state::<AppState>();
+        let has_jobs = state
+            .queue
+            .lock()
+            .map(|queue| !queue.is_empty())
+            .unwrap_or(false);
+        if has_jobs && is_queue_auto_start_enabled(&state).unwrap_or(false) {
+            let _ = ensure_worker(&app, &state);
+        }
+    });
 }
 
-fn resolve_cut_start_time(
-    cut_at_timestamp_enabled: bool,
-    requested_cut_start_time: Option<f64>,
-    url: &str,
-) -> Option<f64> {
-    if !cut_at_timestamp_enabled {
-        return None;
-    }
+fn is_download_window_open(state: &AppState) -> bool {
+    let enabled = state
+        .config
+        .lock()
+        .map(|cfg| cfg.download_window_enabled)
+        .unwrap_or(false);
+    // Invalid times never close the window, so a bad config cannot stall the queue.
+    !enabled
+        || is_within_download_window(state)
+            .ok()
+            .flatten()
+            .unwrap_or(true)
+}
+
+// None when a bound is not a valid `HH:MM` time.
+fn is_within_download_window(state: &AppState) -> Result<Option<bool>, String> {
+    let (start, end) = {
+        let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
+        (
+            parse_clock_minutes(&cfg.download_window_start),
+            parse_clock_minutes(&cfg.download_window_end),
+        )
+    };
+    let (Some(start), Some(end)) = (start, end) else {
+        return Ok(None);
+    };
+    Ok(Some(is_within_time_window(
+        start,
+        end,
+        local_minute_of_day(),
+    )))
+}
 
-    requested_cut_start_time
-        .and_then(normalize_positive_timestamp)
-        .or_else(|| extract_url_start_timestamp(url))
+fn local_minute_of_day() -> u32 {
+    let now = chrono::Local::now();
+    now.hour() * 60 + now.minute()
 }
 
-fn extract_url_start_timestamp(raw_url: &str) -> Option<f64> {
-    let parsed = url::Url::parse(raw_url).ok()?;
+fn parse_clock_minutes(value: &str) -> Option<u32> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let hours = hours.parse::<u32>().ok()?;
+    let minutes = minutes.parse::<u32>().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
 
-    for (name, value) in parsed.query_pairs() {
-        if is_start_timestamp_param(name.as_ref()) {
-            if let Some(seconds) = parse_timestamp_value(value.as_ref()) {
-                return Some(seconds);
-            }
-        }
+// Windows may wrap past midnight (23:00-07:00); equal bounds mean the whole day.
+fn is_within_time_window(start: u32, end: u32, minute: u32) -> bool {
+    match start.cmp(&end) {
+        std::cmp::Ordering::Equal => true,
+        std::cmp::Ordering::Less => minute >= start && minute < end,
+        std::cmp::Ordering::Greater => minute >= start || minute < end,
     }
+}
 
-    if let Some(fragment) = parsed.fragment() {
-        for (name, value) in url::form_urlencoded::parse(fragment.as_bytes()) {
-            if is_start_timestamp_param(name.as_ref()) {
-                if let Some(seconds) = parse_timestamp_value(value.as_ref()) {
-                    return Some(seconds);
-                }
-            }
-        }
+// Higher priorities run first; equal priorities keep their queue order.
+fn next_runnable_job_index(queue: &VecDeque<DownloadJob>, now_millis: u64) -> Option<usize> {
+    queue
+        .iter()
+        .enumerate()
+        .filter(|(_, job)| is_job_runnable(job, now_millis))
+        .max_by_key(|(index, job)| (job.priority, Reverse(*index)))
+        .map(|(index, _)| index)
+}
 
-        parse_timestamp_value(fragment)
-    } else {
-        None
-    }
+fn runnable_job_count(queue: &VecDeque<DownloadJob>, now_millis: u64) -> usize {
+    queue
+        .iter()
+        .filter(|job| is_job_runnable(job, now_millis))
+        .count()
 }
 
-fn is_start_timestamp_param(name: &str) -> bool {
-    matches!(name, "t" | "start" | "start_time" | "time_continue")
+fn schedule_queue_wake(app: &AppHandle, wake_at_millis: u64) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let delay = wake_at_millis.saturating_sub(current_timestamp_millis());
+        thread::sleep(Duration::from_millis(delay));
+        let state = app.state::<AppState>();
+        let _ = ensure_worker(&app, &state);
+    });
 }
 
-fn parse_timestamp_value(raw: &str) -> Option<f64> {
-    let value = raw.trim().to_ascii_lowercase();
-    if value.is_empty() {
-        return None;
+fn process_download_job(app: &AppHandle, state: &AppState, job: DownloadJob) {
+    if let Ok(mut running) = state.running_jobs.lock() {
+        running.insert(
+            job.id.clone(),
+            RunningJob {
+                url: job.url.clone(),
+                child: None,
+                shared_rate_limit_kib: None,
+            },
+        );
     }
+    rebalance_rate_limits(app, state);
+    let _ = update_queue_job_state_in_db(state, &job.id, "running");
+    let _ = emit_queue(app, state);
+    emit_queue_status(app, state);
 
-    if let Ok(seconds) = value.parse::<f64>() {
-        return normalize_positive_timestamp(seconds);
+    set_job_state(
+        app,
+        state,
+        &job.id,
+        JobState::FetchingMetadata {
+            started_at: current_timestamp_millis(),
+        },
+    );
+
+    let mut result = run_download_job(app, state, &job);
+    if should_fall_back_to_local_cut(state, &job, &result) {
+        emit_log(
+            app,
+            LogEvent {
+                id: job.id.clone(),
+                line: "[cut] range download failed; downloading full file for a local cut instead"
+                    .to_string(),
+                is_error: false,
+            },
+        );
+        let fallback_job = DownloadJob {
+            cut_mode: CutMode::Local,
+            ..job.clone()
+        };
+        result = run_download_job(app, state, &fallback_job);
     }
 
-    if value.contains(':') {
-        return parse_colon_timestamp(&value);
+    if let Ok(mut running) = state.running_jobs.lock() {
+        running.remove(&job.id);
     }
+    let cancelled = state
+        .cancel_requested
+        .lock()
+        .map(|mut cancel| cancel.remove(&job.id))
+        .unwrap_or(false);
+    let pause_request = state
+        .pause_requested
+        .lock()
+        .ok()
+        .and_then(|mut pause| pause.remove(&job.id));
+    let finished = matches!(&result, Ok(run_result) if run_result.exit_code == 0);
 
-    parse_unit_timestamp(&value)
-}
+    if let Some(pause_request) = pause_request.filter(|_| !cancelled && !finished) {
+        requeue_paused_job(app, state, job, pause_request);
+        return;
+    }
 
-fn parse_colon_timestamp(value: &str) -> Option<f64> {
-    let parts = value.split(':').collect::<Vec<_>>();
-    if parts.len() < 2 || parts.len() > 3 {
-        return None;
+    let failure = match &result {
+        Ok(run_result) if !cancelled && run_result.exit_code != 0 => {
+            classify_yt_dlp_failure(&run_result.error_lines)
+        }
+        _ => YtDlpFailure::Unknown,
+    };
+    if let (YtDlpFailure::Retryable(reason), Ok(run_result)) = (failure, &result) {
+        if let Some(delay_seconds) = retry_delay_seconds(state, job.retry_count + 1) {
+            let error =
+                JobError::yt_dlp(run_result.exit_code, Some(reason), &run_result.error_lines);
+            schedule_job_retry(app, state, job, error, delay_seconds);
+            return;
+        }
     }
 
-    let mut total = 0.0;
-    for part in parts {
-        if part.is_empty() {
-            return None;
+    let outcome = match result {
+        _ if cancelled => JobState::Cancelled {
+            cancelled_at: current_timestamp_millis(),
+        },
+        Ok(run_result) if run_result.exit_code != 0 => {
+            let reason = match failure {
+                YtDlpFailure::Permanent(reason) => Some(reason),
+                _ => None,
+            };
+            JobState::Failed {
+                failed_at: current_timestamp_millis(),
+                error: JobError::yt_dlp(run_result.exit_code, reason, &run_result.error_lines),
+            }
         }
-        let value = part.parse::<f64>().ok()?;
-        if !value.is_finite() || value < 0.0 {
-            return None;
+        Ok(run_result) if run_result.already_archived => {
+            emit_log(
+                app,
+                LogEvent {
+                    id: job.id.clone(),
+                    line:
+                        "[archive] already downloaded; enable force re-download to fetch it again"
+                            .to_string(),
+                    is_error: false,
+                },
+            );
+            JobState::Skipped {
+                skipped_at: current_timestamp_millis(),
+            }
         }
-        total = total * 60.0 + value;
+        Ok(run_result) if job.transcribe_text => {
+            set_job_state(
+                app,
+                state,
+                &job.id,
+                JobState::Transcribing {
+                    started_at: current_timestamp_millis(),
+                },
+            );
+
+            match run_faster_whisper_transcription(app, &job, run_result.output_path.as_deref()) {
+                Ok(transcript_path) => {
+                    emit_log(
+                        app,
+                        LogEvent {
+                            id: job.id.clone(),
+                            line: format!("[transcript] saved: {transcript_path}"),
+                            is_error: false,
+                        },
+                    );
+                    // Add to history on success
+                    add_history_entry_on_success(app, state, &job, &run_result);
+                    JobState::Done {
+                        finished_at: current_timestamp_millis(),
+                        output_path: Some(transcript_path),
+                        output_paths: Vec::new(),
+                    }
+                }
+                Err(err) => JobState::Failed {
+                    failed_at: current_timestamp_millis(),
+                    error: JobError::new(JobErrorKind::Transcription, err),
+                },
+            }
+        }
+        Ok(run_result) => {
+            // Add to history on success
+            add_history_entry_on_success(app, state, &job, &run_result);
+            JobState::Done {
+                finished_at: current_timestamp_millis(),
+                output_paths: run_result.output_paths(),
+                output_path: run_result.output_path,
+            }
+        }
+        Err(err) => JobState::Failed {
+            failed_at: current_timestamp_millis(),
+            error: JobError::new(JobErrorKind::Internal, err),
+        },
+    };
+
+    if matches!(
+        outcome,
+        JobState::Failed { .. } | JobState::Cancelled { .. }
+    ) {
+        let _ = record_failed_job_in_db(state, &job, &outcome);
     }
+    set_job_state(app, state, &job.id, outcome);
 
-    normalize_positive_timestamp(total)
+    let _ = delete_queue_job_from_db(state, &job.id);
+    let _ = emit_queue(app, state);
 }
 
-fn parse_unit_timestamp(value: &str) -> Option<f64> {
-    let mut number = String::new();
-    let mut total = 0.0;
-    let mut saw_unit = false;
+fn record_job_state(state: &AppState, id: &str, job_state: JobState) {
+    if let Ok(mut lifecycles) = state.job_lifecycles.lock() {
+        lifecycles.record(id, job_state);
+    }
+}
 
-    for character in value.chars() {
-        if character.is_ascii_digit() || character == '.' {
-            number.push(character);
-            continue;
-        }
+fn is_terminal_job_state(job_state: &JobState) -> bool {
+    matches!(
+        job_state,
+        JobState::Done { .. }
+            | JobState::Skipped { .. }
+            | JobState::Failed { .. }
+            | JobState::Cancelled { .. }
+    )
+}
 
-        let multiplier = match character {
-            'h' => 3600.0,
-            'm' => 60.0,
-            's' => 1.0,
-            _ => return None,
-        };
-        if number.is_empty() {
-            return None;
-        }
-        let amount = number.parse::<f64>().ok()?;
-        if !amount.is_finite() || amount < 0.0 {
-            return None;
-        }
-        total += amount * multiplier;
-        number.clear();
-        saw_unit = true;
-    }
+fn set_job_state(app: &AppHandle, state: &AppState, id: &str, job_state: JobState) {
+    record_job_state(state, id, job_state.clone());
+    emit_state(
+        app,
+        DownloadStateEvent {
+            id: id.to_string(),
+            state: job_state,
+        },
+    );
+}
 
-    if !saw_unit || !number.is_empty() {
-        return None;
+#[tauri::command]
+fn get_job(state: State<AppState>, id: String) -> Result<JobLifecycle, String> {
+    let lifecycle = {
+        let lifecycles = state
+            .job_lifecycles
+            .lock()
+            .map_err(|_| "Job lifecycle lock poisoned")?;
+        lifecycles.get(&id).cloned()
+    };
+    if let Some(lifecycle) = lifecycle {
+        return Ok(lifecycle);
     }
 
-    normalize_positive_timestamp(total)
+    // Lifecycles are kept in memory; failed jobs are the only ones that outlive a restart.
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let failed = list_failed_jobs_from_conn(&conn, Some(&id))
+        .map_err(|e| format!("Failed jobs read failed: {e}"))?;
+    failed
+        .into_iter()
+        .next()
+        .map(|failed| JobLifecycle {
+            id: failed.id,
+            state: failed.state.clone(),
+            transitions: vec![failed.state],
+        })
+        .ok_or_else(|| "Job not found".to_string())
 }
 
-fn normalize_positive_timestamp(seconds: f64) -> Option<f64> {
-    if seconds.is_finite() && seconds > 0.0 {
-        Some(seconds)
-    } else {
-        None
-    }
-}
+fn classify_yt_dlp_failure(error_lines: &[String]) -> YtDlpFailure {
+    let text = error_lines.join("\n").to_lowercase();
+    let find = |markers: &[(&str, &'static str)]| {
+        markers
+            .iter()
+            .find(|(marker, _)| text.contains(marker))
+            .map(|(_, reason)| *reason)
+    };
 
-fn format_yt_dlp_timestamp(seconds: f64) -> String {
-    let mut formatted = format!("{seconds:.3}");
-    while formatted.contains('.') && formatted.ends_with('0') {
-        formatted.pop();
+    if let Some(reason) = find(YT_DLP_PERMANENT_FAILURES) {
+        return YtDlpFailure::Permanent(reason);
     }
-    if formatted.ends_with('.') {
-        formatted.pop();
+    if let Some(reason) = find(YT_DLP_RETRYABLE_FAILURES) {
+        return YtDlpFailure::Retryable(reason);
     }
-    formatted
+    YtDlpFailure::Unknown
 }
 
-fn canonical_existing_local_path(raw: &str) -> Result<Option<String>, String> {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return Ok(None);
-    }
-    if trimmed.contains('\0')
-        || trimmed.contains("://")
-        || trimmed.starts_with("mailto:")
-        || trimmed.starts_with("tel:")
+// Failures with a known cause (network, private video, ...) would fail the same
+// way on a full download, so only unexplained range failures fall back.
+fn should_fall_back_to_local_cut(
+    state: &AppState,
+    job: &DownloadJob,
+    result: &Result<DownloadRunResult, String>,
+) -> bool {
+    if job.cut_mode != CutMode::Range
+        || job.cut_start_time.is_none()
+        || has_pending_stop_request(state, &job.id)
     {
-        return Err("Only local filesystem paths can be opened".to_string());
+        return false;
     }
-
-    match fs::canonicalize(trimmed) {
-        Ok(path) => Ok(Some(path.to_string_lossy().to_string())),
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
-        Err(err) => Err(format!("Path could not be opened: {err}")),
+    match result {
+        Ok(run_result) if run_result.exit_code != 0 => matches!(
+            classify_yt_dlp_failure(&run_result.error_lines),
+            YtDlpFailure::Unknown
+        ),
+        _ => false,
     }
 }
 
-fn resolve_output_dir(state: &AppState, requested: Option<String>) -> Result<String, String> {
-    if let Some(dir) = requested {
-        if dir.trim().is_empty() {
-            return Err("Output directory is empty".to_string());
-        }
-        return Ok(dir);
-    }
-    let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
-    cfg.default_output_dir
-        .clone()
-        .ok_or_else(|| "Default output directory not set".to_string())
+fn retry_delay_seconds(state: &AppState, attempt: u32) -> Option<u64> {
+    let cfg = state.config.lock().ok()?;
+    retry_backoff_seconds(&cfg, attempt)
 }
 
-fn resolve_yt_dlp(_app: &AppHandle, state: &AppState) -> Result<String, String> {
-    let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
-    if let Some(path) = cfg.yt_dlp_path.as_ref() {
-        if Path::new(path).exists() {
-            return Ok(path.clone());
-        }
+fn retry_backoff_seconds(config: &AppConfig, attempt: u32) -> Option<u64> {
+    if attempt == 0 || attempt > config.retry_max_attempts {
+        return None;
     }
+    let factor = 1u64.checked_shl(attempt - 1).unwrap_or(u64::MAX);
+    Some(
+        config
+            .retry_base_delay_seconds
+            .saturating_mul(factor)
+            .min(config.retry_max_delay_seconds),
+    )
+}
 
-    if let Some(path) = find_in_path("yt-dlp") {
-        return Ok(path);
+fn schedule_job_retry(
+    app: &AppHandle,
+    state: &AppState,
+    mut job: DownloadJob,
+    error: JobError,
+    delay_seconds: u64,
+) {
+    job.retry_count += 1;
+    let failed_at = current_timestamp_millis();
+    let next_retry_at = failed_at.saturating_add(delay_seconds * 1000);
+    job.retry_at = Some(next_retry_at);
+    if let Ok(mut queue) = state.queue.lock() {
+        queue.push_front(job.clone());
     }
+    let _ = update_queue_job_in_db(state, &job, "queued");
+    let _ = sync_queue_positions_in_db(state);
 
-    Err("yt-dlp not found. Set its path in Settings.".to_string())
+    emit_log(
+        app,
+        LogEvent {
+            id: job.id.clone(),
+            line: format!(
+                "[retry] {}; attempt {} in {delay_seconds}s",
+                error.message, job.retry_count
+            ),
+            is_error: false,
+        },
+    );
+    set_job_state(
+        app,
+        state,
+        &job.id,
+        JobState::Retrying {
+            attempt: job.retry_count,
+            failed_at,
+            next_retry_at,
+            error,
+        },
+    );
+    let _ = emit_queue(app, state);
+    schedule_queue_wake(app, next_retry_at);
 }
 
-fn resolve_yt_dlp_for_version(
+fn requeue_paused_job(
     app: &AppHandle,
     state: &AppState,
-    path: Option<String>,
-) -> Result<String, String> {
-    if let Some(raw) = path {
-        let trimmed = raw.trim();
-        if !trimmed.is_empty() {
-            if Path::new(trimmed).exists() {
-                return Ok(trimmed.to_string());
-            }
-            return Err(format!("yt-dlp path not found: {trimmed}"));
-        }
+    mut job: DownloadJob,
+    pause_request: PauseRequest,
+) {
+    // Queue-wide pauses leave the job runnable so it starts again with the queue.
+    job.paused = pause_request == PauseRequest::Job;
+    let job_state = if job.paused { "paused" } else { "queued" };
+    if let Ok(mut queue) = state.queue.lock() {
+        queue.push_front(job.clone());
     }
-    resolve_yt_dlp(app, state)
-}
+    let _ = update_queue_job_in_db(state, &job, job_state);
+    let _ = sync_queue_positions_in_db(state);
 
-fn find_in_path(binary: &str) -> Option<String> {
-    let paths = std::env::var_os("PATH")?;
-    let splitter = if cfg!(windows) { ';' } else { ':' };
-    for path in paths.to_string_lossy().split(splitter) {
-        let candidate = Path::new(path).join(if cfg!(windows) {
-            format!("{binary}.exe")
-        } else {
-            binary.to_string()
-        });
-        if candidate.exists() {
-            return Some(candidate.to_string_lossy().to_string());
-        }
+    if pause_request != PauseRequest::Rebalance {
+        emit_log(
+            app,
+            LogEvent {
+                id: job.id.clone(),
+                line: "[pause] download paused; partial file kept for resume".to_string(),
+                is_error: false,
+            },
+        );
     }
-    None
+    let paused_at = current_timestamp_millis();
+    let job_state = if job.paused {
+        JobState::Paused { paused_at }
+    } else {
+        JobState::Queued {
+            queued_at: paused_at,
+        }
+    };
+    set_job_state(app, state, &job.id, job_state);
+    let _ = emit_queue(app, state);
 }
 
-fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
-    let dir =
-        tauri::api::path::app_config_dir(&app.config()).ok_or("Config directory unavailable")?;
-    fs::create_dir_all(&dir).map_err(|e| format!("Config dir create failed: {e}"))?;
-    Ok(dir.join("config.json"))
-}
+fn run_download_job(
+    app: &AppHandle,
+    state: &AppState,
+    job: &DownloadJob,
+) -> Result<DownloadRunResult, String> {
+    let yt_dlp = resolve_yt_dlp(app, state)?;
+    let ffmpeg_location = resolve_ffmpeg_location(app, &yt_dlp);
+    let deno_path = resolve_deno_executable(app);
+    let cookie_profile = cookie_profile_for_url(state, &job.url).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        None
+    });
+    let cookies_copy = cookie_profile
+        .as_ref()
+        .map(|profile| CookiesCopy::create(&profile.cookies_path))
+        .transpose()?;
+    let output_template = build_job_output_template(job);
+    let output_template_for_fallback = output_template.clone();
 
-fn legacy_history_path(app: &AppHandle) -> Result<PathBuf, String> {
-    let dir = tauri::api::path::app_data_dir(&app.config()).ok_or("Data directory unavailable")?;
-    fs::create_dir_all(&dir).map_err(|e| format!("Data dir create failed: {e}"))?;
-    Ok(dir.join("history.json"))
-}
+    let mut args = vec![
+        "--no-playlist".to_string(),
+        "--continue".to_string(),
+        "--newline".to_string(),
+        "--progress".to_string(),
+        "--progress-template".to_string(),
+        YT_DLP_PROGRESS_TEMPLATE.to_string(),
+        "--no-color".to_string(),
+        "--print".to_string(),
+        "after_move:filepath".to_string(),
+        "--print".to_string(),
+        "after_video:filepath".to_string(),
+        "-f".to_string(),
+        job.format.clone(),
+        "-o".to_string(),
+        output_template,
+    ];
 
-fn load_legacy_history_json(app: &AppHandle) -> Vec<HistoryEntry> {
-    if let Ok(path) = legacy_history_path(app) {
-        if let Ok(raw) = fs::read_to_string(path) {
-            if let Ok(history) = serde_json::from_str::<Vec<HistoryEntry>>(&raw) {
-                return history.into_iter().map(normalize_history_entry).collect();
-            }
-        }
+    let needs_ffmpeg = job.extract_audio
+        || job.transcribe_text
+        || job.format.contains('+')
+        || job.cut_start_time.is_some()
+        || job.subtitles.convert_format.is_some()
+        || job.subtitles.embed
+        || job.embed.metadata
+        || job.embed.thumbnail
+        || job.embed.chapters
+        || job.sponsorblock.mode == SponsorBlockMode::Remove
+        || job.split_chapters;
+    if let Some(location) = ffmpeg_location.as_ref() {
+        args.push("--ffmpeg-location".to_string());
+        args.push(location.clone());
+    } else if needs_ffmpeg {
+        return Err(
+            "ffmpeg and ffprobe not found. Install ffmpeg (or make sure it is in the same directory as yt-dlp) and try again."
+                .to_string(),
+        );
     }
-    Vec::new()
-}
 
-fn normalize_history_entry(mut entry: HistoryEntry) -> HistoryEntry {
-    entry.title = trim_optional_string(entry.title);
-    entry.uploader = trim_optional_string(entry.uploader);
-    entry.filename = trim_optional_string(entry.filename)
-        .or_else(|| filename_from_path(entry.output_path.as_deref()));
-    entry.thumbnail = trim_optional_string(entry.thumbnail);
-    entry.upload_date = trim_optional_string(entry.upload_date);
-    entry.timestamp = entry.timestamp.filter(|timestamp| *timestamp >= 0);
-    entry.duration_seconds = entry.duration_seconds.filter(|duration| *duration >= 0);
-    entry.file_size_bytes = entry.file_size_bytes.filter(|size| *size >= 0);
-    entry.medium = trim_optional_string(entry.medium)
-        .map(|medium| medium.to_ascii_lowercase())
-        .filter(|medium| matches!(medium.as_str(), "video" | "audio" | "transcript"));
-    entry.source = trim_optional_string(entry.source)
-        .map(|source| source.to_ascii_lowercase())
-        .or_else(|| source_from_url(&entry.url));
-    entry.platform = trim_optional_string(entry.platform).or_else(|| detect_platform(&entry.url));
-    entry.output_path = trim_optional_string(entry.output_path);
-    if entry.title.is_none() {
-        entry.title = title_from_filename(entry.filename.as_deref());
+    if let Some(deno) = deno_path.as_ref() {
+        args.push("--js-runtimes".to_string());
+        args.push(format!("deno:{deno}"));
     }
-    entry
-}
-
-fn millis_to_i64(value: u64) -> i64 {
-    value.min(i64::MAX as u64) as i64
-}
 
-fn i64_to_millis(value: i64) -> u64 {
-    if value < 0 {
-        0
-    } else {
-        value as u64
+    let rate_limit = resolve_job_rate_limit_kib(state, job);
+    if let Some(limit) = rate_limit {
+        args.push("--limit-rate".to_string());
+        args.push(format!("{limit}K"));
+    }
+    set_running_job_shared_rate_limit(
+        state,
+        &job.id,
+        rate_limit.filter(|_| job.rate_limit_kib.is_none()),
+    )?;
+    let throttled_rate = state
+        .config
+        .lock()
+        .ok()
+        .and_then(|cfg| cfg.throttled_rate_kib);
+    if let Some(rate) = throttled_rate {
+        args.push("--throttled-rate".to_string());
+        args.push(format!("{rate}K"));
     }
-}
 
-fn optional_i64_to_millis(value: Option<i64>) -> Option<u64> {
-    value.map(i64_to_millis)
-}
+    if job.extract_audio {
+        args.push("--extract-audio".to_string());
+        if let Some(fmt) = job.audio_format.as_ref() {
+            args.push("--audio-format".to_string());
+            args.push(fmt.to_string());
+        }
+    }
 
-fn count_history_entries_in_db(state: &AppState) -> Result<u64, String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    let count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM history_entries", [], |row| row.get(0))
-        .map_err(|e| format!("History read failed: {e}"))?;
-    Ok(count.max(0) as u64)
-}
+    args.extend(subtitle_args(&job.subtitles, job.extract_audio));
+    args.extend(embed_args(job));
+    args.extend(sponsorblock_args(&job.sponsorblock));
+    if job.split_chapters {
+        args.extend(split_chapters_args(&output_template_for_fallback));
+    }
 
-fn list_history_page_from_db(
-    state: &AppState,
+    if let (Some(profile), Some(cookies)) = (cookie_profile.as_ref(), cookies_copy.as_ref()) {
+        args.push("--cookies".to_string());
+        args.push(cookies.path());
+        emit_log(
+            app,
+            LogEvent {
+                id: job.id.clone(),
+                line: format!("[cookies] using profile \"{}\"", profile.name),
+                is_error: false,
+            },
+        );
+    }
+
+    // A clip is not the whole video, so it must not mark the video as downloaded.
+    if !job.force_download && job.cut_start_time.is_none() {
+        match download_archive_path(app, &download_archive_file_name(job)) {
+            Ok(path) => {
+                args.push("--download-archive".to_string());
+                args.push(path.to_string_lossy().to_string());
+            }
+            Err(err) => emit_log(
+                app,
+                LogEvent {
+                    id: job.id.clone(),
+                    line: format!("[archive] {err}"),
+                    is_error: true,
+                },
+            ),
+        }
+    }
+
+    if let Some(cut_start_time) = job.cut_start_time {
+        let cut_timestamp = format_yt_dlp_timestamp(cut_start_time);
+        let line = match job.cut_mode {
+            CutMode::Range => {
+                let section = download_section_spec(cut_start_time, job.cut_end_time);
+                args.push("--download-sections".to_string());
+                args.push(section.clone());
+                if job.cut_precision == CutPrecision::Accurate {
+                    args.push("--force-keyframes-at-cuts".to_string());
+                }
+                format!(
+                    "[cut] downloading only the range {section} with --download-sections ({} cut)",
+                    job.cut_precision.as_str()
+                )
+            }
+            CutMode::Local => format!(
+                "[cut] URL timestamp detected; downloading full file before local cut at {cut_timestamp}s"
+            ),
+        };
+        emit_log(
+            app,
+            LogEvent {
+                id: job.id.clone(),
+                line,
+                is_error: false,
+            },
+        );
+    }
+
+    args.push(job.url.clone());
+
+    let mut command = Command::new(&yt_dlp);
+    command.args(args);
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    let child = command.spawn().map_err(|e| format!("Spawn failed: {e}"))?;
+    let child = Arc::new(Mutex::new(child));
+
+    set_running_job_child(state, &job.id, Some(child.clone()))?;
+
+    let (stdout, stderr) = {
+        let mut guard = child.lock().map_err(|_| "Child lock poisoned")?;
+        (guard.stdout.take(), guard.stderr.take())
+    };
+
+    let output_path_capture: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let already_archived = Arc::new(AtomicBool::new(false));
+    let removed_segments_capture: Arc<Mutex<Vec<SponsorBlockSegment>>> =
+        Arc::new(Mutex::new(Vec::new()));
+    let chapters_capture: Arc<Mutex<SplitChaptersCapture>> =
+        Arc::new(Mutex::new(SplitChaptersCapture::default()));
+
+    let app_stdout = app.clone();
+    let id_stdout = job.id.clone();
+    let output_path_for_stdout = output_path_capture.clone();
+    let already_archived_for_stdout = already_archived.clone();
+    let removed_segments_for_stdout = removed_segments_capture.clone();
+    let chapters_for_stdout = chapters_capture.clone();
+    let handle_out = thread::spawn(move || {
+        if let Some(out) = stdout {
+            let reader = BufReader::new(out);
+            let mut phase = None;
+            for line in reader.lines().flatten() {
+                if let Some(segments) = parse_sponsorblock_segments_line(&line) {
+                    if let Ok(mut slot) = removed_segments_for_stdout.lock() {
+                        *slot = segments;
+                    }
+                    continue;
+                }
+                if let Some(json) = line.strip_prefix(YT_DLP_CHAPTERS_PREFIX) {
+                    if let Ok(mut slot) = chapters_for_stdout.lock() {
+                        slot.chapters = serde_json::from_str::<serde_json::Value>(json.trim())
+                            .map(|value| parse_info_chapters(&value))
+                            .unwrap_or_default();
+                    }
+                    continue;
+                }
+                if let Some(chapter_file) = parse_split_chapter_line(&line) {
+                    if let Ok(mut slot) = chapters_for_stdout.lock() {
+                        slot.files.push(chapter_file);
+                    }
+                }
+
+                // Structured progress lines are emitted as events, not logged.
+                if let Some(progress) = parse_yt_dlp_progress_line(&id_stdout, &line) {
+                    if phase != Some(YtDlpPhase::Downloading) {
+                        phase = Some(YtDlpPhase::Downloading);
+                        let state = app_stdout.state::<AppState>();
+                        if !has_pending_stop_request(&state, &id_stdout) {
+                            set_job_state(
+                                &app_stdout,
+                                &state,
+                                &id_stdout,
+                                YtDlpPhase::Downloading.job_state(),
+                            );
+                        }
+                    }
+                    emit_progress(&app_stdout, progress);
+                    continue;
+                }
+
+                emit_log(
+                    &app_stdout,
+                    LogEvent {
+                        id: id_stdout.clone(),
+                        line: line.clone(),
+                        is_error: false,
+                    },
+                );
+
+                let next_phase = yt_dlp_line_phase(&line);
+                if next_phase.is_some() && next_phase != phase {
+                    phase = next_phase;
+                    let state = app_stdout.state::<AppState>();
+                    if let Some(job_state) = next_phase.map(YtDlpPhase::job_state) {
+                        if !has_pending_stop_request(&state, &id_stdout) {
+                            set_job_state(&app_stdout, &state, &id_stdout, job_state);
+                        }
+                    }
+                    if next_phase == Some(YtDlpPhase::Merging) {
+                        emit_progress(
+                            &app_stdout,
+                            DownloadProgress {
+                                id: id_stdout.clone(),
+                                stream: Some(DownloadStream::Merge),
+                                ..DownloadProgress::default()
+                            },
+                        );
+                    }
+                }
+
+                if is_download_archive_skip_line(&line) {
+                    already_archived_for_stdout.store(true, Ordering::SeqCst);
+                }
+
+                if let Some(path_line) = parse_yt_dlp_filepath(&line) {
+                    if let Ok(mut slot) = output_path_for_stdout.lock() {
+                        slot.push(path_line);
+                    }
+                }
+            }
+        }
+    });
+
+    let error_lines_capture: Arc<Mutex<VecDeque<String>>> = Arc::new(Mutex::new(VecDeque::new()));
+
+    let app_stderr = app.clone();
+    let id_stderr = job.id.clone();
+    let error_lines_for_stderr = error_lines_capture.clone();
+    let handle_err = thread::spawn(move || {
+        if let Some(err) = stderr {
+            let reader = BufReader::new(err);
+            for line in reader.lines().flatten() {
+                if let Ok(mut lines) = error_lines_for_stderr.lock() {
+                    if lines.len() == YT_DLP_ERROR_LINES_LIMIT {
+                        lines.pop_front();
+                    }
+                    lines.push_back(line.clone());
+                }
+                emit_log(
+                    &app_stderr,
+                    LogEvent {
+                        id: id_stderr.clone(),
+                        line,
+                        is_error: true,
+                    },
+                );
+            }
+        }
+    });
+
+    let status = loop {
+        let maybe_status = {
+            let mut guard = child.lock().map_err(|_| "Child lock poisoned")?;
+            guard.try_wait().map_err(|e| format!("Wait failed: {e}"))?
+        };
+
+        if let Some(status) = maybe_status {
+            break status;
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    };
+    set_running_job_child(state, &job.id, None)?;
+    let _ = handle_out.join();
+    let _ = handle_err.join();
+
+    let mut output_path = output_path_capture
+        .lock()
+        .ok()
+        .and_then(|guard| select_existing_output_path(&guard));
+    let already_archived = already_archived.load(Ordering::SeqCst);
+    let mut subtitle_paths = Vec::new();
+
+    if status.success() && !already_archived {
+        if output_path.is_none() {
+            output_path = resolve_existing_output_path_fallback(
+                job,
+                &yt_dlp,
+                deno_path.as_deref(),
+                cookies_copy.as_ref().map(CookiesCopy::path).as_deref(),
+                &output_template_for_fallback,
+            );
+        }
+
+        if job.subtitles.write_subs || job.subtitles.write_auto_subs {
+            subtitle_paths = output_path
+                .as_deref()
+                .map(|path| find_subtitle_files(Path::new(path)))
+                .unwrap_or_default();
+        }
+
+        let local_cut_start_time = job
+            .cut_start_time
+            .filter(|_| job.cut_mode == CutMode::Local);
+        if let Some(cut_start_time) = local_cut_start_time {
+            set_job_state(
+                app,
+                state,
+                &job.id,
+                JobState::Cutting {
+                    started_at: current_timestamp_millis(),
+                },
+            );
+            let trimmed_path = trim_downloaded_file(
+                app,
+                state,
+                job,
+                output_path.as_deref(),
+                ffmpeg_location.as_deref(),
+                cut_start_time,
+            )?;
+            output_path = Some(trimmed_path);
+        }
+    }
+
+    let error_lines = error_lines_capture
+        .lock()
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default();
+
+    let removed_segments = removed_segments_capture
+        .lock()
+        .map(|segments| segments.clone())
+        .unwrap_or_default();
+    let chapter_files = chapters_capture
+        .lock()
+        .map(|capture| capture.chapter_files())
+        .unwrap_or_default();
+
+    Ok(DownloadRunResult {
+        exit_code: status.code().unwrap_or(-1),
+        output_path,
+        error_lines,
+        already_archived,
+        subtitle_paths,
+        removed_segments,
+        chapter_files,
+    })
+}
+
+fn resolve_job_rate_limit_kib(state: &AppState, job: &DownloadJob) -> Option<u64> {
+    job.rate_limit_kib
+        .or_else(|| shared_rate_limit_share_kib(state))
+}
+
+fn shared_rate_limit_share_kib(state: &AppState) -> Option<u64> {
+    let (rate_limit, download_window_rate_limit) = {
+        let cfg = state.config.lock().ok()?;
+        (cfg.rate_limit_kib, cfg.download_window_rate_limit_kib)
+    };
+    let shared_limit = match download_window_rate_limit {
+        Some(limit)
+            if is_within_download_window(state)
+                .ok()
+                .flatten()
+                .unwrap_or(false) =>
+        {
+            limit
+        }
+        _ => rate_limit?,
+    };
+
+    let running = state
+        .running_jobs
+        .lock()
+        .map(|running| running.len())
+        .unwrap_or(1);
+    let queued = state
+        .queue
+        .lock()
+        .map(|queue| runnable_job_count(&queue, current_timestamp_millis()))
+        .unwrap_or(0);
+    let max_slots = max_concurrent_downloads(state).unwrap_or(1);
+    Some(split_rate_limit_kib(
+        shared_limit,
+        running + queued,
+        max_slots,
+    ))
+}
+
+// The queue keeps every slot busy while work is waiting, so the shared cap is
+// divided by the number of slots that will be in use rather than by the jobs
+// running right now.
+fn split_rate_limit_kib(limit_kib: u64, active_jobs: usize, max_slots: usize) -> u64 {
+    let parallel = active_jobs.min(max_slots).max(1) as u64;
+    (limit_kib / parallel).max(1)
+}
+
+// yt-dlp cannot change `--limit-rate` while it runs, so when jobs start or
+// finish, downloads whose share of the shared cap changed noticeably are
+// restarted. Like a pause, the `.part` file is kept and the download continues
+// from there.
+fn rebalance_rate_limits(app: &AppHandle, state: &AppState) {
+    let share = shared_rate_limit_share_kib(state);
+    let ids = rebalance_candidates(state, share);
+    for id in ids {
+        emit_log(
+            app,
+            LogEvent {
+                id: id.clone(),
+                line: "[rate] bandwidth share changed; restarting download with the new limit"
+                    .to_string(),
+                is_error: false,
+            },
+        );
+        let _ = request_running_job_pause(app, state, &id, PauseRequest::Rebalance);
+    }
+}
+
+// Only jobs that are downloading are restarted; a cut or transcription in
+// progress would be lost.
+fn rebalance_candidates(state: &AppState, share: Option<u64>) -> Vec<String> {
+    let candidates = {
+        let Ok(running) = state.running_jobs.lock() else {
+            return Vec::new();
+        };
+        running
+            .iter()
+            .filter(|(_, job)| {
+                job.shared_rate_limit_kib
+                    .is_some_and(|applied| rate_share_changed_enough(applied, share))
+            })
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>()
+    };
+    let Ok(lifecycles) = state.job_lifecycles.lock() else {
+        return Vec::new();
+    };
+    candidates
+        .into_iter()
+        .filter(|id| {
+            matches!(
+                lifecycles.get(id).map(|lifecycle| &lifecycle.state),
+                Some(JobState::Downloading { .. })
+            )
+        })
+        .collect()
+}
+
+// Restarting costs a reconnect, so small shifts keep the cap the job started
+// with; a cap that was lifted always restarts.
+fn rate_share_changed_enough(applied_kib: u64, share_kib: Option<u64>) -> bool {
+    match share_kib {
+        Some(share_kib) => {
+            applied_kib.abs_diff(share_kib) * 100
+                >= applied_kib * RATE_LIMIT_REBALANCE_MIN_CHANGE_PERCENT
+        }
+        None => true,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum YtDlpPhase {
+    Downloading,
+    Merging,
+}
+
+impl YtDlpPhase {
+    fn job_state(self) -> JobState {
+        let started_at = current_timestamp_millis();
+        match self {
+            Self::Downloading => JobState::Downloading { started_at },
+            Self::Merging => JobState::Merging { started_at },
+        }
+    }
+}
+
+fn yt_dlp_line_phase(line: &str) -> Option<YtDlpPhase> {
+    if line.starts_with("[download]") {
+        Some(YtDlpPhase::Downloading)
+    } else if line.starts_with("[Merger]") || line.starts_with("[ExtractAudio]") {
+        Some(YtDlpPhase::Merging)
+    } else {
+        None
+    }
+}
+
+fn parse_yt_dlp_progress_line(id: &str, line: &str) -> Option<DownloadProgress> {
+    let mut parts = line.strip_prefix(YT_DLP_PROGRESS_PREFIX)?.splitn(3, ' ');
+    let vcodec = parts.next()?;
+    let acodec = parts.next()?;
+    let progress: serde_json::Value = serde_json::from_str(parts.next()?).ok()?;
+    let u64_field = |key: &str| {
+        progress
+            .get(key)
+            .and_then(json_value_to_i64)
+            .and_then(|value| u64::try_from(value).ok())
+    };
+
+    let downloaded_bytes = u64_field("downloaded_bytes");
+    let total_bytes = u64_field("total_bytes");
+    let total_bytes_estimate = u64_field("total_bytes_estimate");
+    let fragment_index = u64_field("fragment_index");
+    let fragment_count = u64_field("fragment_count");
+    let finished = progress.get("status").and_then(|v| v.as_str()) == Some("finished");
+    let percent = if finished {
+        Some(100.0)
+    } else {
+        match (downloaded_bytes, total_bytes.or(total_bytes_estimate)) {
+            (Some(done), Some(total)) if total > 0 => Some(done as f64 / total as f64),
+            _ => match (fragment_index, fragment_count) {
+                (Some(index), Some(count)) if count > 0 => Some(index as f64 / count as f64),
+                _ => None,
+            },
+        }
+        .map(|ratio| (ratio * 100.0).clamp(0.0, 100.0) as f32)
+    };
+
+    Some(DownloadProgress {
+        id: id.to_string(),
+        percent,
+        speed: progress
+            .get("speed")
+            .and_then(|v| v.as_f64())
+            .filter(|speed| speed.is_finite() && *speed > 0.0)
+            .map(format_transfer_rate),
+        eta: u64_field("eta").map(format_eta_seconds),
+        downloaded_bytes,
+        total_bytes,
+        total_bytes_estimate,
+        fragment_index,
+        fragment_count,
+        stream: download_stream_for_codecs(vcodec, acodec),
+    })
+}
+
+// Missing template fields are rendered as "NA" by yt-dlp.
+fn download_stream_for_codecs(vcodec: &str, acodec: &str) -> Option<DownloadStream> {
+    let has_codec = |codec: &str| !matches!(codec, "none" | "NA" | "");
+    match (has_codec(vcodec), has_codec(acodec)) {
+        (true, _) => Some(DownloadStream::Video),
+        (false, true) => Some(DownloadStream::Audio),
+        (false, false) => None,
+    }
+}
+
+fn format_transfer_rate(bytes_per_second: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes_per_second;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.2}{}/s", UNITS[unit])
+}
+
+fn format_eta_seconds(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+fn is_download_archive_skip_line(line: &str) -> bool {
+    line.contains("has already been recorded in the archive")
+}
+
+fn has_pending_stop_request(state: &AppState, id: &str) -> bool {
+    let cancelling = state
+        .cancel_requested
+        .lock()
+        .map(|cancel| cancel.contains(id))
+        .unwrap_or(false);
+    let pausing = state
+        .pause_requested
+        .lock()
+        .map(|pause| pause.contains_key(id))
+        .unwrap_or(false);
+    cancelling || pausing
+}
+
+fn set_running_job_shared_rate_limit(
+    state: &AppState,
+    id: &str,
+    shared_rate_limit_kib: Option<u64>,
+) -> Result<(), String> {
+    let mut running = state
+        .running_jobs
+        .lock()
+        .map_err(|_| "Running jobs lock poisoned")?;
+    if let Some(running_job) = running.get_mut(id) {
+        running_job.shared_rate_limit_kib = shared_rate_limit_kib;
+    }
+    Ok(())
+}
+
+fn set_running_job_child(
+    state: &AppState,
+    id: &str,
+    child: Option<Arc<Mutex<Child>>>,
+) -> Result<(), String> {
+    let mut running = state
+        .running_jobs
+        .lock()
+        .map_err(|_| "Running jobs lock poisoned")?;
+    if let Some(running_job) = running.get_mut(id) {
+        running_job.child = child;
+    }
+    Ok(())
+}
+
+fn trim_downloaded_file(
+    app: &AppHandle,
+    state: &AppState,
+    job: &DownloadJob,
+    output_path: Option<&str>,
+    ffmpeg_location: Option<&str>,
+    cut_start_time: f64,
+) -> Result<String, String> {
+    let input_path = output_path
+        .ok_or_else(|| "Could not determine downloaded file path for timestamp cut".to_string())?;
+    let input_path = Path::new(input_path);
+    if !input_path.exists() {
+        return Err(format!(
+            "Downloaded file not found for timestamp cut: {}",
+            input_path.to_string_lossy()
+        ));
+    }
+
+    let ffmpeg_location =
+        ffmpeg_location.ok_or_else(|| "ffmpeg not available for timestamp cut".to_string())?;
+    let ffmpeg_path = Path::new(ffmpeg_location).join(ffmpeg_tool_name());
+    if !ffmpeg_path.exists() {
+        return Err(format!(
+            "ffmpeg executable not found for timestamp cut: {}",
+            ffmpeg_path.to_string_lossy()
+        ));
+    }
+
+    let cut_timestamp = format_yt_dlp_timestamp(cut_start_time);
+    emit_log(
+        app,
+        LogEvent {
+            id: job.id.clone(),
+            line: format!(
+                "[cut] trimming local file from {cut_timestamp}s ({} cut)",
+                job.cut_precision.as_str()
+            ),
+            is_error: false,
+        },
+    );
+
+    let final_path = build_local_cut_output_path(input_path)?;
+    let temp_path = build_cut_sidecar_path(input_path, "cut")?;
+    let backup_path = build_cut_sidecar_path(input_path, "original")?;
+
+    let cut_result = match job.cut_precision {
+        CutPrecision::Fast => run_fast_timestamp_cut(
+            app,
+            state,
+            job,
+            &ffmpeg_path,
+            input_path,
+            &temp_path,
+            cut_start_time,
+        ),
+        CutPrecision::Accurate => match plan_accurate_cut(Path::new(ffmpeg_location), input_path) {
+            Ok(plan) => run_accurate_timestamp_cut(
+                app,
+                state,
+                job,
+                &plan,
+                input_path,
+                &temp_path,
+                cut_start_time,
+            ),
+            Err(reason) => {
+                emit_log(
+                    app,
+                    LogEvent {
+                        id: job.id.clone(),
+                        line: format!("[cut] warning: {reason}; falling back to a fast cut"),
+                        is_error: false,
+                    },
+                );
+                run_fast_timestamp_cut(
+                    app,
+                    state,
+                    job,
+                    &ffmpeg_path,
+                    input_path,
+                    &temp_path,
+                    cut_start_time,
+                )
+            }
+        },
+    };
+    if let Err(err) = cut_result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+
+    if !temp_path.exists() {
+        return Err("ffmpeg finished but no cut file was created".to_string());
+    }
+
+    fs::rename(input_path, &backup_path)
+        .map_err(|e| format!("Could not back up full file before cut replace: {e}"))?;
+    if final_path.exists() {
+        let _ = fs::remove_file(&final_path);
+    }
+    if let Err(err) = fs::rename(&temp_path, &final_path) {
+        let _ = fs::rename(&backup_path, input_path);
+        return Err(format!("Could not move cut file into final path: {err}"));
+    }
+    let _ = fs::remove_file(&backup_path);
+
+    emit_log(
+        app,
+        LogEvent {
+            id: job.id.clone(),
+            line: format!("[cut] saved: {}", final_path.to_string_lossy()),
+            is_error: false,
+        },
+    );
+
+    Ok(final_path.to_string_lossy().to_string())
+}
+
+fn run_fast_timestamp_cut(
+    app: &AppHandle,
+    state: &AppState,
+    job: &DownloadJob,
+    ffmpeg_path: &Path,
+    input_path: &Path,
+    temp_path: &Path,
+    cut_start_time: f64,
+) -> Result<(), String> {
+    let mut command = timestamp_cut_command(ffmpeg_path, job, input_path, cut_start_time);
+    command.args(["-map", "0", "-c", "copy"]);
+    let cut_duration = job
+        .cut_end_time
+        .map(|end| end - cut_start_time)
+        .filter(|duration| *duration > 0.0);
+    run_timestamp_cut_command(app, state, job, command, temp_path, cut_duration)
+}
+
+fn timestamp_cut_command(
+    ffmpeg_path: &Path,
+    job: &DownloadJob,
+    input_path: &Path,
+    cut_start_time: f64,
+) -> Command {
+    let mut command = Command::new(ffmpeg_path);
+    command
+        .args([
+            "-hide_banner",
+            "-y",
+            "-nostats",
+            "-progress",
+            "pipe:1",
+            "-ss",
+            format_yt_dlp_timestamp(cut_start_time).as_str(),
+            "-i",
+        ])
+        .arg(input_path);
+    if let Some(cut_end_time) = job.cut_end_time {
+        command.arg("-t");
+        command.arg(format_yt_dlp_timestamp(cut_end_time - cut_start_time));
+    }
+    command
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AccurateCutPlan {
+    ffmpeg_path: PathBuf,
+    codec_args: Vec<String>,
+    duration_seconds: Option<f64>,
+}
+
+// An Err means the file cannot be re-encoded here, and the caller falls back
+// to a fast cut instead of failing the job.
+fn plan_accurate_cut(ffmpeg_location: &Path, input_path: &Path) -> Result<AccurateCutPlan, String> {
+    let ffmpeg_path = ffmpeg_location.join(ffmpeg_tool_name());
+    let probe = probe_media_file(&ffmpeg_location.join(ffprobe_tool_name()), input_path)?;
+    let encoders = list_ffmpeg_encoders(&ffmpeg_path)?;
+    let codec_args = accurate_cut_codec_args(&probe.streams, &encoders)?;
+    Ok(AccurateCutPlan {
+        ffmpeg_path,
+        codec_args,
+        duration_seconds: probe.duration_seconds,
+    })
+}
+
+fn run_accurate_timestamp_cut(
+    app: &AppHandle,
+    state: &AppState,
+    job: &DownloadJob,
+    plan: &AccurateCutPlan,
+    input_path: &Path,
+    temp_path: &Path,
+    cut_start_time: f64,
+) -> Result<(), String> {
+    let cut_duration = job
+        .cut_end_time
+        .or(plan.duration_seconds)
+        .map(|end| end - cut_start_time)
+        .filter(|duration| *duration > 0.0);
+    let mut command = timestamp_cut_command(&plan.ffmpeg_path, job, input_path, cut_start_time);
+    command.args(["-map", "0"]).args(&plan.codec_args);
+    run_timestamp_cut_command(app, state, job, command, temp_path, cut_duration)
+}
+
+// Both cut modes run like a yt-dlp child: registered on the running job so
+// cancel and pause can kill it, with `-progress pipe:1` feeding the job's
+// progress bar.
+fn run_timestamp_cut_command(
+    app: &AppHandle,
+    state: &AppState,
+    job: &DownloadJob,
+    mut command: Command,
+    temp_path: &Path,
+    cut_duration: Option<f64>,
+) -> Result<(), String> {
+    command
+        .args(["-avoid_negative_ts", "make_zero"])
+        .arg(temp_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if has_pending_stop_request(state, &job.id) {
+        return Err("Timestamp cut stopped before it started".to_string());
+    }
+
+    emit_progress(
+        app,
+        DownloadProgress {
+            id: job.id.clone(),
+            percent: Some(0.0),
+            speed: Some("cutting".to_string()),
+            eta: Some("-".to_string()),
+            ..DownloadProgress::default()
+        },
+    );
+
+    let child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run ffmpeg timestamp cut: {e}"))?;
+    let child = Arc::new(Mutex::new(child));
+    set_running_job_child(state, &job.id, Some(child.clone()))?;
+
+    let (stdout, stderr) = {
+        let mut guard = child.lock().map_err(|_| "Child lock poisoned")?;
+        (guard.stdout.take(), guard.stderr.take())
+    };
+
+    let app_stdout = app.clone();
+    let job_id_stdout = job.id.clone();
+    let handle_out = thread::spawn(move || {
+        if let Some(out) = stdout {
+            let mut block = FfmpegProgressBlock::default();
+            for line in BufReader::new(out).lines().map_while(Result::ok) {
+                if !block.apply_line(&line) {
+                    continue;
+                }
+                emit_progress(
+                    &app_stdout,
+                    DownloadProgress {
+                        id: job_id_stdout.clone(),
+                        percent: block.percent(cut_duration),
+                        speed: Some(match block.speed {
+                            Some(speed) => format!("cutting {speed:.2}x"),
+                            None => "cutting".to_string(),
+                        }),
+                        eta: Some(
+                            block
+                                .eta_seconds(cut_duration)
+                                .map(|seconds| format_eta_seconds(seconds.round() as u64))
+                                .unwrap_or_else(|| "-".to_string()),
+                        ),
+                        ..DownloadProgress::default()
+                    },
+                );
+            }
+        }
+    });
+    let handle_err = thread::spawn(move || {
+        stderr
+            .map(|err| {
+                BufReader::new(err)
+                    .lines()
+                    .map_while(Result::ok)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    });
+
+    let status = loop {
+        let maybe_status = {
+            let mut guard = child.lock().map_err(|_| "Child lock poisoned")?;
+            guard.try_wait().map_err(|e| format!("Wait failed: {e}"))?
+        };
+
+        if let Some(status) = maybe_status {
+            break status;
+        }
+
+        thread::sleep(Duration::from_millis(100));
+    };
+    set_running_job_child(state, &job.id, None)?;
+    let _ = handle_out.join();
+    let stderr_lines = handle_err.join().unwrap_or_default();
+
+    for line in stderr_lines {
+        emit_log(
+            app,
+            LogEvent {
+                id: job.id.clone(),
+                line: format!("[ffmpeg] {line}"),
+                is_error: !status.success(),
+            },
+        );
+    }
+
+    if !status.success() {
+        if has_pending_stop_request(state, &job.id) {
+            return Err("Timestamp cut stopped".to_string());
+        }
+        let code = status.code().unwrap_or(-1);
+        return Err(format!("ffmpeg timestamp cut failed with exit code {code}"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProbedStream {
+    index: usize,
+    codec_type: String,
+    codec_name: String,
+    attached_pic: bool,
+    bit_rate: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct MediaProbe {
+    duration_seconds: Option<f64>,
+    streams: Vec<ProbedStream>,
+}
+
+fn probe_media_file(ffprobe_path: &Path, input_path: &Path) -> Result<MediaProbe, String> {
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration:stream=index,codec_type,codec_name,bit_rate:stream_disposition=attached_pic:stream_tags=BPS",
+            "-of",
+            "json",
+        ])
+        .arg(input_path)
+        .output()
+        .map_err(|e| format!("ffprobe failed: {e}"))?;
+    if !output.status.success() {
+        let code = output.status.code().unwrap_or(-1);
+        return Err(format!("ffprobe failed with exit code {code}"));
+    }
+    parse_media_probe(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_media_probe(raw: &str) -> Result<MediaProbe, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(raw).map_err(|e| format!("ffprobe output parse failed: {e}"))?;
+    let duration_seconds = value
+        .pointer("/format/duration")
+        .and_then(|duration| duration.as_str())
+        .and_then(|duration| duration.parse::<f64>().ok())
+        .filter(|duration| duration.is_finite() && *duration > 0.0);
+    let streams = value
+        .get("streams")
+        .and_then(|streams| streams.as_array())
+        .map(|streams| {
+            streams
+                .iter()
+                .filter_map(|stream| {
+                    Some(ProbedStream {
+                        index: stream.get("index")?.as_u64()? as usize,
+                        codec_type: stream.get("codec_type")?.as_str()?.to_string(),
+                        codec_name: stream
+                            .get("codec_name")
+                            .and_then(|name| name.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        attached_pic: stream
+                            .pointer("/disposition/attached_pic")
+                            .and_then(|flag| flag.as_i64())
+                            == Some(1),
+                        // Matroska keeps the bitrate in a `BPS` tag instead.
+                        bit_rate: stream
+                            .get("bit_rate")
+                            .or_else(|| stream.pointer("/tags/BPS"))
+                            .and_then(|bit_rate| bit_rate.as_str())
+                            .and_then(|bit_rate| bit_rate.parse::<u64>().ok())
+                            .filter(|bit_rate| *bit_rate > 0),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(MediaProbe {
+        duration_seconds,
+        streams,
+    })
+}
+
+fn list_ffmpeg_encoders(ffmpeg_path: &Path) -> Result<HashSet<String>, String> {
+    let output = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-encoders"])
+        .output()
+        .map_err(|e| format!("ffmpeg encoder list failed: {e}"))?;
+    if !output.status.success() {
+        let code = output.status.code().unwrap_or(-1);
+        return Err(format!("ffmpeg encoder list failed with exit code {code}"));
+    }
+    Ok(parse_ffmpeg_encoders(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+// `ffmpeg -encoders` prints a legend, a ` ------` rule, then one
+// `<flags> <name> <description>` line per encoder.
+fn parse_ffmpeg_encoders(raw: &str) -> HashSet<String> {
+    raw.lines()
+        .skip_while(|line| !line.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+        .collect()
+}
+
+// AV1 is re-encoded with SVT-AV1 only; libaom-av1 is too slow for a cut.
+fn reencode_encoder_for_codec(codec_name: &str) -> Option<&'static str> {
+    let encoder = match codec_name {
+        "h264" => "libx264",
+        "hevc" => "libx265",
+        "vp8" => "libvpx",
+        "vp9" => "libvpx-vp9",
+        "av1" => "libsvtav1",
+        "mpeg4" => "mpeg4",
+        "aac" => "aac",
+        "mp3" => "libmp3lame",
+        "opus" => "libopus",
+        "vorbis" => "libvorbis",
+        "flac" => "flac",
+        "alac" => "alac",
+        "ac3" => "ac3",
+        "eac3" => "eac3",
+        codec if codec.starts_with("pcm_") => return Some(pcm_encoder_name(codec)),
+        _ => return None,
+    };
+    Some(encoder)
+}
+
+fn pcm_encoder_name(codec_name: &str) -> &'static str {
+    match codec_name {
+        "pcm_s24le" => "pcm_s24le",
+        "pcm_s32le" => "pcm_s32le",
+        "pcm_f32le" => "pcm_f32le",
+        _ => "pcm_s16le",
+    }
+}
+
+// Video and audio are re-encoded with the encoder matching their source codec;
+// cover art, subtitles and data streams are copied untouched.
+fn accurate_cut_codec_args(
+    streams: &[ProbedStream],
+    encoders: &HashSet<String>,
+) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for stream in streams {
+        let reencode = match stream.codec_type.as_str() {
+            "video" => !stream.attached_pic,
+            "audio" => true,
+            _ => false,
+        };
+        let encoder = if reencode {
+            let encoder = reencode_encoder_for_codec(&stream.codec_name).ok_or_else(|| {
+                format!(
+                    "accurate cut cannot re-encode {} streams",
+                    stream.codec_name
+                )
+            })?;
+            if !encoders.contains(encoder) {
+                return Err(format!(
+                    "ffmpeg has no {encoder} encoder for {} streams",
+                    stream.codec_name
+                ));
+            }
+            encoder
+        } else {
+            "copy"
+        };
+        args.push(format!("-c:{}", stream.index));
+        args.push(encoder.to_string());
+        if reencode {
+            args.extend(reencode_quality_args(stream, encoder));
+        }
+    }
+    if args.is_empty() {
+        return Err("ffprobe found no streams to cut".to_string());
+    }
+    Ok(args)
+}
+
+// Encoder defaults are tuned for small files, so a cut would come out visibly
+// worse than its source. The source bitrate is kept when ffprobe reports one;
+// otherwise video gets a near-transparent quality level and lossy audio a
+// fixed bitrate. Lossless codecs need nothing.
+fn reencode_quality_args(stream: &ProbedStream, encoder: &str) -> Vec<String> {
+    let index = stream.index;
+    if let Some(bit_rate) = stream.bit_rate {
+        if !matches!(encoder, "flac" | "alac") && !encoder.starts_with("pcm_") {
+            return vec![format!("-b:{index}"), bit_rate.to_string()];
+        }
+    }
+    let args: &[(&str, &str)] = match encoder {
+        "libx264" => &[("crf", "18")],
+        "libx265" => &[("crf", "20")],
+        "libvpx" => &[("crf", "10"), ("b", "8M")],
+        "libvpx-vp9" => &[("crf", "31"), ("b", "0")],
+        "libsvtav1" => &[("crf", "30")],
+        "mpeg4" => &[("q", "3")],
+        "libopus" => &[("b", "160k")],
+        "aac" | "libmp3lame" | "libvorbis" | "ac3" | "eac3" => &[("b", "192k")],
+        _ => &[],
+    };
+    args.iter()
+        .flat_map(|(option, value)| [format!("-{option}:{index}"), value.to_string()])
+        .collect()
+}
+
+// One block of `-progress` output: key=value lines closed by `progress=...`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FfmpegProgressBlock {
+    out_time_seconds: Option<f64>,
+    speed: Option<f64>,
+}
+
+impl FfmpegProgressBlock {
+    // Returns true when the line closes a block and progress should be emitted.
+    fn apply_line(&mut self, line: &str) -> bool {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            return false;
+        };
+        match key {
+            // Despite the name, `out_time_ms` is in microseconds as well.
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<i64>() {
+                    self.out_time_seconds = Some(micros.max(0) as f64 / 1_000_000.0);
+                }
+                false
+            }
+            "speed" => {
+                self.speed = value
+                    .trim()
+                    .trim_end_matches('x')
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|speed| speed.is_finite() && *speed > 0.0);
+                false
+            }
+            "progress" => true,
+            _ => false,
+        }
+    }
+
+    fn percent(&self, duration: Option<f64>) -> Option<f32> {
+        let out_time = self.out_time_seconds?;
+        let duration = duration?;
+        Some(((out_time / duration) * 100.0).clamp(0.0, 100.0) as f32)
+    }
+
+    fn eta_seconds(&self, duration: Option<f64>) -> Option<f64> {
+        let remaining = (duration? - self.out_time_seconds?).max(0.0);
+        Some(remaining / self.speed?)
+    }
+}
+
+fn download_section_spec(cut_start_time: f64, cut_end_time: Option<f64>) -> String {
+    let end = cut_end_time
+        .map(format_yt_dlp_timestamp)
+        .unwrap_or_else(|| "inf".to_string());
+    format!("*{}-{end}", format_yt_dlp_timestamp(cut_start_time))
+}
+
+fn build_cut_sidecar_path(input_path: &Path, label: &str) -> Result<PathBuf, String> {
+    let parent = input_path
+        .parent()
+        .ok_or_else(|| "Downloaded file has no parent directory".to_string())?;
+    let extension = input_path
+        .extension()
+        .and_then(|value| value.to_str())
+        .filter(|value| !value.is_empty())
+        .unwrap_or("tmp");
+    Ok(parent.join(format!(
+        ".pinefetch-{label}-{}.{}",
+        Uuid::new_v4(),
+        extension
+    )))
+}
+
+// The download already carries the range suffix, so the clip only drops the
+// source marker; without one the clip replaces the download in place.
+fn build_local_cut_output_path(input_path: &Path) -> Result<PathBuf, String> {
+    let parent = input_path
+        .parent()
+        .ok_or_else(|| "Downloaded file has no parent directory".to_string())?;
+    let stem = input_path
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .ok_or_else(|| "Downloaded file has no usable file name".to_string())?;
+
+    let mut filename = stem
+        .strip_suffix(LOCAL_CUT_SOURCE_MARKER)
+        .unwrap_or(stem)
+        .to_string();
+    if let Some(extension) = input_path.extension().and_then(|value| value.to_str()) {
+        if !extension.is_empty() {
+            filename.push('.');
+            filename.push_str(extension);
+        }
+    }
+
+    Ok(parent.join(filename))
+}
+
+fn format_cut_range_filename_suffix(cut_start_time: f64, cut_end_time: Option<f64>) -> String {
+    let mut suffix = format_timestamp_filename_suffix(cut_start_time);
+    if let Some(cut_end_time) = cut_end_time {
+        let end_suffix = format_timestamp_filename_suffix(cut_end_time);
+        suffix.push('-');
+        suffix.push_str(end_suffix.trim_start_matches("_t"));
+    }
+    suffix
+}
+
+fn format_timestamp_filename_suffix(seconds: f64) -> String {
+    let mut timestamp = format_yt_dlp_timestamp(seconds);
+    timestamp = timestamp
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("_t{timestamp}")
+}
+
+fn select_existing_output_path(candidates: &[String]) -> Option<String> {
+    let existing = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let path = Path::new(candidate.as_str());
+            let metadata = path.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            Some((index, candidate, metadata.len(), is_format_part_path(path)))
+        })
+        .collect::<Vec<_>>();
+
+    if let Some((_, candidate, _, _)) = existing.iter().rev().find(|(_, _, _, is_part)| !*is_part) {
+        return Some((*candidate).clone());
+    }
+
+    existing
+        .into_iter()
+        .max_by(|left, right| left.2.cmp(&right.2).then_with(|| left.0.cmp(&right.0)))
+        .map(|(_, candidate, _, _)| candidate.clone())
+}
+
+fn is_format_part_path(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|value| value.to_str())
+        .and_then(|stem| stem.rsplit_once(".f"))
+        .map(|(_, suffix)| !suffix.is_empty() && suffix.chars().all(|ch| ch.is_ascii_digit()))
+        .unwrap_or(false)
+}
+
+fn parse_yt_dlp_filepath(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    for prefix in [
+        "[download] Destination:",
+        "[ExtractAudio] Destination:",
+        "[Metadata] Writing metadata to:",
+    ] {
+        if let Some(candidate) = trimmed.strip_prefix(prefix) {
+            return normalize_filepath_candidate(candidate);
+        }
+    }
+
+    if let Some(candidate) = trimmed.strip_prefix("[Merger] Merging formats into ") {
+        return normalize_filepath_candidate(candidate);
+    }
+
+    if let Some(candidate) = trimmed
+        .strip_prefix("[download] ")
+        .and_then(|value| value.strip_suffix(" has already been downloaded"))
+    {
+        return normalize_filepath_candidate(candidate);
+    }
+
+    if trimmed.starts_with('[') {
+        return None;
+    }
+
+    normalize_filepath_candidate(trimmed)
+}
+
+fn normalize_filepath_candidate(raw: &str) -> Option<String> {
+    let mut candidate = raw.trim();
+    if candidate.len() >= 2 {
+        let bytes = candidate.as_bytes();
+        if (bytes[0] == b'"' && bytes[candidate.len() - 1] == b'"')
+            || (bytes[0] == b'\'' && bytes[candidate.len() - 1] == b'\'')
+        {
+            candidate = &candidate[1..candidate.len() - 1];
+        }
+    }
+    let candidate = candidate.trim();
+    if matches!(candidate, "" | "NA" | "N/A" | "None" | "null") {
+        return None;
+    }
+    if candidate.starts_with("http://") || candidate.starts_with("https://") {
+        return None;
+    }
+    Some(candidate.to_string())
+}
+
+fn resolve_existing_output_path_fallback(
+    job: &DownloadJob,
+    yt_dlp: &str,
+    deno_path: Option<&str>,
+    cookies_path: Option<&str>,
+    output_template: &str,
+) -> Option<String> {
+    let expected_path =
+        probe_expected_output_filename(job, yt_dlp, deno_path, cookies_path, output_template)
+            .ok()??;
+    let candidates = existing_output_candidates_from_expected(&expected_path, job);
+    select_existing_output_path(&candidates)
+}
+
+fn probe_expected_output_filename(
+    job: &DownloadJob,
+    yt_dlp: &str,
+    deno_path: Option<&str>,
+    cookies_path: Option<&str>,
+    output_template: &str,
+) -> Result<Option<String>, String> {
+    let mut command = Command::new(yt_dlp);
+    command.args([
+        "--simulate",
+        "--no-playlist",
+        "--no-warnings",
+        "--print",
+        "filename",
+        "-f",
+    ]);
+    command.arg(&job.format);
+    command.arg("-o");
+    command.arg(output_template);
+
+    if let Some(deno) = deno_path {
+        command.arg("--js-runtimes");
+        command.arg(format!("deno:{deno}"));
+    }
+
+    if let Some(cookies) = cookies_path {
+        command.arg("--cookies");
+        command.arg(cookies);
+    }
+
+    if job.extract_audio {
+        command.arg("--extract-audio");
+        if let Some(fmt) = job.audio_format.as_ref() {
+            command.arg("--audio-format");
+            command.arg(fmt);
+        }
+    }
+
+    command.arg(&job.url);
+
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to probe output filename with yt-dlp: {e}"))?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .rev()
+        .find_map(parse_yt_dlp_filepath))
+}
+
+fn existing_output_candidates_from_expected(expected_path: &str, job: &DownloadJob) -> Vec<String> {
+    let mut candidates = vec![expected_path.to_string()];
+    let expected = Path::new(expected_path);
+
+    if job.extract_audio {
+        if let Some(audio_format) = job.audio_format.as_deref() {
+            candidates.push(
+                expected
+                    .with_extension(audio_format)
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+    }
+
+    if job.cut_mode == CutMode::Local && job.cut_start_time.is_some() {
+        for candidate in candidates.clone() {
+            if let Ok(cut_path) = build_local_cut_output_path(Path::new(&candidate)) {
+                candidates.push(cut_path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    candidates.extend(related_existing_output_paths(expected));
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+fn related_existing_output_paths(expected: &Path) -> Vec<String> {
+    let Some(parent) = expected.parent() else {
+        return Vec::new();
+    };
+    let Some(expected_stem) = expected.file_stem().and_then(|value| value.to_str()) else {
+        return Vec::new();
+    };
+    let format_part_prefix = format!("{expected_stem}.f");
+
+    fs::read_dir(parent)
+        .ok()
+        .into_iter()
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if !path.is_file() {
+                return None;
+            }
+            let stem = path.file_stem().and_then(|value| value.to_str())?;
+            if is_subtitle_path(&path) {
+                return None;
+            }
+            if stem == expected_stem || stem.starts_with(&format_part_prefix) {
+                Some(path.to_string_lossy().to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn ffmpeg_tool_name() -> &'static str {
+    if cfg!(windows) {
+        "ffmpeg.exe"
+    } else {
+        "ffmpeg"
+    }
+}
+
+fn ffprobe_tool_name() -> &'static str {
+    if cfg!(windows) {
+        "ffprobe.exe"
+    } else {
+        "ffprobe"
+    }
+}
+
+fn ffmpeg_tool_is_usable(path: &Path) -> bool {
+    if !path.is_file() {
+        return false;
+    }
+
+    Command::new(path)
+        .arg("-version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+fn has_usable_ffmpeg_tools_in_dir(dir: &Path) -> bool {
+    ffmpeg_tool_is_usable(&dir.join(ffmpeg_tool_name()))
+        && ffmpeg_tool_is_usable(&dir.join(ffprobe_tool_name()))
+}
+
+fn normalize_ffmpeg_location(path: &Path) -> Option<String> {
+    if path.is_dir() {
+        if has_usable_ffmpeg_tools_in_dir(path) {
+            return Some(path.to_string_lossy().to_string());
+        }
+        return None;
+    }
+
+    if path.is_file() {
+        if let Some(parent) = path.parent() {
+            if has_usable_ffmpeg_tools_in_dir(parent) {
+                return Some(parent.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    None
+}
+
+fn resolve_bundled_ffmpeg_location(app: &AppHandle) -> Option<String> {
+    for relative in [
+        "ffmpeg-runtime/bin",
+        "ffmpeg-runtime",
+        "resources/ffmpeg-runtime/bin",
+        "resources/ffmpeg-runtime",
+    ] {
+        if let Some(path) = app.path_resolver().resolve_resource(relative) {
+            if let Some(location) = normalize_ffmpeg_location(&path) {
+                return Some(location);
+            }
+        }
+    }
+    None
+}
+
+fn resolve_ffmpeg_location(app: &AppHandle, yt_dlp_path: &str) -> Option<String> {
+    if let Ok(raw) = std::env::var("PINEFETCH_FFMPEG_LOCATION") {
+        let trimmed = raw.trim();
+        if !trimmed.is_empty() {
+            if let Some(location) = normalize_ffmpeg_location(Path::new(trimmed)) {
+                return Some(location);
+            }
+        }
+    }
+
+    if let Some(location) = resolve_bundled_ffmpeg_location(app) {
+        return Some(location);
+    }
+
+    if let Some(location) = normalize_ffmpeg_location(Path::new(yt_dlp_path)) {
+        return Some(location);
+    }
+
+    for candidate in ["/opt/homebrew/bin", "/usr/local/bin"] {
+        if let Some(location) = normalize_ffmpeg_location(Path::new(candidate)) {
+            return Some(location);
+        }
+    }
+
+    if let Some(ffmpeg_path) = find_in_path("ffmpeg") {
+        if let Some(location) = normalize_ffmpeg_location(Path::new(&ffmpeg_path)) {
+            return Some(location);
+        }
+    }
+
+    if let Some(ffprobe_path) = find_in_path("ffprobe") {
+        if let Some(location) = normalize_ffmpeg_location(Path::new(&ffprobe_path)) {
+            return Some(location);
+        }
+    }
+
+    None
+}
+
+fn resolve_bundled_python(app: &AppHandle) -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let candidates = vec![
+        "whisper-runtime/Scripts/python.exe",
+        "resources/whisper-runtime/Scripts/python.exe",
+    ];
+
+    #[cfg(not(target_os = "windows"))]
+    let candidates = vec![
+        "whisper-runtime/bin/python3.12",
+        "whisper-runtime/bin/python3.11",
+        "whisper-runtime/bin/python3.10",
+        "whisper-runtime/bin/python3",
+        "whisper-runtime/bin/python",
+        "resources/whisper-runtime/bin/python3.12",
+        "resources/whisper-runtime/bin/python3.11",
+        "resources/whisper-runtime/bin/python3.10",
+        "resources/whisper-runtime/bin/python3",
+        "resources/whisper-runtime/bin/python",
+    ];
+
+    for relative in candidates {
+        if let Some(path) = app.path_resolver().resolve_resource(relative) {
+            if path.exists() {
+                return Some(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    None
+}
+
+fn resolve_bundled_deno(app: &AppHandle) -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let candidates = vec![
+        "deno-runtime/bin/deno.exe",
+        "resources/deno-runtime/bin/deno.exe",
+    ];
+
+    #[cfg(not(target_os = "windows"))]
+    let candidates = vec!["deno-runtime/bin/deno", "resources/deno-runtime/bin/deno"];
+
+    for relative in candidates {
+        if let Some(path) = app.path_resolver().resolve_resource(relative) {
+            if path.exists() {
+                return Some(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    None
+}
+
+fn resolve_deno_executable(app: &AppHandle) -> Option<String> {
+    if let Ok(raw) = std::env::var("PINEFETCH_DENO_PATH") {
+        let trimmed = raw.trim();
+        if !trimmed.is_empty() && Path::new(trimmed).exists() {
+            return Some(trimmed.to_string());
+        }
+    }
+
+    if let Some(path) = resolve_bundled_deno(app) {
+        return Some(path);
+    }
+
+    find_in_path("deno")
+}
+
+fn resolve_python_executable(app: &AppHandle) -> Option<String> {
+    if let Ok(raw) = std::env::var("PINEFETCH_FASTER_WHISPER_PYTHON") {
+        let trimmed = raw.trim();
+        if !trimmed.is_empty() && Path::new(trimmed).exists() {
+            return Some(trimmed.to_string());
+        }
+    }
+
+    if let Some(path) = resolve_bundled_python(app) {
+        return Some(path);
+    }
+
+    for candidate in [
+        "python3.12",
+        "python3.11",
+        "python3.10",
+        "python3",
+        "python",
+    ] {
+        if let Some(path) = find_in_path(candidate) {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+fn run_faster_whisper_transcription(
+    app: &AppHandle,
+    job: &DownloadJob,
+    output_path: Option<&str>,
+) -> Result<String, String> {
+    let audio_path = output_path
+        .ok_or_else(|| "Could not determine downloaded file path for transcription".to_string())?;
+    if !Path::new(audio_path).exists() {
+        return Err(format!(
+            "Downloaded file not found for transcription: {audio_path}"
+        ));
+    }
+
+    let python = resolve_python_executable(app).ok_or_else(|| {
+    "No Python runtime found for faster-whisper (bundled runtime missing and no compatible Python in PATH)"
+      .to_string()
+  })?;
+    emit_log(
+        app,
+        LogEvent {
+            id: job.id.clone(),
+            line: format!("[faster-whisper] using python: {python}"),
+            is_error: false,
+        },
+    );
+
+    let transcript_path = Path::new(audio_path).with_extension("txt");
+    let transcript_path_str = transcript_path.to_string_lossy().to_string();
+    let model_name = std::env::var("PINEFETCH_FASTER_WHISPER_MODEL")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "base".to_string());
+
+    let mut command = Command::new(python);
+    command
+        .arg("-c")
+        .arg(FASTER_WHISPER_TRANSCRIBE_SNIPPET)
+        .arg(audio_path)
+        .arg(&transcript_path_str)
+        .arg(&model_name)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start faster-whisper transcription: {e}"))?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let app_stdout = app.clone();
+    let job_id_stdout = job.id.clone();
+    let handle_out = thread::spawn(move || {
+        if let Some(out) = stdout {
+            let reader = BufReader::new(out);
+            for line in reader.lines().flatten() {
+                emit_log(
+                    &app_stdout,
+                    LogEvent {
+                        id: job_id_stdout.clone(),
+                        line: format!("[faster-whisper] {line}"),
+                        is_error: false,
+                    },
+                );
+            }
+        }
+    });
+
+    let app_stderr = app.clone();
+    let job_id_stderr = job.id.clone();
+    let handle_err = thread::spawn(move || {
+        if let Some(err) = stderr {
+            let reader = BufReader::new(err);
+            for line in reader.lines().flatten() {
+                emit_log(
+                    &app_stderr,
+                    LogEvent {
+                        id: job_id_stderr.clone(),
+                        line: format!("[faster-whisper] {line}"),
+                        is_error: true,
+                    },
+                );
+            }
+        }
+    });
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed while waiting for faster-whisper: {e}"))?;
+    let _ = handle_out.join();
+    let _ = handle_err.join();
+
+    if !status.success() {
+        let code = status.code().unwrap_or(-1);
+        return Err(format!(
+      "faster-whisper failed (exit code {code}). Ensure Python deps are installed (`pip install faster-whisper`)."
+    ));
+    }
+
+    if !transcript_path.exists() {
+        return Err("faster-whisper finished but no transcript file was created".to_string());
+    }
+
+    Ok(transcript_path_str)
+}
+
+// `template` is relative to the output folder and may contain `/` for
+// subfolders. The suffix and `.%(ext)s` are always added here, so range and
+// audio outputs can still be found from the probed file name.
+fn build_output_template(
+    output_dir: &str,
+    template: &str,
+    filename_suffix: Option<&str>,
+) -> String {
+    let mut path = PathBuf::from(output_dir);
+    let suffix = filename_suffix.unwrap_or("");
+    path.push(format!("{template}{suffix}.%(ext)s"));
+    path.to_string_lossy().to_string()
+}
+
+fn configured_output_template(state: &AppState) -> Result<String, String> {
+    let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
+    Ok(cfg
+        .output_template
+        .clone()
+        .unwrap_or_else(default_output_template))
+}
+
+// Accepts `%(field)s` and `%(field)0Nd` for allowlisted fields, literal text
+// and `/` between folders. Returns `None` for an empty template.
+fn normalize_output_template(raw: Option<&str>) -> Result<Option<String>, String> {
+    let Some(template) = raw.map(str::trim).filter(|template| !template.is_empty()) else {
+        return Ok(None);
+    };
+    if template.chars().count() > OUTPUT_TEMPLATE_MAX_LENGTH {
+        return Err(format!(
+            "Output template must be at most {OUTPUT_TEMPLATE_MAX_LENGTH} characters"
+        ));
+    }
+
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut segment_has_field = false;
+    let mut chars = template.chars().peekable();
+    while let Some(character) = chars.next() {
+        match character {
+            '%' => match chars.next() {
+                Some('%') => segment.push_str("%%"),
+                Some('(') => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(')') => break,
+                            Some(character) => field.push(character),
+                            None => return Err("Output template has an unclosed %(".to_string()),
+                        }
+                    }
+                    validate_output_template_field(&field)?;
+                    let mut width = String::new();
+                    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                        width.push(digit);
+                    }
+                    let conversion = match chars.next() {
+                        Some(conversion @ 's') if width.is_empty() => conversion,
+                        Some(conversion @ 'd') => conversion,
+                        _ => {
+                            return Err(format!(
+                                "Template field %({field}) must end with s or a number format such as 03d"
+                            ));
+                        }
+                    };
+                    segment.push_str(&format!("%({field}){width}{conversion}"));
+                    segment_has_field = true;
+                }
+                _ => return Err("Write a literal % in the output template as %%".to_string()),
+            },
+            '/' => {
+                segments.push(finish_output_template_segment(&segment)?);
+                segment.clear();
+                segment_has_field = false;
+            }
+            '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => {
+                return Err(format!(
+                    "Output template may not contain {character} outside of a field"
+                ));
+            }
+            character if character.is_control() => {
+                return Err("Output template may not contain control characters".to_string());
+            }
+            character => segment.push(character),
+        }
+    }
+    if !segment_has_field {
+        return Err(
+            "The file name part of the output template needs at least one field".to_string(),
+        );
+    }
+    segments.push(finish_output_template_segment(&segment)?);
+    Ok(Some(segments.join("/")))
+}
+
+fn finish_output_template_segment(segment: &str) -> Result<String, String> {
+    let trimmed = segment.trim();
+    if matches!(trimmed, "" | "." | "..") {
+        return Err("Output template folders must not be empty, '.' or '..'".to_string());
+    }
+    Ok(trimmed.to_string())
+}
+
+// `field`, `field>DATE_FORMAT` or either of those followed by `|default`.
+fn validate_output_template_field(field: &str) -> Result<(), String> {
+    let (field, default) = match field.split_once('|') {
+        Some((field, default)) => (field, Some(default)),
+        None => (field, None),
+    };
+    let (name, date_format) = match field.split_once('>') {
+        Some((name, date_format)) => (name, Some(date_format)),
+        None => (field, None),
+    };
+    if name == "ext" {
+        return Err(
+            "Leave %(ext)s out of the output template; it is added automatically".to_string(),
+        );
+    }
+    if !OUTPUT_TEMPLATE_FIELDS.contains(&name) {
+        return Err(format!("Unsupported output template field: {name}"));
+    }
+    if let Some(date_format) = date_format {
+        if !OUTPUT_TEMPLATE_DATE_FIELDS.contains(&name) {
+            return Err(format!(
+                "Only {} accept a date format",
+                OUTPUT_TEMPLATE_DATE_FIELDS.join(" and ")
+            ));
+        }
+        if date_format.is_empty()
+            || !date_format.chars().all(|character| {
+                character.is_ascii_alphanumeric()
+                    || matches!(character, '%' | '-' | '_' | '.' | ' ')
+            })
+        {
+            return Err(format!(
+                "Unsupported date format in %({name}>{date_format})"
+            ));
+        }
+    }
+    if let Some(default) = default {
+        if default.chars().any(|character| {
+            character.is_control()
+                || matches!(
+                    character,
+                    '/' | '\\' | '%' | ':' | '*' | '?' | '"' | '<' | '>' | '|'
+                )
+        }) {
+            return Err(format!("Unsupported default value in %({field}|{default})"));
+        }
+    }
+    Ok(())
+}
+
+// Clip jobs carry their range in the name from the start, so clips of the
+// same video never share a file while downloading. Local cuts also mark the
+// full download, which keeps it apart from a failed range attempt and from
+// the finished clip.
+fn build_job_output_template(job: &DownloadJob) -> String {
+    let mut suffix = job.filename_suffix.clone().unwrap_or_default();
+    if let Some(cut_start_time) = job.cut_start_time {
+        suffix.push_str(&format_cut_range_filename_suffix(
+            cut_start_time,
+            job.cut_end_time,
+        ));
+        if job.cut_mode == CutMode::Local {
+            suffix.push_str(LOCAL_CUT_SOURCE_MARKER);
+        }
+    }
+    let suffix = Some(suffix.as_str()).filter(|suffix| !suffix.is_empty());
+    match job.playlist_index {
+        Some(index) if job.playlist_in_output_template => build_playlist_output_template(
+            &job.output_dir,
+            job.playlist_title.as_deref(),
+            index,
+            &job.output_template,
+            suffix,
+        ),
+        _ => build_output_template(&job.output_dir, &job.output_template, suffix),
+    }
+}
+
+// Entries are downloaded one by one, so yt-dlp's own %(playlist)s fields are
+// empty here; the playlist name and index are written in as literal text.
+fn build_playlist_output_template(
+    output_dir: &str,
+    playlist_title: Option<&str>,
+    index: u32,
+    template: &str,
+    filename_suffix: Option<&str>,
+) -> String {
+    let mut path = PathBuf::from(output_dir);
+    let folder = sanitize_path_component(playlist_title.unwrap_or_default());
+    path.push(if folder.is_empty() {
+        "Playlist".to_string()
+    } else {
+        folder.replace('%', "%%")
+    });
+    // The index goes in front of the file name, below any template folders.
+    let (folders, name) = match template.rsplit_once('/') {
+        Some((folders, name)) => (Some(folders), name),
+        None => (None, template),
+    };
+    if let Some(folders) = folders {
+        path.push(folders);
+    }
+    let suffix = filename_suffix.unwrap_or("");
+    path.push(format!("{index:03} - {name}{suffix}.%(ext)s"));
+    path.to_string_lossy().to_string()
+}
+
+fn sanitize_path_component(raw: &str) -> String {
+    let sanitized = raw
+        .chars()
+        .map(|character| match character {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            character if character.is_control() => '_',
+            character => character,
+        })
+        .take(120)
+        .collect::<String>();
+    sanitized
+        .trim_matches(|c: char| c == '.' || c.is_whitespace())
+        .to_string()
+}
+
+fn normalize_filename_suffix(raw: Option<&str>) -> Option<String> {
+    let suffix = raw?.trim();
+    if suffix.is_empty()
+        || suffix.len() > 32
+        || !suffix.chars().all(|character| {
+            character.is_ascii_alphanumeric() || character == '_' || character == '-'
+        })
+    {
+        return None;
+    }
+
+    Some(suffix.to_string())
+}
+
+fn emit_queue(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+    app.emit_all("queue:update", queue.clone())
+        .map_err(|e| format!("Emit queue failed: {e}"))
+}
+
+fn emit_progress(app: &AppHandle, progress: DownloadProgress) {
+    let _ = app.emit_all("download:progress", progress);
+}
+
+fn emit_state(app: &AppHandle, state: DownloadStateEvent) {
+    let _ = app.emit_all("download:state", state);
+}
+
+fn emit_log(app: &AppHandle, log: LogEvent) {
+    let _ = app.emit_all("download:log", log);
+}
+
+fn is_valid_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+// Explicit ranges win over the single cut; ends may also come from the URL
+// (`#t=30,90`, `?start=30&end=90`). A range without start and end is no cut.
+fn resolve_clip_ranges(request: &DownloadRequest) -> Result<Vec<ClipRange>, String> {
+    if !request.cut_at_timestamp_enabled {
+        return Ok(Vec::new());
+    }
+
+    let ranges = if request.ranges.is_empty() {
+        let end = request
+            .cut_end_time
+            .and_then(normalize_positive_timestamp)
+            .or_else(|| extract_url_end_timestamp(&request.url));
+        let start = resolve_cut_start_time(true, request.cut_start_time, &request.url);
+        match (start, end) {
+            (None, None) => Vec::new(),
+            (start, end) => vec![ClipRange {
+                start: start.unwrap_or(0.0),
+                end,
+            }],
+        }
+    } else {
+        request.ranges.clone()
+    };
+
+    if ranges.len() > CLIP_RANGES_LIMIT {
+        return Err(format!(
+            "At most {CLIP_RANGES_LIMIT} clip ranges are allowed"
+        ));
+    }
+    let mut normalized: Vec<ClipRange> = Vec::new();
+    for range in ranges {
+        if !range.start.is_finite() || range.start < 0.0 {
+            return Err("Clip start time must be zero or positive".to_string());
+        }
+        if let Some(end) = range.end {
+            if !end.is_finite() || end <= range.start {
+                return Err("Cut end time must be after the start time".to_string());
+            }
+        }
+        if (range.start > 0.0 || range.end.is_some()) && !normalized.contains(&range) {
+            normalized.push(range);
+        }
+    }
+    Ok(normalized)
+}
+
+fn resolve_cut_start_time(
+    cut_at_timestamp_enabled: bool,
+    requested_cut_start_time: Option<f64>,
+    url: &str,
+) -> Option<f64> {
+    if !cut_at_timestamp_enabled {
+        return None;
+    }
+
+    requested_cut_start_time
+        .and_then(normalize_positive_timestamp)
+        .or_else(|| extract_url_start_timestamp(url))
+}
+
+fn extract_url_start_timestamp(raw_url: &str) -> Option<f64> {
+    let parsed = url::Url::parse(raw_url).ok()?;
+
+    for (name, value) in parsed.query_pairs() {
+        if is_start_timestamp_param(name.as_ref()) {
+            if let Some(seconds) = parse_timestamp_value(split_time_range(value.as_ref()).0) {
+                return Some(seconds);
+            }
+        }
+    }
+
+    if let Some(fragment) = parsed.fragment() {
+        for (name, value) in url::form_urlencoded::parse(fragment.as_bytes()) {
+            if is_start_timestamp_param(name.as_ref()) {
+                if let Some(seconds) = parse_timestamp_value(split_time_range(value.as_ref()).0) {
+                    return Some(seconds);
+                }
+            }
+        }
+
+        parse_timestamp_value(fragment)
+    } else {
+        None
+    }
+}
+
+// YouTube embeds and clips use `end`; media fragments use `t=start,end`.
+fn extract_url_end_timestamp(raw_url: &str) -> Option<f64> {
+    let parsed = url::Url::parse(raw_url).ok()?;
+    let fragment_pairs = parsed
+        .fragment()
+        .map(|fragment| url::form_urlencoded::parse(fragment.as_bytes()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    parsed
+        .query_pairs()
+        .chain(fragment_pairs)
+        .find_map(|(name, value)| {
+            if matches!(name.as_ref(), "end" | "end_time") {
+                parse_timestamp_value(value.as_ref())
+            } else if is_start_timestamp_param(name.as_ref()) {
+                parse_timestamp_value(split_time_range(value.as_ref()).1?)
+            } else {
+                None
+            }
+        })
+}
+
+// Media fragment times look like `30,90`, `,90` or `npt:30,90`.
+fn split_time_range(value: &str) -> (&str, Option<&str>) {
+    let value = value.strip_prefix("npt:").unwrap_or(value);
+    match value.split_once(',') {
+        Some((start, end)) => (start, Some(end)),
+        None => (value, None),
+    }
+}
+
+fn is_start_timestamp_param(name: &str) -> bool {
+    matches!(name, "t" | "start" | "start_time" | "time_continue")
+}
+
+fn parse_timestamp_value(raw: &str) -> Option<f64> {
+    let value = raw.trim().to_ascii_lowercase();
+    if value.is_empty() {
+        return None;
+    }
+
+    if let Ok(seconds) = value.parse::<f64>() {
+        return normalize_positive_timestamp(seconds);
+    }
+
+    if value.contains(':') {
+        return parse_colon_timestamp(&value);
+    }
+
+    parse_unit_timestamp(&value)
+}
+
+fn parse_colon_timestamp(value: &str) -> Option<f64> {
+    let parts = value.split(':').collect::<Vec<_>>();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+
+    let mut total = 0.0;
+    for part in parts {
+        if part.is_empty() {
+            return None;
+        }
+        let value = part.parse::<f64>().ok()?;
+        if !value.is_finite() || value < 0.0 {
+            return None;
+        }
+        total = total * 60.0 + value;
+    }
+
+    normalize_positive_timestamp(total)
+}
+
+fn parse_unit_timestamp(value: &str) -> Option<f64> {
+    let mut number = String::new();
+    let mut total = 0.0;
+    let mut saw_unit = false;
+
+    for character in value.chars() {
+        if character.is_ascii_digit() || character == '.' {
+            number.push(character);
+            continue;
+        }
+
+        let multiplier = match character {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => return None,
+        };
+        if number.is_empty() {
+            return None;
+        }
+        let amount = number.parse::<f64>().ok()?;
+        if !amount.is_finite() || amount < 0.0 {
+            return None;
+        }
+        total += amount * multiplier;
+        number.clear();
+        saw_unit = true;
+    }
+
+    if !saw_unit || !number.is_empty() {
+        return None;
+    }
+
+    normalize_positive_timestamp(total)
+}
+
+fn normalize_positive_timestamp(seconds: f64) -> Option<f64> {
+    if seconds.is_finite() && seconds > 0.0 {
+        Some(seconds)
+    } else {
+        None
+    }
+}
+
+fn format_yt_dlp_timestamp(seconds: f64) -> String {
+    let mut formatted = format!("{seconds:.3}");
+    while formatted.contains('.') && formatted.ends_with('0') {
+        formatted.pop();
+    }
+    if formatted.ends_with('.') {
+        formatted.pop();
+    }
+    formatted
+}
+
+fn canonical_existing_local_path(raw: &str) -> Result<Option<String>, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    if trimmed.contains('\0')
+        || trimmed.contains("://")
+        || trimmed.starts_with("mailto:")
+        || trimmed.starts_with("tel:")
+    {
+        return Err("Only local filesystem paths can be opened".to_string());
+    }
+
+    match fs::canonicalize(trimmed) {
+        Ok(path) => Ok(Some(path.to_string_lossy().to_string())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(format!("Path could not be opened: {err}")),
+    }
+}
+
+fn resolve_output_dir(state: &AppState, requested: Option<String>) -> Result<String, String> {
+    if let Some(dir) = requested {
+        if dir.trim().is_empty() {
+            return Err("Output directory is empty".to_string());
+        }
+        return Ok(dir);
+    }
+    let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
+    cfg.default_output_dir
+        .clone()
+        .ok_or_else(|| "Default output directory not set".to_string())
+}
+
+fn resolve_yt_dlp(_app: &AppHandle, state: &AppState) -> Result<String, String> {
+    let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
+    if let Some(path) = cfg.yt_dlp_path.as_ref() {
+        if Path::new(path).exists() {
+            return Ok(path.clone());
+        }
+    }
+
+    if let Some(path) = find_in_path("yt-dlp") {
+        return Ok(path);
+    }
+
+    Err("yt-dlp not found. Set its path in Settings.".to_string())
+}
+
+fn resolve_yt_dlp_for_version(
+    app: &AppHandle,
+    state: &AppState,
+    path: Option<String>,
+) -> Result<String, String> {
+    if let Some(raw) = path {
+        let trimmed = raw.trim();
+        if !trimmed.is_empty() {
+            if Path::new(trimmed).exists() {
+                return Ok(trimmed.to_string());
+            }
+            return Err(format!("yt-dlp path not found: {trimmed}"));
+        }
+    }
+    resolve_yt_dlp(app, state)
+}
+
+fn find_in_path(binary: &str) -> Option<String> {
+    let paths = std::env::var_os("PATH")?;
+    let splitter = if cfg!(windows) { ';' } else { ':' };
+    for path in paths.to_string_lossy().split(splitter) {
+        let candidate = Path::new(path).join(if cfg!(windows) {
+            format!("{binary}.exe")
+        } else {
+            binary.to_string()
+        });
+        if candidate.exists() {
+            return Some(candidate.to_string_lossy().to_string());
+        }
+    }
+    None
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir =
+        tauri::api::path::app_config_dir(&app.config()).ok_or("Config directory unavailable")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Config dir create failed: {e}"))?;
+    Ok(dir.join("config.json"))
+}
+
+fn legacy_history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = tauri::api::path::app_data_dir(&app.config()).ok_or("Data directory unavailable")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Data dir create failed: {e}"))?;
+    Ok(dir.join("history.json"))
+}
+
+fn load_legacy_history_json(app: &AppHandle) -> Vec<HistoryEntry> {
+    if let Ok(path) = legacy_history_path(app) {
+        if let Ok(raw) = fs::read_to_string(path) {
+            if let Ok(history) = serde_json::from_str::<Vec<HistoryEntry>>(&raw) {
+                return history.into_iter().map(normalize_history_entry).collect();
+            }
+        }
+    }
+    Vec::new()
+}
+
+fn normalize_history_entry(mut entry: HistoryEntry) -> HistoryEntry {
+    entry.title = trim_optional_string(entry.title);
+    entry.uploader = trim_optional_string(entry.uploader);
+    entry.filename = trim_optional_string(entry.filename)
+        .or_else(|| filename_from_path(entry.output_path.as_deref()));
+    entry.thumbnail = trim_optional_string(entry.thumbnail);
+    entry.upload_date = trim_optional_string(entry.upload_date);
+    entry.timestamp = entry.timestamp.filter(|timestamp| *timestamp >= 0);
+    entry.duration_seconds = entry.duration_seconds.filter(|duration| *duration >= 0);
+    entry.file_size_bytes = entry.file_size_bytes.filter(|size| *size >= 0);
+    entry.medium = trim_optional_string(entry.medium)
+        .map(|medium| medium.to_ascii_lowercase())
+        .filter(|medium| matches!(medium.as_str(), "video" | "audio" | "transcript"));
+    entry.source = trim_optional_string(entry.source)
+        .map(|source| source.to_ascii_lowercase())
+        .or_else(|| source_from_url(&entry.url));
+    entry.platform = trim_optional_string(entry.platform).or_else(|| detect_platform(&entry.url));
+    entry.output_path = trim_optional_string(entry.output_path);
+    if entry.title.is_none() {
+        entry.title = title_from_filename(entry.filename.as_deref());
+    }
+    entry
+}
+
+fn millis_to_i64(value: u64) -> i64 {
+    value.min(i64::MAX as u64) as i64
+}
+
+fn i64_to_millis(value: i64) -> u64 {
+    if value < 0 {
+        0
+    } else {
+        value as u64
+    }
+}
+
+fn optional_i64_to_millis(value: Option<i64>) -> Option<u64> {
+    value.map(i64_to_millis)
+}
+
+fn count_history_entries_in_db(state: &AppState) -> Result<u64, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM history_entries", [], |row| row.get(0))
+        .map_err(|e| format!("History read failed: {e}"))?;
+    Ok(count.max(0) as u64)
+}
+
+fn list_history_page_from_db(
+    state: &AppState,
     limit: u32,
     offset: u32,
 ) -> Result<HistoryPage, String> {
     let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    let total: i64 = conn
-        .query_row("SELECT COUNT(*) FROM history_entries", [], |row| row.get(0))
-        .map_err(|e| format!("History read failed: {e}"))?;
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM history_entries", [], |row| row.get(0))
+        .map_err(|e| format!("History read failed: {e}"))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, url, title, uploader, filename, thumbnail, upload_date, timestamp, duration_seconds, file_size_bytes, medium, source, platform, output_path, created_at, completed_at, subtitle_paths_json, removed_segments_json, parent_id, download_archive
+             FROM history_entries
+             ORDER BY COALESCE(completed_at, created_at) DESC, created_at DESC, id DESC
+             LIMIT ?1 OFFSET ?2",
+        )
+        .map_err(|e| format!("History read failed: {e}"))?;
+
+    let rows = stmt
+        .query_map(params![i64::from(limit), i64::from(offset)], |row| {
+            let created_at: i64 = row.get(14)?;
+            let completed_at: Option<i64> = row.get(15)?;
+            let subtitle_paths_json: Option<String> = row.get(16)?;
+            let removed_segments_json: Option<String> = row.get(17)?;
+            Ok(HistoryEntry {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                title: row.get(2)?,
+                uploader: row.get(3)?,
+                filename: row.get(4)?,
+                thumbnail: row.get(5)?,
+                upload_date: row.get(6)?,
+                timestamp: row.get(7)?,
+                duration_seconds: row.get(8)?,
+                file_size_bytes: row.get(9)?,
+                medium: row.get(10)?,
+                source: row.get(11)?,
+                platform: row.get(12)?,
+                output_path: row.get(13)?,
+                subtitle_paths: subtitle_paths_json
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default(),
+                removed_segments: removed_segments_json
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default(),
+                parent_id: row.get(18)?,
+                download_archive: row.get(19)?,
+                created_at: i64_to_millis(created_at),
+                completed_at: optional_i64_to_millis(completed_at),
+            })
+        })
+        .map_err(|e| format!("History read failed: {e}"))?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(normalize_history_entry(
+            row.map_err(|e| format!("History read failed: {e}"))?,
+        ));
+    }
+
+    let loaded_count = u64::from(offset).saturating_add(entries.len() as u64);
+    Ok(HistoryPage {
+        entries,
+        has_more: loaded_count < total.max(0) as u64,
+    })
+}
+
+fn list_history_entries_from_db(state: &AppState) -> Result<Vec<HistoryEntry>, String> {
+    Ok(list_history_page_from_db(state, u32::MAX, 0)?.entries)
+}
+
+fn get_history_stats_from_db(state: &AppState) -> Result<HistoryStats, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    // Chapter files are counted in storage only; their runtime is already part
+    // of the parent download.
+    conn.query_row(
+        "SELECT
+            COUNT(*) FILTER (WHERE parent_id IS NULL),
+            COALESCE(SUM(MAX(duration_seconds - COALESCE(removed_duration_seconds, 0), 0))
+                FILTER (WHERE parent_id IS NULL), 0),
+            COALESCE(SUM(file_size_bytes), 0)
+         FROM history_entries",
+        [],
+        |row| {
+            let video_count: i64 = row.get(0)?;
+            let total_duration_seconds: i64 = row.get(1)?;
+            let total_file_size_bytes: i64 = row.get(2)?;
+            Ok(HistoryStats {
+                video_count: video_count.max(0) as u64,
+                total_duration_seconds: total_duration_seconds.max(0) as u64,
+                total_file_size_bytes: total_file_size_bytes.max(0) as u64,
+            })
+        },
+    )
+    .map_err(|e| format!("History stats read failed: {e}"))
+}
+
+fn insert_history_entry_in_db(state: &AppState, entry: &HistoryEntry) -> Result<(), String> {
+    let entry = normalize_history_entry(entry.clone());
+    let subtitle_paths_json = serde_json::to_string(&entry.subtitle_paths)
+        .map_err(|e| format!("History insert failed: {e}"))?;
+    let removed_segments_json = serde_json::to_string(&entry.removed_segments)
+        .map_err(|e| format!("History insert failed: {e}"))?;
+    let removed_duration_seconds = removed_segments_duration_seconds(&entry.removed_segments);
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "INSERT OR REPLACE INTO history_entries (
+            id,
+            url,
+            title,
+            uploader,
+            filename,
+            thumbnail,
+            upload_date,
+            timestamp,
+            duration_seconds,
+            file_size_bytes,
+            medium,
+            source,
+            platform,
+            output_path,
+            created_at,
+            completed_at,
+            subtitle_paths_json,
+            removed_segments_json,
+            removed_duration_seconds,
+            parent_id,
+            download_archive
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+        params![
+            entry.id,
+            entry.url,
+            entry.title,
+            entry.uploader,
+            entry.filename,
+            entry.thumbnail,
+            entry.upload_date,
+            entry.timestamp,
+            entry.duration_seconds,
+            entry.file_size_bytes,
+            entry.medium,
+            entry.source,
+            entry.platform,
+            entry.output_path,
+            millis_to_i64(entry.created_at),
+            entry.completed_at.map(millis_to_i64),
+            subtitle_paths_json,
+            removed_segments_json,
+            removed_duration_seconds,
+            entry.parent_id,
+            entry.download_archive,
+        ],
+    )
+    .map_err(|e| format!("History insert failed: {e}"))?;
+    Ok(())
+}
+
+fn delete_history_entry_from_db(state: &AppState, id: &str) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "DELETE FROM history_entries WHERE id = ?1 OR parent_id = ?1",
+        params![id],
+    )
+    .map_err(|e| format!("History delete failed: {e}"))?;
+    Ok(())
+}
+
+fn clear_history_entries_in_db(state: &AppState) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute("DELETE FROM history_entries", [])
+        .map_err(|e| format!("History clear failed: {e}"))?;
+    Ok(())
+}
+
+fn insert_queue_jobs_in_db(state: &AppState, jobs: &[DownloadJob]) -> Result<(), String> {
+    let mut conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Queue write failed: {e}"))?;
+    let next_position: i64 = tx
+        .query_row(
+            "SELECT COALESCE(MAX(position), -1) + 1 FROM queue_jobs",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Queue write failed: {e}"))?;
+    let now = millis_to_i64(current_timestamp_millis());
+
+    for (offset, job) in jobs.iter().enumerate() {
+        let job_json =
+            serde_json::to_string(job).map_err(|e| format!("Queue job encode failed: {e}"))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO queue_jobs (id, url, state, position, job_json, created_at, updated_at)
+             VALUES (?1, ?2, 'queued', ?3, ?4, ?5, ?5)",
+            params![
+                job.id,
+                job.url,
+                next_position + offset as i64,
+                job_json,
+                now
+            ],
+        )
+        .map_err(|e| format!("Queue write failed: {e}"))?;
+    }
+
+    tx.commit().map_err(|e| format!("Queue write failed: {e}"))
+}
+
+fn update_queue_job_state_in_db(state: &AppState, id: &str, job_state: &str) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "UPDATE queue_jobs SET state = ?1, updated_at = ?2 WHERE id = ?3",
+        params![job_state, millis_to_i64(current_timestamp_millis()), id],
+    )
+    .map_err(|e| format!("Queue update failed: {e}"))?;
+    Ok(())
+}
+
+fn update_queue_job_in_db(
+    state: &AppState,
+    job: &DownloadJob,
+    job_state: &str,
+) -> Result<(), String> {
+    let job_json =
+        serde_json::to_string(job).map_err(|e| format!("Queue job encode failed: {e}"))?;
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "UPDATE queue_jobs SET state = ?1, job_json = ?2, updated_at = ?3 WHERE id = ?4",
+        params![
+            job_state,
+            job_json,
+            millis_to_i64(current_timestamp_millis()),
+            job.id
+        ],
+    )
+    .map_err(|e| format!("Queue update failed: {e}"))?;
+    Ok(())
+}
+
+fn sync_queue_positions_in_db(state: &AppState) -> Result<(), String> {
+    let ids = {
+        let queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+        queue.iter().map(|job| job.id.clone()).collect::<Vec<_>>()
+    };
+
+    let mut conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Queue reorder failed: {e}"))?;
+    for (position, id) in ids.iter().enumerate() {
+        tx.execute(
+            "UPDATE queue_jobs SET position = ?1 WHERE id = ?2",
+            params![position as i64, id],
+        )
+        .map_err(|e| format!("Queue reorder failed: {e}"))?;
+    }
+    tx.commit()
+        .map_err(|e| format!("Queue reorder failed: {e}"))
+}
+
+fn delete_queue_job_from_db(state: &AppState, id: &str) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute("DELETE FROM queue_jobs WHERE id = ?1", params![id])
+        .map_err(|e| format!("Queue delete failed: {e}"))?;
+    Ok(())
+}
+
+fn list_queue_jobs_from_conn(conn: &Connection) -> rusqlite::Result<Vec<DownloadJob>> {
+    // Jobs that were running when the app quit go first so they resume before newer work.
+    let mut stmt = conn.prepare(
+        "SELECT job_json
+         FROM queue_jobs
+         WHERE state IN ('queued', 'running', 'paused')
+         ORDER BY CASE state WHEN 'running' THEN 0 ELSE 1 END, position ASC",
+    )?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+    let mut jobs = Vec::new();
+    for row in rows {
+        match serde_json::from_str::<DownloadJob>(&row?) {
+            Ok(job) => jobs.push(job),
+            Err(err) => eprintln!("Skipping unreadable queue job: {err}"),
+        }
+    }
+    Ok(jobs)
+}
+
+fn restore_queue_jobs_from_db(state: &AppState) -> Result<Vec<DownloadJob>, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let jobs = list_queue_jobs_from_conn(&conn).map_err(|e| format!("Queue read failed: {e}"))?;
+    conn.execute(
+        "UPDATE queue_jobs SET state = 'queued', updated_at = ?1 WHERE state = 'running'",
+        params![millis_to_i64(current_timestamp_millis())],
+    )
+    .map_err(|e| format!("Queue restore failed: {e}"))?;
+    Ok(jobs)
+}
+
+fn record_failed_job_in_db(
+    state: &AppState,
+    job: &DownloadJob,
+    outcome: &JobState,
+) -> Result<(), String> {
+    let (job_state, failed_at, error) = match outcome {
+        JobState::Failed { failed_at, error } => ("failed", *failed_at, Some(error)),
+        JobState::Cancelled { cancelled_at } => ("cancelled", *cancelled_at, None),
+        _ => return Err("Only failed or cancelled jobs can be recorded".to_string()),
+    };
+    let job_json =
+        serde_json::to_string(job).map_err(|e| format!("Failed job encode failed: {e}"))?;
+    let stderr_json = serde_json::to_string(
+        &error
+            .map(|error| error.stderr_lines.clone())
+            .unwrap_or_default(),
+    )
+    .map_err(|e| format!("Failed job encode failed: {e}"))?;
+    let error_kind = error
+        .and_then(|error| serde_json::to_value(error.kind).ok())
+        .and_then(|value| value.as_str().map(str::to_string));
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "INSERT OR REPLACE INTO failed_jobs (id, url, state, exit_code, error, error_kind, stderr_json, job_json, failed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            job.id,
+            job.url,
+            job_state,
+            error.and_then(|error| error.exit_code),
+            error.map(|error| error.message.as_str()),
+            error_kind,
+            stderr_json,
+            job_json,
+            millis_to_i64(failed_at)
+        ],
+    )
+    .map_err(|e| format!("Failed job insert failed: {e}"))?;
+    Ok(())
+}
+
+fn list_failed_jobs_from_conn(
+    conn: &Connection,
+    id: Option<&str>,
+) -> rusqlite::Result<Vec<FailedJob>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, url, state, exit_code, error, error_kind, stderr_json, job_json, failed_at
+         FROM failed_jobs
+         WHERE ?1 IS NULL OR id = ?1
+         ORDER BY failed_at DESC",
+    )?;
+    let rows = stmt.query_map(params![id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<i32>>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, String>(6)?,
+            row.get::<_, String>(7)?,
+            row.get::<_, i64>(8)?,
+        ))
+    })?;
+
+    let mut failed = Vec::new();
+    for row in rows {
+        let (id, url, job_state, exit_code, error, error_kind, stderr_json, job_json, failed_at) =
+            row?;
+        let job = match serde_json::from_str::<DownloadJob>(&job_json) {
+            Ok(job) => job,
+            Err(err) => {
+                eprintln!("Skipping unreadable failed job: {err}");
+                continue;
+            }
+        };
+        let failed_at = failed_at.max(0) as u64;
+        let state = if job_state == "cancelled" {
+            JobState::Cancelled {
+                cancelled_at: failed_at,
+            }
+        } else {
+            let kind = error_kind
+                .and_then(|kind| serde_json::from_value(serde_json::Value::String(kind)).ok())
+                .unwrap_or(JobErrorKind::Internal);
+            JobState::Failed {
+                failed_at,
+                error: JobError {
+                    kind,
+                    message: error.unwrap_or_default(),
+                    exit_code,
+                    stderr_lines: serde_json::from_str(&stderr_json).unwrap_or_default(),
+                },
+            }
+        };
+        failed.push(FailedJob {
+            id,
+            url,
+            state,
+            job,
+        });
+    }
+    Ok(failed)
+}
+
+fn list_failed_jobs_from_db(state: &AppState, id: Option<&str>) -> Result<Vec<FailedJob>, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    list_failed_jobs_from_conn(&conn, id).map_err(|e| format!("Failed jobs read failed: {e}"))
+}
+
+fn delete_failed_jobs_from_db(state: &AppState, ids: &[String]) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    for id in ids {
+        conn.execute("DELETE FROM failed_jobs WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed job delete failed: {e}"))?;
+    }
+    Ok(())
+}
+
+fn list_subscriptions_from_conn(
+    conn: &Connection,
+    id: Option<&str>,
+) -> rusqlite::Result<Vec<Subscription>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, url, title, preset_key, output_dir, filters_json, paused, last_checked_at, last_error, created_at
+         FROM subscriptions
+         WHERE ?1 IS NULL OR id = ?1
+         ORDER BY created_at ASC",
+    )?;
+    let rows = stmt.query_map(params![id], |row| {
+        let filters_json: String = row.get(5)?;
+        Ok(Subscription {
+            id: row.get(0)?,
+            url: row.get(1)?,
+            title: row.get(2)?,
+            preset_key: row.get(3)?,
+            output_dir: row.get(4)?,
+            filters: serde_json::from_str(&filters_json).unwrap_or_default(),
+            paused: row.get::<_, i64>(6)? != 0,
+            last_checked_at: optional_i64_to_millis(row.get(7)?),
+            last_error: row.get(8)?,
+            created_at: i64_to_millis(row.get(9)?),
+        })
+    })?;
+
+    rows.collect()
+}
+
+fn list_presets_from_conn(conn: &Connection, key: Option<&str>) -> rusqlite::Result<Vec<Preset>> {
+    let mut stmt = conn.prepare(
+        "SELECT key, label, format, extract_audio, audio_format, transcribe_text, filename_suffix,
+                subtitles_json, embed_json, sponsorblock_json, builtin, output_template
+         FROM presets
+         WHERE ?1 IS NULL OR key = ?1
+         ORDER BY builtin DESC, position ASC, created_at ASC, key ASC",
+    )?;
+    let rows = stmt.query_map(params![key], |row| {
+        let subtitles_json: String = row.get(7)?;
+        let embed_json: String = row.get(8)?;
+        let sponsorblock_json: String = row.get(9)?;
+        Ok(Preset {
+            key: row.get(0)?,
+            label: row.get(1)?,
+            format: row.get(2)?,
+            extract_audio: row.get::<_, i64>(3)? != 0,
+            audio_format: row.get(4)?,
+            transcribe_text: row.get::<_, i64>(5)? != 0,
+            filename_suffix: row.get(6)?,
+            subtitles: serde_json::from_str(&subtitles_json).unwrap_or_default(),
+            embed: serde_json::from_str(&embed_json).unwrap_or_default(),
+            sponsorblock: serde_json::from_str(&sponsorblock_json).unwrap_or_default(),
+            output_template: row.get(11)?,
+            builtin: row.get::<_, i64>(10)? != 0,
+        })
+    })?;
+
+    rows.collect()
+}
+
+fn get_preset_from_conn(conn: &Connection, key: &str) -> rusqlite::Result<Option<Preset>> {
+    Ok(list_presets_from_conn(conn, Some(key))?.into_iter().next())
+}
+
+fn upsert_preset_in_conn(
+    conn: &Connection,
+    preset: &Preset,
+    position: i64,
+) -> rusqlite::Result<()> {
+    let encode = |value: serde_json::Result<String>| {
+        value.map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    };
+    let now = millis_to_i64(current_timestamp_millis());
+    conn.execute(
+        "INSERT INTO presets (
+            key, label, format, extract_audio, audio_format, transcribe_text, filename_suffix,
+            subtitles_json, embed_json, sponsorblock_json, builtin, position, created_at, updated_at,
+            output_template
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?13, ?14)
+        ON CONFLICT(key) DO UPDATE SET
+            label = excluded.label,
+            format = excluded.format,
+            extract_audio = excluded.extract_audio,
+            audio_format = excluded.audio_format,
+            transcribe_text = excluded.transcribe_text,
+            filename_suffix = excluded.filename_suffix,
+            subtitles_json = excluded.subtitles_json,
+            embed_json = excluded.embed_json,
+            sponsorblock_json = excluded.sponsorblock_json,
+            output_template = excluded.output_template,
+            builtin = excluded.builtin,
+            position = excluded.position,
+            updated_at = excluded.updated_at",
+        params![
+            preset.key,
+            preset.label,
+            preset.format,
+            i64::from(preset.extract_audio),
+            preset.audio_format,
+            i64::from(preset.transcribe_text),
+            preset.filename_suffix,
+            encode(serde_json::to_string(&preset.subtitles))?,
+            encode(serde_json::to_string(&preset.embed))?,
+            encode(serde_json::to_string(&preset.sponsorblock))?,
+            i64::from(preset.builtin),
+            position,
+            now,
+            preset.output_template,
+        ],
+    )?;
+    Ok(())
+}
+
+fn list_presets_from_db(state: &AppState) -> Result<Vec<Preset>, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    list_presets_from_conn(&conn, None).map_err(|e| format!("Presets read failed: {e}"))
+}
+
+// Built-in keys are reserved, so user presets can never shadow them.
+fn save_user_preset_in_db(
+    state: &AppState,
+    preset: Preset,
+    replace_existing: bool,
+) -> Result<Preset, String> {
+    let preset = normalize_preset(preset)?;
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let existing =
+        get_preset_from_conn(&conn, &preset.key).map_err(|e| format!("Preset read failed: {e}"))?;
+    match existing {
+        Some(existing) if existing.builtin => {
+            return Err(format!("Built-in preset {} is read-only", existing.key));
+        }
+        Some(existing) if !replace_existing => {
+            return Err(format!("Preset {} already exists", existing.key));
+        }
+        None if replace_existing => return Err(format!("Unknown preset: {}", preset.key)),
+        _ => {}
+    }
+    upsert_preset_in_conn(&conn, &preset, 0).map_err(|e| format!("Preset write failed: {e}"))?;
+    Ok(preset)
+}
+
+fn delete_user_preset_in_db(state: &AppState, key: &str) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let preset = get_preset_from_conn(&conn, key)
+        .map_err(|e| format!("Preset read failed: {e}"))?
+        .ok_or_else(|| format!("Unknown preset: {key}"))?;
+    if preset.builtin {
+        return Err(format!("Built-in preset {key} is read-only"));
+    }
+    let subscriptions: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM subscriptions WHERE preset_key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Preset delete failed: {e}"))?;
+    if subscriptions > 0 {
+        return Err(format!(
+            "Preset {key} is used by {subscriptions} subscription{}",
+            if subscriptions == 1 { "" } else { "s" }
+        ));
+    }
+    conn.execute("DELETE FROM presets WHERE key = ?1", params![key])
+        .map_err(|e| format!("Preset delete failed: {e}"))?;
+    Ok(())
+}
+
+fn list_subscriptions_from_db(state: &AppState) -> Result<Vec<Subscription>, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    list_subscriptions_from_conn(&conn, None).map_err(|e| format!("Subscriptions read failed: {e}"))
+}
+
+fn get_subscription_from_db(state: &AppState, id: &str) -> Result<Subscription, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    list_subscriptions_from_conn(&conn, Some(id))
+        .map_err(|e| format!("Subscriptions read failed: {e}"))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Subscription not found".to_string())
+}
+
+fn insert_subscription_in_db(state: &AppState, subscription: &Subscription) -> Result<(), String> {
+    let filters_json = serde_json::to_string(&subscription.filters)
+        .map_err(|e| format!("Subscription encode failed: {e}"))?;
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "INSERT INTO subscriptions (id, url, title, preset_key, output_dir, filters_json, paused, last_checked_at, last_error, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            subscription.id,
+            subscription.url,
+            subscription.title,
+            subscription.preset_key,
+            subscription.output_dir,
+            filters_json,
+            i64::from(subscription.paused),
+            subscription.last_checked_at.map(millis_to_i64),
+            subscription.last_error,
+            millis_to_i64(subscription.created_at)
+        ],
+    )
+    .map_err(|e| format!("Subscription insert failed: {e}"))?;
+    Ok(())
+}
+
+fn update_subscription_paused_in_db(
+    state: &AppState,
+    id: &str,
+    paused: bool,
+) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let changed = conn
+        .execute(
+            "UPDATE subscriptions SET paused = ?2 WHERE id = ?1",
+            params![id, i64::from(paused)],
+        )
+        .map_err(|e| format!("Subscription update failed: {e}"))?;
+    if changed == 0 {
+        return Err("Subscription not found".to_string());
+    }
+    Ok(())
+}
+
+// A successful check clears the previous error and fills in the title once
+// the playlist or channel name is known.
+fn record_subscription_check_in_db(
+    state: &AppState,
+    id: &str,
+    checked_at: u64,
+    title: Option<&str>,
+    error: Option<&str>,
+) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "UPDATE subscriptions
+         SET last_checked_at = ?2, last_error = ?3, title = COALESCE(title, ?4)
+         WHERE id = ?1",
+        params![id, millis_to_i64(checked_at), error, title],
+    )
+    .map_err(|e| format!("Subscription update failed: {e}"))?;
+    Ok(())
+}
+
+fn delete_subscription_from_db(state: &AppState, id: &str) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute("DELETE FROM subscriptions WHERE id = ?1", params![id])
+        .map_err(|e| format!("Subscription delete failed: {e}"))?;
+    Ok(())
+}
+
+fn list_cookie_profiles_from_conn(conn: &Connection) -> rusqlite::Result<Vec<CookieProfile>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, cookies_path, host_patterns_json, created_at
+         FROM cookie_profiles
+         ORDER BY created_at ASC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let host_patterns_json: String = row.get(3)?;
+        Ok(CookieProfile {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            cookies_path: row.get(2)?,
+            host_patterns: serde_json::from_str(&host_patterns_json).unwrap_or_default(),
+            created_at: i64_to_millis(row.get(4)?),
+        })
+    })?;
+
+    rows.collect()
+}
+
+fn list_cookie_profiles_from_db(state: &AppState) -> Result<Vec<CookieProfile>, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    list_cookie_profiles_from_conn(&conn).map_err(|e| format!("Cookie profiles read failed: {e}"))
+}
+
+fn upsert_cookie_profile_in_db(state: &AppState, profile: &CookieProfile) -> Result<(), String> {
+    let host_patterns_json = serde_json::to_string(&profile.host_patterns)
+        .map_err(|e| format!("Cookie profile encode failed: {e}"))?;
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "INSERT INTO cookie_profiles (id, name, cookies_path, host_patterns_json, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            cookies_path = excluded.cookies_path,
+            host_patterns_json = excluded.host_patterns_json",
+        params![
+            profile.id,
+            profile.name,
+            profile.cookies_path,
+            host_patterns_json,
+            millis_to_i64(profile.created_at)
+        ],
+    )
+    .map_err(|e| format!("Cookie profile save failed: {e}"))?;
+    Ok(())
+}
+
+fn delete_cookie_profile_from_db(state: &AppState, id: &str) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute("DELETE FROM cookie_profiles WHERE id = ?1", params![id])
+        .map_err(|e| format!("Cookie profile delete failed: {e}"))?;
+    Ok(())
+}
+
+// Only full downloads made with the plain video profile count: a clip or an
+// audio/subtitle preset does not mean the video itself was saved.
+fn list_archived_history_urls_from_conn(
+    conn: &Connection,
+    archive_file: &str,
+) -> rusqlite::Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT DISTINCT url FROM history_entries WHERE download_archive = ?1")?;
+    let rows = stmt.query_map(params![archive_file], |row| row.get::<_, String>(0))?;
+    rows.collect()
+}
+
+fn list_history_urls_from_conn(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT DISTINCT url FROM history_entries")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    rows.collect()
+}
+
+fn migrate_legacy_history_json(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    if count_history_entries_in_db(state)? > 0 {
+        return Ok(());
+    }
+
+    for entry in load_legacy_history_json(app) {
+        insert_history_entry_in_db(state, &entry)?;
+    }
+    Ok(())
+}
+
+fn load_legacy_config_json(app: &AppHandle) -> Option<AppConfig> {
+    if let Ok(path) = config_path(app) {
+        if let Ok(raw) = fs::read_to_string(path) {
+            if let Ok(cfg) = serde_json::from_str::<AppConfig>(&raw) {
+                return Some(normalize_app_config(cfg));
+            }
+        }
+    }
+    None
+}
+
+fn get_app_config_from_conn(conn: &Connection) -> rusqlite::Result<AppConfig> {
+    conn.query_row(
+        "SELECT yt_dlp_path, default_output_dir, selected_preset_key, magic_import_enabled, cut_at_timestamp_enabled, last_download_url, resume_queue_on_startup, max_concurrent_downloads,
+                retry_max_attempts, retry_base_delay_seconds, retry_max_delay_seconds,
+                download_window_enabled, download_window_start, download_window_end,
+                rate_limit_kib, download_window_rate_limit_kib, throttled_rate_kib, cut_precision,
+                output_template
+         FROM app_config
+         WHERE id = 1",
+        [],
+        |row| {
+            Ok(normalize_app_config(AppConfig {
+                yt_dlp_path: row.get(0)?,
+                default_output_dir: row.get(1)?,
+                selected_preset_key: row.get(2)?,
+                magic_import_enabled: row.get::<_, i64>(3)? != 0,
+                cut_at_timestamp_enabled: row.get::<_, i64>(4)? != 0,
+                last_download_url: row.get(5)?,
+                resume_queue_on_startup: row.get::<_, i64>(6)? != 0,
+                max_concurrent_downloads: row.get::<_, i64>(7)?.max(1) as usize,
+                retry_max_attempts: row.get::<_, i64>(8)?.max(0) as u32,
+                retry_base_delay_seconds: row.get::<_, i64>(9)?.max(0) as u64,
+                retry_max_delay_seconds: row.get::<_, i64>(10)?.max(0) as u64,
+                download_window_enabled: row.get::<_, i64>(11)? != 0,
+                download_window_start: row.get(12)?,
+                download_window_end: row.get(13)?,
+                rate_limit_kib: row.get::<_, Option<i64>>(14)?.map(|limit| limit.max(0) as u64),
+                download_window_rate_limit_kib: row
+                    .get::<_, Option<i64>>(15)?
+                    .map(|limit| limit.max(0) as u64),
+                throttled_rate_kib: row.get::<_, Option<i64>>(16)?.map(|rate| rate.max(0) as u64),
+                cut_precision: CutPrecision::from_db_value(&row.get::<_, String>(17)?),
+                output_template: row.get(18)?,
+            }))
+        },
+    )
+}
+
+fn load_config_from_db(conn: &Connection) -> Result<AppConfig, String> {
+    let mut config =
+        get_app_config_from_conn(conn).map_err(|e| format!("Config read failed: {e}"))?;
+    config.selected_preset_key = Some(
+        normalize_selected_preset_key_in_conn(conn, config.selected_preset_key.as_deref())
+            .map_err(|e| format!("Config read failed: {e}"))?,
+    );
+    Ok(config)
+}
+
+fn upsert_app_config_in_conn(conn: &Connection, config: &AppConfig) -> rusqlite::Result<()> {
+    let mut config = normalize_app_config(config.clone());
+    config.selected_preset_key = Some(normalize_selected_preset_key_in_conn(
+        conn,
+        config.selected_preset_key.as_deref(),
+    )?);
+    conn.execute(
+        "INSERT INTO app_config (
+            id,
+            yt_dlp_path,
+            default_output_dir,
+            selected_preset_key,
+            magic_import_enabled,
+            cut_at_timestamp_enabled,
+            last_download_url,
+            resume_queue_on_startup,
+            max_concurrent_downloads,
+            retry_max_attempts,
+            retry_base_delay_seconds,
+            retry_max_delay_seconds,
+            download_window_enabled,
+            download_window_start,
+            download_window_end,
+            rate_limit_kib,
+            download_window_rate_limit_kib,
+            throttled_rate_kib,
+            cut_precision,
+            output_template,
+            created_at,
+            updated_at
+        ) VALUES (
+            1,
+            ?1,
+            ?2,
+            ?3,
+            ?4,
+            ?5,
+            ?6,
+            ?7,
+            ?8,
+            ?9,
+            ?10,
+            ?11,
+            ?12,
+            ?13,
+            ?14,
+            ?15,
+            ?16,
+            ?17,
+            ?18,
+            ?19,
+            datetime('now'),
+            datetime('now')
+        )
+        ON CONFLICT(id) DO UPDATE SET
+            yt_dlp_path = excluded.yt_dlp_path,
+            default_output_dir = excluded.default_output_dir,
+            selected_preset_key = excluded.selected_preset_key,
+            magic_import_enabled = excluded.magic_import_enabled,
+            cut_at_timestamp_enabled = excluded.cut_at_timestamp_enabled,
+            last_download_url = excluded.last_download_url,
+            resume_queue_on_startup = excluded.resume_queue_on_startup,
+            max_concurrent_downloads = excluded.max_concurrent_downloads,
+            retry_max_attempts = excluded.retry_max_attempts,
+            retry_base_delay_seconds = excluded.retry_base_delay_seconds,
+            retry_max_delay_seconds = excluded.retry_max_delay_seconds,
+            download_window_enabled = excluded.download_window_enabled,
+            download_window_start = excluded.download_window_start,
+            download_window_end = excluded.download_window_end,
+            rate_limit_kib = excluded.rate_limit_kib,
+            download_window_rate_limit_kib = excluded.download_window_rate_limit_kib,
+            throttled_rate_kib = excluded.throttled_rate_kib,
+            cut_precision = excluded.cut_precision,
+            output_template = excluded.output_template,
+            updated_at = datetime('now')",
+        params![
+            config.yt_dlp_path,
+            config.default_output_dir,
+            config.selected_preset_key,
+            if config.magic_import_enabled { 1 } else { 0 },
+            if config.cut_at_timestamp_enabled {
+                1
+            } else {
+                0
+            },
+            config.last_download_url,
+            if config.resume_queue_on_startup { 1 } else { 0 },
+            config.max_concurrent_downloads as i64,
+            config.retry_max_attempts as i64,
+            config.retry_base_delay_seconds as i64,
+            config.retry_max_delay_seconds as i64,
+            if config.download_window_enabled { 1 } else { 0 },
+            config.download_window_start,
+            config.download_window_end,
+            config.rate_limit_kib.map(|limit| limit as i64),
+            config
+                .download_window_rate_limit_kib
+                .map(|limit| limit as i64),
+            config.throttled_rate_kib.map(|rate| rate as i64),
+            config.cut_precision.as_str(),
+            config.output_template,
+        ],
+    )?;
+    Ok(())
+}
+
+fn save_config_to_db(state: &AppState, config: &AppConfig) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    upsert_app_config_in_conn(&conn, config).map_err(|e| format!("Config write failed: {e}"))
+}
+
+fn get_app_meta_value(conn: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM app_meta WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+fn set_app_meta_value(conn: &Connection, key: &str, value: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO app_meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+fn migrate_legacy_config_json(app: &AppHandle, conn: &Connection) -> Result<(), String> {
+    let already_migrated = get_app_meta_value(conn, LEGACY_CONFIG_MIGRATION_KEY)
+        .map_err(|e| format!("Config migration check failed: {e}"))?
+        .as_deref()
+        == Some("1");
+
+    if already_migrated {
+        return Ok(());
+    }
+
+    if let Some(config) = load_legacy_config_json(app) {
+        upsert_app_config_in_conn(conn, &config)
+            .map_err(|e| format!("Config migration failed: {e}"))?;
+    }
+
+    set_app_meta_value(conn, LEGACY_CONFIG_MIGRATION_KEY, "1")
+        .map_err(|e| format!("Config migration marker failed: {e}"))?;
+    Ok(())
+}
+
+fn link_dump_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = tauri::api::path::app_data_dir(&app.config()).ok_or("Data directory unavailable")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Data dir create failed: {e}"))?;
+    Ok(dir.join("pinefetch.sqlite"))
+}
+
+fn download_archive_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    let dir = tauri::api::path::app_data_dir(&app.config()).ok_or("Data directory unavailable")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Data dir create failed: {e}"))?;
+    Ok(dir.join(file_name))
+}
+
+// The archive only records extractor and video id, so every output profile
+// gets its own file. Otherwise an audio-only or subtitle download would be
+// skipped because the same video was once saved as video. The plain video
+// profile keeps the original file name so existing archives stay valid.
+fn download_archive_file_name(job: &DownloadJob) -> String {
+    let subtitles = &job.subtitles;
+    let wants_subtitles = subtitles.write_subs || subtitles.write_auto_subs;
+    if job.format == DEFAULT_DOWNLOAD_ARCHIVE_FORMAT
+        && !job.extract_audio
+        && !job.transcribe_text
+        && !wants_subtitles
+    {
+        return DEFAULT_DOWNLOAD_ARCHIVE_FILE.to_string();
+    }
+
+    let profile = json!({
+        "format": job.format,
+        "extract_audio": job.extract_audio,
+        "audio_format": job.audio_format,
+        "transcribe_text": job.transcribe_text,
+        "subtitles": wants_subtitles.then(|| json!({
+            "write_subs": subtitles.write_subs,
+            "write_auto_subs": subtitles.write_auto_subs,
+            "languages": subtitles.languages,
+            "convert_format": subtitles.convert_format,
+        })),
+    });
+    let mut hasher = Sha256::new();
+    hasher.update(profile.to_string().as_bytes());
+    let digest = bytes_to_hex(&hasher.finalize());
+    format!("download-archive-{}.txt", &digest[..16])
+}
+
+// yt-dlp appends finished downloads to the archive itself. Seeding only adds
+// video history entries whose extractor and id can be derived from the URL,
+// which today means YouTube. History does not record the format, so seeded
+// entries only go to the plain video archive.
+fn seed_download_archive(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let path = download_archive_path(app, DEFAULT_DOWNLOAD_ARCHIVE_FILE)?;
+    let existing = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(format!("Download archive read failed: {err}")),
+    };
+    let urls = {
+        let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+        list_archived_history_urls_from_conn(&conn, DEFAULT_DOWNLOAD_ARCHIVE_FILE)
+            .map_err(|e| format!("History read failed: {e}"))?
+    };
+    let missing = missing_download_archive_lines(&existing, &urls);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Download archive open failed: {e}"))?;
+    let separator = if existing.is_empty() || existing.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    writeln!(file, "{separator}{}", missing.join("\n"))
+        .map_err(|e| format!("Download archive write failed: {e}"))
+}
+
+fn missing_download_archive_lines(existing: &str, urls: &[String]) -> Vec<String> {
+    let mut known = existing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect::<HashSet<_>>();
+    urls.iter()
+        .filter_map(|url| download_archive_line_for_url(url))
+        .filter(|line| known.insert(line.clone()))
+        .collect()
+}
+
+fn download_archive_line_for_url(url: &str) -> Option<String> {
+    let normalized = normalize_youtube_url(url)?;
+    let video_id = normalized.key.strip_prefix("youtube:")?;
+    Some(format!("youtube {video_id}"))
+}
+
+fn open_link_dump_db(app: &AppHandle) -> Result<Connection, String> {
+    let path = link_dump_db_path(app)?;
+    let conn = Connection::open(path).map_err(|e| format!("SQLite open failed: {e}"))?;
+    run_link_dump_migrations(&conn)
+        .map_err(|e| format!("Link Dump SQLite migration failed: {e}"))?;
+    println!("SQLite migration completed");
+    Ok(conn)
+}
+
+fn run_link_dump_migrations(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS link_dump_settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            server_enabled INTEGER NOT NULL DEFAULT 1,
+            host TEXT NOT NULL DEFAULT '127.0.0.1',
+            port INTEGER NOT NULL DEFAULT 2255,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        INSERT OR IGNORE INTO link_dump_settings (
+            id,
+            server_enabled,
+            host,
+            port,
+            created_at,
+            updated_at
+        ) VALUES (
+            1,
+            1,
+            '127.0.0.1',
+            2255,
+            datetime('now'),
+            datetime('now')
+        );
+
+        CREATE TABLE IF NOT EXISTS app_config (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            yt_dlp_path TEXT,
+            default_output_dir TEXT,
+            selected_preset_key TEXT,
+            magic_import_enabled INTEGER NOT NULL DEFAULT 1,
+            cut_at_timestamp_enabled INTEGER NOT NULL DEFAULT 1,
+            last_download_url TEXT,
+            resume_queue_on_startup INTEGER NOT NULL DEFAULT 1,
+            max_concurrent_downloads INTEGER NOT NULL DEFAULT 1,
+            retry_max_attempts INTEGER NOT NULL DEFAULT 3,
+            retry_base_delay_seconds INTEGER NOT NULL DEFAULT 15,
+            retry_max_delay_seconds INTEGER NOT NULL DEFAULT 600,
+            download_window_enabled INTEGER NOT NULL DEFAULT 0,
+            download_window_start TEXT NOT NULL DEFAULT '23:00',
+            download_window_end TEXT NOT NULL DEFAULT '07:00',
+            rate_limit_kib INTEGER,
+            download_window_rate_limit_kib INTEGER,
+            throttled_rate_kib INTEGER,
+            cut_precision TEXT NOT NULL DEFAULT 'fast',
+            output_template TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        INSERT OR IGNORE INTO app_config (
+            id,
+            yt_dlp_path,
+            default_output_dir,
+            selected_preset_key,
+            magic_import_enabled,
+            cut_at_timestamp_enabled,
+            last_download_url,
+            created_at,
+            updated_at
+        ) VALUES (
+            1,
+            NULL,
+            NULL,
+            'best',
+            1,
+            1,
+            NULL,
+            datetime('now'),
+            datetime('now')
+        );
+
+        CREATE TABLE IF NOT EXISTS app_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS link_dump_secrets (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            secret_hash TEXT NOT NULL UNIQUE,
+            created_at TEXT NOT NULL,
+            last_used_at TEXT,
+            revoked_at TEXT,
+            deleted_at TEXT
+        );
+
+        DROP TABLE IF EXISTS link_dump_request_log;
+
+        CREATE TABLE IF NOT EXISTS history_entries (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            title TEXT,
+            uploader TEXT,
+            filename TEXT,
+            thumbnail TEXT,
+            upload_date TEXT,
+            timestamp INTEGER,
+            duration_seconds INTEGER,
+            file_size_bytes INTEGER,
+            medium TEXT,
+            source TEXT,
+            platform TEXT,
+            output_path TEXT,
+            created_at INTEGER NOT NULL,
+            completed_at INTEGER,
+            subtitle_paths_json TEXT,
+            removed_segments_json TEXT,
+            removed_duration_seconds INTEGER,
+            parent_id TEXT,
+            download_archive TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS queue_jobs (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            state TEXT NOT NULL DEFAULT 'queued',
+            position INTEGER NOT NULL,
+            job_json TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS failed_jobs (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            state TEXT NOT NULL,
+            exit_code INTEGER,
+            error TEXT,
+            error_kind TEXT,
+            stderr_json TEXT NOT NULL DEFAULT '[]',
+            job_json TEXT NOT NULL,
+            failed_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS subscriptions (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            title TEXT,
+            preset_key TEXT NOT NULL,
+            output_dir TEXT,
+            filters_json TEXT NOT NULL DEFAULT '{}',
+            paused INTEGER NOT NULL DEFAULT 0,
+            last_checked_at INTEGER,
+            last_error TEXT,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS presets (
+            key TEXT PRIMARY KEY,
+            label TEXT NOT NULL,
+            format TEXT NOT NULL,
+            extract_audio INTEGER NOT NULL DEFAULT 0,
+            audio_format TEXT,
+            transcribe_text INTEGER NOT NULL DEFAULT 0,
+            filename_suffix TEXT,
+            subtitles_json TEXT NOT NULL DEFAULT '{}',
+            embed_json TEXT NOT NULL DEFAULT '{}',
+            sponsorblock_json TEXT NOT NULL DEFAULT '{}',
+            output_template TEXT,
+            builtin INTEGER NOT NULL DEFAULT 0,
+            position INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS cookie_profiles (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            cookies_path TEXT NOT NULL,
+            host_patterns_json TEXT NOT NULL DEFAULT '[]',
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_link_dump_secrets_active
+            ON link_dump_secrets(revoked_at, deleted_at);
+
+        CREATE INDEX IF NOT EXISTS idx_history_entries_completed_at
+            ON history_entries(completed_at, created_at);
+
+        CREATE INDEX IF NOT EXISTS idx_queue_jobs_state_position
+            ON queue_jobs(state, position);
+        "#,
+    )?;
+
+    ensure_history_entries_timestamp_column(conn)?;
+    ensure_history_entries_duration_seconds_column(conn)?;
+    ensure_history_entries_file_size_bytes_column(conn)?;
+    ensure_history_entries_text_column(conn, "uploader")?;
+    ensure_history_entries_text_column(conn, "medium")?;
+    ensure_history_entries_text_column(conn, "source")?;
+    ensure_history_entries_text_column(conn, "subtitle_paths_json")?;
+    ensure_history_entries_text_column(conn, "removed_segments_json")?;
+    ensure_history_entries_integer_column(conn, "removed_duration_seconds")?;
+    ensure_history_entries_text_column(conn, "parent_id")?;
+    ensure_history_entries_text_column(conn, "download_archive")?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "resume_queue_on_startup",
+        "INTEGER NOT NULL DEFAULT 1",
+    )?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "max_concurrent_downloads",
+        "INTEGER NOT NULL DEFAULT 1",
+    )?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "retry_max_attempts",
+        "INTEGER NOT NULL DEFAULT 3",
+    )?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "retry_base_delay_seconds",
+        "INTEGER NOT NULL DEFAULT 15",
+    )?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "retry_max_delay_seconds",
+        "INTEGER NOT NULL DEFAULT 600",
+    )?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "download_window_enabled",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "download_window_start",
+        "TEXT NOT NULL DEFAULT '23:00'",
+    )?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "download_window_end",
+        "TEXT NOT NULL DEFAULT '07:00'",
+    )?;
+    ensure_table_column(conn, "failed_jobs", "error_kind", "TEXT")?;
+    ensure_table_column(conn, "app_config", "rate_limit_kib", "INTEGER")?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "download_window_rate_limit_kib",
+        "INTEGER",
+    )?;
+    ensure_table_column(conn, "app_config", "throttled_rate_kib", "INTEGER")?;
+    ensure_table_column(
+        conn,
+        "app_config",
+        "cut_precision",
+        "TEXT NOT NULL DEFAULT 'fast'",
+    )?;
+    ensure_table_column(conn, "app_config", "output_template", "TEXT")?;
+    ensure_table_column(conn, "presets", "output_template", "TEXT")?;
+    backfill_history_sources(conn)?;
+    seed_builtin_presets(conn)?;
+    Ok(())
+}
+
+fn seed_builtin_presets(conn: &Connection) -> rusqlite::Result<()> {
+    for (position, builtin) in BUILTIN_PRESETS.iter().enumerate() {
+        upsert_preset_in_conn(conn, &builtin.to_preset(), position as i64)?;
+    }
+    Ok(())
+}
+
+fn backfill_history_sources(conn: &Connection) -> rusqlite::Result<()> {
+    let entries = {
+        let mut stmt = conn.prepare(
+            "SELECT id, url FROM history_entries WHERE source IS NULL OR TRIM(source) = ''",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    for (id, url) in entries {
+        if let Some(source) = source_from_url(&url) {
+            conn.execute(
+                "UPDATE history_entries SET source = ?1 WHERE id = ?2",
+                params![source, id],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn ensure_history_entries_timestamp_column(conn: &Connection) -> rusqlite::Result<()> {
+    ensure_history_entries_integer_column(conn, "timestamp")
+}
+
+fn ensure_history_entries_duration_seconds_column(conn: &Connection) -> rusqlite::Result<()> {
+    ensure_history_entries_integer_column(conn, "duration_seconds")
+}
+
+fn ensure_history_entries_file_size_bytes_column(conn: &Connection) -> rusqlite::Result<()> {
+    ensure_history_entries_integer_column(conn, "file_size_bytes")
+}
+
+fn ensure_history_entries_integer_column(
+    conn: &Connection,
+    column_name: &str,
+) -> rusqlite::Result<()> {
+    ensure_history_entries_column(conn, column_name, "INTEGER")
+}
+
+fn ensure_history_entries_text_column(
+    conn: &Connection,
+    column_name: &str,
+) -> rusqlite::Result<()> {
+    ensure_history_entries_column(conn, column_name, "TEXT")
+}
+
+fn ensure_history_entries_column(
+    conn: &Connection,
+    column_name: &str,
+    column_type: &str,
+) -> rusqlite::Result<()> {
+    ensure_table_column(conn, "history_entries", column_name, column_type)
+}
+
+fn ensure_table_column(
+    conn: &Connection,
+    table_name: &str,
+    column_name: &str,
+    column_definition: &str,
+) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table_name})"))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for column in columns {
+        if column? == column_name {
+            return Ok(());
+        }
+    }
+    drop(stmt);
+
+    conn.execute(
+        &format!("ALTER TABLE {table_name} ADD COLUMN {column_name} {column_definition}"),
+        [],
+    )?;
+    Ok(())
+}
+
+fn get_link_dump_settings_from_conn(conn: &Connection) -> rusqlite::Result<LinkDumpSettings> {
+    conn.query_row(
+        "SELECT server_enabled, host, port, created_at, updated_at FROM link_dump_settings WHERE id = 1",
+        [],
+        |row| {
+            Ok(LinkDumpSettings {
+                server_enabled: row.get::<_, i64>(0)? != 0,
+                host: row.get(1)?,
+                port: row.get::<_, i64>(2)? as u16,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        },
+    )
+}
+
+fn get_link_dump_settings(state: &AppState) -> Result<LinkDumpSettings, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    get_link_dump_settings_from_conn(&conn)
+        .map_err(|e| format!("Link Dump settings read failed: {e}"))
+}
+
+fn update_link_dump_settings_in_db(
+    state: &AppState,
+    patch: LinkDumpSettingsPatch,
+) -> Result<LinkDumpSettings, String> {
+    let mut current = get_link_dump_settings(state)?;
+    if let Some(enabled) = patch.server_enabled {
+        current.server_enabled = enabled;
+    }
+    if let Some(host) = patch.host {
+        let trimmed = host.trim();
+        if !trimmed.is_empty() {
+            current.host = normalize_link_dump_host(trimmed);
+        }
+    }
+    if let Some(port) = patch.port {
+        if port == 0 {
+            return Err("Port must be between 1 and 65535".to_string());
+        }
+        current.port = port;
+    }
+
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "UPDATE link_dump_settings
+         SET server_enabled = ?1, host = ?2, port = ?3, updated_at = datetime('now')
+         WHERE id = 1",
+        params![
+            if current.server_enabled { 1 } else { 0 },
+            current.host,
+            i64::from(current.port)
+        ],
+    )
+    .map_err(|e| format!("Link Dump settings update failed: {e}"))?;
+    get_link_dump_settings_from_conn(&conn)
+        .map_err(|e| format!("Link Dump settings read failed: {e}"))
+}
+
+fn list_link_dump_secrets_from_conn(
+    conn: &Connection,
+) -> rusqlite::Result<Vec<LinkDumpSecretView>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, created_at, last_used_at, revoked_at, deleted_at
+         FROM link_dump_secrets
+         WHERE deleted_at IS NULL
+         ORDER BY created_at DESC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let revoked_at: Option<String> = row.get(4)?;
+        let deleted_at: Option<String> = row.get(5)?;
+        let status = if deleted_at.is_some() {
+            "deleted"
+        } else if revoked_at.is_some() {
+            "revoked"
+        } else {
+            "active"
+        };
+        Ok(LinkDumpSecretView {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            created_at: row.get(2)?,
+            last_used_at: row.get(3)?,
+            revoked_at,
+            deleted_at,
+            status: status.to_string(),
+        })
+    })?;
+
+    rows.collect()
+}
+
+fn list_link_dump_secrets(state: &AppState) -> Result<Vec<LinkDumpSecretView>, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    list_link_dump_secrets_from_conn(&conn)
+        .map_err(|e| format!("Link Dump secrets read failed: {e}"))
+}
+
+fn next_link_dump_secret_name(conn: &Connection) -> rusqlite::Result<String> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM link_dump_secrets WHERE deleted_at IS NULL",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(format!("Link Dump Connection {}", count + 1))
+}
+
+fn generate_link_dump_secret_value() -> Result<String, String> {
+    let mut bytes = [0_u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Secret generation failed: {e}"))?;
+    Ok(format!("pfld_{}", URL_SAFE_NO_PAD.encode(bytes)))
+}
+
+fn hash_link_dump_secret(secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(secret.as_bytes());
+    bytes_to_hex(&hasher.finalize())
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        out.push_str(&format!("{byte:02x}"));
+    }
+    out
+}
+
+fn constant_time_eq_str(left: &str, right: &str) -> bool {
+    let left = left.as_bytes();
+    let right = right.as_bytes();
+    let max_len = left.len().max(right.len());
+    let mut diff = left.len() ^ right.len();
+
+    for index in 0..max_len {
+        let left_byte = left.get(index).copied().unwrap_or(0);
+        let right_byte = right.get(index).copied().unwrap_or(0);
+        diff |= usize::from(left_byte ^ right_byte);
+    }
+
+    diff == 0
+}
+
+fn create_link_dump_secret_in_db(
+    state: &AppState,
+    name: Option<String>,
+) -> Result<GeneratedLinkDumpSecret, String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    let clean_name = name
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .map(Ok)
+        .unwrap_or_else(|| next_link_dump_secret_name(&conn))
+        .map_err(|e| format!("Link Dump name generation failed: {e}"))?;
+
+    let secret = generate_link_dump_secret_value()?;
+    let secret_hash = hash_link_dump_secret(&secret);
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO link_dump_secrets (id, name, secret_hash, created_at)
+         VALUES (?1, ?2, ?3, datetime('now'))",
+        params![id, clean_name, secret_hash],
+    )
+    .map_err(|e| format!("Link Dump secret create failed: {e}"))?;
+
+    let connection = conn
+        .query_row(
+            "SELECT id, name, created_at, last_used_at, revoked_at, deleted_at
+             FROM link_dump_secrets
+             WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(LinkDumpSecretView {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    created_at: row.get(2)?,
+                    last_used_at: row.get(3)?,
+                    revoked_at: row.get(4)?,
+                    deleted_at: row.get(5)?,
+                    status: "active".to_string(),
+                })
+            },
+        )
+        .map_err(|e| format!("Link Dump secret read failed: {e}"))?;
+
+    Ok(GeneratedLinkDumpSecret { secret, connection })
+}
+
+fn revoke_link_dump_secret_in_db(state: &AppState, id: &str) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "UPDATE link_dump_secrets
+         SET revoked_at = COALESCE(revoked_at, datetime('now'))
+         WHERE id = ?1 AND deleted_at IS NULL",
+        params![id],
+    )
+    .map_err(|e| format!("Link Dump secret revoke failed: {e}"))?;
+    Ok(())
+}
+
+fn delete_link_dump_secret_in_db(state: &AppState, id: &str) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
+    conn.execute(
+        "UPDATE link_dump_secrets
+         SET deleted_at = COALESCE(deleted_at, datetime('now'))
+         WHERE id = ?1",
+        params![id],
+    )
+    .map_err(|e| format!("Link Dump secret delete failed: {e}"))?;
+    Ok(())
+}
+
+fn validate_link_dump_secret(
+    state: &AppState,
+    secret: Option<&str>,
+) -> Result<Option<ValidSecretResult>, String> {
+    let Some(secret) = secret.map(str::trim).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+
+    let candidate_hash = hash_link_dump_secret(secret);
+    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
     let mut stmt = conn
         .prepare(
-            "SELECT id, url, title, uploader, filename, thumbnail, upload_date, timestamp, duration_seconds, file_size_bytes, medium, source, platform, output_path, created_at, completed_at
-             FROM history_entries
-             ORDER BY COALESCE(completed_at, created_at) DESC, created_at DESC, id DESC
-             LIMIT ?1 OFFSET ?2",
+            "SELECT id, name, secret_hash
+             FROM link_dump_secrets
+             WHERE revoked_at IS NULL AND deleted_at IS NULL",
         )
-        .map_err(|e| format!("History read failed: {e}"))?;
-
+        .map_err(|e| format!("Link Dump secret validation failed: {e}"))?;
     let rows = stmt
-        .query_map(params![i64::from(limit), i64::from(offset)], |row| {
-            let created_at: i64 = row.get(14)?;
-            let completed_at: Option<i64> = row.get(15)?;
-            Ok(HistoryEntry {
-                id: row.get(0)?,
-                url: row.get(1)?,
-                title: row.get(2)?,
-                uploader: row.get(3)?,
-                filename: row.get(4)?,
-                thumbnail: row.get(5)?,
-                upload_date: row.get(6)?,
-                timestamp: row.get(7)?,
-                duration_seconds: row.get(8)?,
-                file_size_bytes: row.get(9)?,
-                medium: row.get(10)?,
-                source: row.get(11)?,
-                platform: row.get(12)?,
-                output_path: row.get(13)?,
-                created_at: i64_to_millis(created_at),
-                completed_at: optional_i64_to_millis(completed_at),
-            })
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
         })
-        .map_err(|e| format!("History read failed: {e}"))?;
+        .map_err(|e| format!("Link Dump secret validation failed: {e}"))?;
+
+    let mut matched: Option<ValidSecretResult> = None;
+    for row in rows {
+        let (id, name, stored_hash) =
+            row.map_err(|e| format!("Link Dump secret validation failed: {e}"))?;
+        if constant_time_eq_str(&candidate_hash, &stored_hash) {
+            matched = Some(ValidSecretResult { id, name });
+        }
+    }
+
+    if let Some(valid) = matched.as_ref() {
+        conn.execute(
+            "UPDATE link_dump_secrets SET last_used_at = datetime('now') WHERE id = ?1",
+            params![valid.id],
+        )
+        .map_err(|e| format!("Link Dump secret last-used update failed: {e}"))?;
+    }
+
+    Ok(matched)
+}
+
+fn snapshot_link_dump_server_status(state: &AppState) -> LinkDumpServerStatus {
+    state
+        .link_dump_server
+        .lock()
+        .map(|runtime| runtime.status.clone())
+        .unwrap_or_default()
+}
+
+fn emit_link_dump_server_status(app: &AppHandle, state: &AppState) {
+    let _ = app.emit_all(
+        "link-dump:server-status",
+        snapshot_link_dump_server_status(state),
+    );
+}
+
+#[tauri::command]
+fn get_link_dump_overview(state: State<AppState>) -> Result<LinkDumpOverview, String> {
+    Ok(LinkDumpOverview {
+        settings: get_link_dump_settings(state.inner())?,
+        secrets: list_link_dump_secrets(state.inner())?,
+        server_status: snapshot_link_dump_server_status(state.inner()),
+    })
+}
+
+#[tauri::command]
+fn update_link_dump_settings(
+    app: AppHandle,
+    state: State<AppState>,
+    patch: LinkDumpSettingsPatch,
+) -> Result<LinkDumpOverview, String> {
+    let settings = update_link_dump_settings_in_db(state.inner(), patch)?;
+    let server_status = restart_link_dump_server_internal(&app, state.inner())?;
+    Ok(LinkDumpOverview {
+        settings,
+        secrets: list_link_dump_secrets(state.inner())?,
+        server_status,
+    })
+}
+
+#[tauri::command]
+fn create_link_dump_secret(
+    state: State<AppState>,
+    name: Option<String>,
+) -> Result<GeneratedLinkDumpSecret, String> {
+    create_link_dump_secret_in_db(state.inner(), name)
+}
+
+#[tauri::command]
+fn revoke_link_dump_secret(
+    state: State<AppState>,
+    id: String,
+) -> Result<Vec<LinkDumpSecretView>, String> {
+    revoke_link_dump_secret_in_db(state.inner(), &id)?;
+    list_link_dump_secrets(state.inner())
+}
+
+#[tauri::command]
+fn delete_link_dump_secret(
+    state: State<AppState>,
+    id: String,
+) -> Result<Vec<LinkDumpSecretView>, String> {
+    delete_link_dump_secret_in_db(state.inner(), &id)?;
+    list_link_dump_secrets(state.inner())
+}
+
+#[tauri::command]
+fn restart_link_dump_server(
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<LinkDumpServerStatus, String> {
+    restart_link_dump_server_internal(&app, state.inner())
+}
+
+fn normalize_link_dump_host(host: &str) -> String {
+    if host.trim() == "127.0.1" {
+        return LINK_DUMP_DEFAULT_HOST.to_string();
+    }
+    host.trim().to_string()
+}
+
+fn is_allowed_link_dump_host(host: &str) -> bool {
+    let normalized = normalize_link_dump_host(host);
+    if normalized.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    normalized
+        .parse::<std::net::IpAddr>()
+        .map(|addr| addr.is_loopback())
+        .unwrap_or(false)
+}
+
+fn link_dump_server_url(settings: &LinkDumpSettings) -> String {
+    format!("http://{}:{}", settings.host, settings.port)
+}
+
+fn restart_link_dump_server_internal(
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<LinkDumpServerStatus, String> {
+    stop_link_dump_server(state);
+    start_link_dump_server_from_settings(app, state)
+}
 
-    let mut entries = Vec::new();
-    for row in rows {
-        entries.push(normalize_history_entry(
-            row.map_err(|e| format!("History read failed: {e}"))?,
-        ));
+fn stop_link_dump_server(state: &AppState) {
+    let handle = {
+        let Ok(mut runtime) = state.link_dump_server.lock() else {
+            return;
+        };
+        if let Some(shutdown) = runtime.shutdown.take() {
+            shutdown.store(true, Ordering::SeqCst);
+        }
+        runtime.handle.take()
+    };
+
+    if let Some(handle) = handle {
+        let _ = handle.join();
     }
 
-    let loaded_count = u64::from(offset).saturating_add(entries.len() as u64);
-    Ok(HistoryPage {
-        entries,
-        has_more: loaded_count < total.max(0) as u64,
-    })
+    if let Ok(mut runtime) = state.link_dump_server.lock() {
+        runtime.status.status = "stopped".to_string();
+        runtime.status.error_message = None;
+    }
 }
 
-fn list_history_entries_from_db(state: &AppState) -> Result<Vec<HistoryEntry>, String> {
-    Ok(list_history_page_from_db(state, u32::MAX, 0)?.entries)
+fn set_link_dump_server_status(
+    app: &AppHandle,
+    state: &AppState,
+    status: LinkDumpServerStatus,
+) -> LinkDumpServerStatus {
+    if let Ok(mut runtime) = state.link_dump_server.lock() {
+        runtime.status = status.clone();
+    }
+    emit_link_dump_server_status(app, state);
+    status
 }
 
-fn get_history_stats_from_db(state: &AppState) -> Result<HistoryStats, String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    conn.query_row(
-        "SELECT
-            COUNT(*),
-            COALESCE(SUM(duration_seconds), 0),
-            COALESCE(SUM(file_size_bytes), 0)
-         FROM history_entries",
-        [],
-        |row| {
-            let video_count: i64 = row.get(0)?;
-            let total_duration_seconds: i64 = row.get(1)?;
-            let total_file_size_bytes: i64 = row.get(2)?;
-            Ok(HistoryStats {
-                video_count: video_count.max(0) as u64,
-                total_duration_seconds: total_duration_seconds.max(0) as u64,
-                total_file_size_bytes: total_file_size_bytes.max(0) as u64,
-            })
-        },
-    )
-    .map_err(|e| format!("History stats read failed: {e}"))
-}
+fn start_link_dump_server_from_settings(
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<LinkDumpServerStatus, String> {
+    let mut settings = get_link_dump_settings(state)?;
+    settings.host = normalize_link_dump_host(&settings.host);
+    let url = link_dump_server_url(&settings);
 
-fn insert_history_entry_in_db(state: &AppState, entry: &HistoryEntry) -> Result<(), String> {
-    let entry = normalize_history_entry(entry.clone());
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    conn.execute(
-        "INSERT OR REPLACE INTO history_entries (
-            id,
+    if !settings.server_enabled {
+        let status = LinkDumpServerStatus {
+            status: "stopped".to_string(),
             url,
-            title,
-            uploader,
-            filename,
-            thumbnail,
-            upload_date,
-            timestamp,
-            duration_seconds,
-            file_size_bytes,
-            medium,
-            source,
-            platform,
-            output_path,
-            created_at,
-            completed_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
-        params![
-            entry.id,
-            entry.url,
-            entry.title,
-            entry.uploader,
-            entry.filename,
-            entry.thumbnail,
-            entry.upload_date,
-            entry.timestamp,
-            entry.duration_seconds,
-            entry.file_size_bytes,
-            entry.medium,
-            entry.source,
-            entry.platform,
-            entry.output_path,
-            millis_to_i64(entry.created_at),
-            entry.completed_at.map(millis_to_i64),
-        ],
-    )
-    .map_err(|e| format!("History insert failed: {e}"))?;
-    Ok(())
+            error_message: None,
+        };
+        return Ok(set_link_dump_server_status(app, state, status));
+    }
+
+    if !is_allowed_link_dump_host(&settings.host) {
+        let status = LinkDumpServerStatus {
+            status: "error".to_string(),
+            url,
+            error_message: Some("Link Dump Server only supports loopback hosts.".to_string()),
+        };
+        return Ok(set_link_dump_server_status(app, state, status));
+    }
+
+    let bind_addr = format!("{}:{}", settings.host, settings.port);
+    let listener = match TcpListener::bind(&bind_addr) {
+        Ok(listener) => listener,
+        Err(err) => {
+            let message = if err.kind() == std::io::ErrorKind::AddrInUse {
+                format!(
+                    "Link Dump Server could not start. Port {} is already in use.",
+                    settings.port
+                )
+            } else {
+                format!("Link Dump Server could not start: {err}")
+            };
+            let status = LinkDumpServerStatus {
+                status: "error".to_string(),
+                url,
+                error_message: Some(message),
+            };
+            return Ok(set_link_dump_server_status(app, state, status));
+        }
+    };
+
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Link Dump listener setup failed: {e}"))?;
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_thread = shutdown.clone();
+    let app_handle = app.clone();
+    let url_for_thread = url.clone();
+    let handle = thread::spawn(move || {
+        println!("Server started on {bind_addr}");
+        while !shutdown_thread.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let request_app = app_handle.clone();
+                    thread::spawn(move || {
+                        if let Err(err) = handle_link_dump_stream(stream, request_app) {
+                            eprintln!("Link Dump request rejected: {err}");
+                        }
+                    });
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(err) => {
+                    let state = app_handle.state::<AppState>();
+                    let status = LinkDumpServerStatus {
+                        status: "error".to_string(),
+                        url: url_for_thread.clone(),
+                        error_message: Some(format!("Link Dump Server stopped: {err}")),
+                    };
+                    let _ = set_link_dump_server_status(&app_handle, &state, status);
+                    break;
+                }
+            }
+        }
+    });
+
+    let status = LinkDumpServerStatus {
+        status: "running".to_string(),
+        url,
+        error_message: None,
+    };
+    {
+        let mut runtime = state
+            .link_dump_server
+            .lock()
+            .map_err(|_| "Link Dump server lock poisoned")?;
+        runtime.status = status.clone();
+        runtime.shutdown = Some(shutdown);
+        runtime.handle = Some(handle);
+    }
+    emit_link_dump_server_status(app, state);
+    Ok(status)
 }
 
-fn delete_history_entry_from_db(state: &AppState, id: &str) -> Result<(), String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    conn.execute("DELETE FROM history_entries WHERE id = ?1", params![id])
-        .map_err(|e| format!("History delete failed: {e}"))?;
-    Ok(())
+fn handle_link_dump_stream(mut stream: TcpStream, app: AppHandle) -> Result<(), String> {
+    let request = match read_http_request(&mut stream) {
+        Ok(request) => request,
+        Err(err) => {
+            let _ = write_json_response(
+                &mut stream,
+                400,
+                &json!({ "ok": false, "error": "Bad request" }),
+            );
+            return Err(err);
+        }
+    };
+
+    let path = request.path.split('?').next().unwrap_or("").to_string();
+    if request.method == "OPTIONS" {
+        if is_link_dump_endpoint(&path) {
+            return write_options_response(&mut stream);
+        }
+        return write_json_response(
+            &mut stream,
+            404,
+            &json!({ "ok": false, "error": "Not found" }),
+        );
+    }
+
+    if request.method != "POST" {
+        return write_json_response(
+            &mut stream,
+            405,
+            &json!({ "ok": false, "error": "Method not allowed" }),
+        );
+    }
+
+    let state = app.state::<AppState>();
+    match path.as_str() {
+        "/addYoutubeLinkToQueue/" | "/addYoutubeLinkToQueue" => {
+            handle_add_youtube_link(&app, state.inner(), &mut stream, &request.body)
+        }
+        "/addYoutubeLinksToQueue/" | "/addYoutubeLinksToQueue" => {
+            handle_add_youtube_links(&app, state.inner(), &mut stream, &request.body)
+        }
+        _ => write_json_response(
+            &mut stream,
+            404,
+            &json!({ "ok": false, "error": "Not found" }),
+        ),
+    }
+}
+
+fn is_link_dump_endpoint(path: &str) -> bool {
+    matches!(
+        path,
+        "/addYoutubeLinkToQueue/"
+            | "/addYoutubeLinkToQueue"
+            | "/addYoutubeLinksToQueue/"
+            | "/addYoutubeLinksToQueue"
+    )
 }
 
-fn clear_history_entries_in_db(state: &AppState) -> Result<(), String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    conn.execute("DELETE FROM history_entries", [])
-        .map_err(|e| format!("History clear failed: {e}"))?;
-    Ok(())
-}
+fn read_http_request(stream: &mut TcpStream) -> Result<HttpRequest, String> {
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .map_err(|e| format!("Read timeout setup failed: {e}"))?;
+    let mut buffer = Vec::new();
+    let mut temp = [0_u8; 4096];
+    let header_end;
 
-fn migrate_legacy_history_json(app: &AppHandle, state: &AppState) -> Result<(), String> {
-    if count_history_entries_in_db(state)? > 0 {
-        return Ok(());
+    loop {
+        let read = stream
+            .read(&mut temp)
+            .map_err(|e| format!("HTTP request read failed: {e}"))?;
+        if read == 0 {
+            return Err("HTTP request closed before headers".to_string());
+        }
+        buffer.extend_from_slice(&temp[..read]);
+        if buffer.len() > LINK_DUMP_MAX_BODY_BYTES {
+            return Err("HTTP request too large".to_string());
+        }
+        if let Some(index) = find_header_end(&buffer) {
+            header_end = index;
+            break;
+        }
     }
 
-    for entry in load_legacy_history_json(app) {
-        insert_history_entry_in_db(state, &entry)?;
-    }
-    Ok(())
-}
+    let header_text = std::str::from_utf8(&buffer[..header_end])
+        .map_err(|_| "HTTP headers are not UTF-8".to_string())?;
+    let mut lines = header_text.split("\r\n");
+    let request_line = lines
+        .next()
+        .ok_or_else(|| "Missing HTTP request line".to_string())?;
+    let mut request_parts = request_line.split_whitespace();
+    let method = request_parts
+        .next()
+        .ok_or_else(|| "Missing HTTP method".to_string())?
+        .to_string();
+    let path = request_parts
+        .next()
+        .ok_or_else(|| "Missing HTTP path".to_string())?
+        .to_string();
 
-fn load_legacy_config_json(app: &AppHandle) -> Option<AppConfig> {
-    if let Ok(path) = config_path(app) {
-        if let Ok(raw) = fs::read_to_string(path) {
-            if let Ok(cfg) = serde_json::from_str::<AppConfig>(&raw) {
-                return Some(normalize_app_config(cfg));
+    let mut content_length = 0_usize;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| "Invalid Content-Length".to_string())?;
             }
         }
     }
-    None
-}
-
-fn get_app_config_from_conn(conn: &Connection) -> rusqlite::Result<AppConfig> {
-    conn.query_row(
-        "SELECT yt_dlp_path, default_output_dir, selected_preset_key, magic_import_enabled, cut_at_timestamp_enabled, last_download_url
-         FROM app_config
-         WHERE id = 1",
-        [],
-        |row| {
-            Ok(normalize_app_config(AppConfig {
-                yt_dlp_path: row.get(0)?,
-                default_output_dir: row.get(1)?,
-                selected_preset_key: row.get(2)?,
-                magic_import_enabled: row.get::<_, i64>(3)? != 0,
-                cut_at_timestamp_enabled: row.get::<_, i64>(4)? != 0,
-                last_download_url: row.get(5)?,
-            }))
-        },
-    )
-}
 
-fn load_config_from_db(conn: &Connection) -> Result<AppConfig, String> {
-    get_app_config_from_conn(conn).map_err(|e| format!("Config read failed: {e}"))
-}
+    if content_length > LINK_DUMP_MAX_BODY_BYTES {
+        return Err("HTTP body too large".to_string());
+    }
 
-fn upsert_app_config_in_conn(conn: &Connection, config: &AppConfig) -> rusqlite::Result<()> {
-    let config = normalize_app_config(config.clone());
-    conn.execute(
-        "INSERT INTO app_config (
-            id,
-            yt_dlp_path,
-            default_output_dir,
-            selected_preset_key,
-            magic_import_enabled,
-            cut_at_timestamp_enabled,
-            last_download_url,
-            created_at,
-            updated_at
-        ) VALUES (
-            1,
-            ?1,
-            ?2,
-            ?3,
-            ?4,
-            ?5,
-            ?6,
-            datetime('now'),
-            datetime('now')
-        )
-        ON CONFLICT(id) DO UPDATE SET
-            yt_dlp_path = excluded.yt_dlp_path,
-            default_output_dir = excluded.default_output_dir,
-            selected_preset_key = excluded.selected_preset_key,
-            magic_import_enabled = excluded.magic_import_enabled,
-            cut_at_timestamp_enabled = excluded.cut_at_timestamp_enabled,
-            last_download_url = excluded.last_download_url,
-            updated_at = datetime('now')",
-        params![
-            config.yt_dlp_path,
-            config.default_output_dir,
-            config.selected_preset_key,
-            if config.magic_import_enabled { 1 } else { 0 },
-            if config.cut_at_timestamp_enabled {
-                1
-            } else {
-                0
-            },
-            config.last_download_url,
-        ],
-    )?;
-    Ok(())
-}
+    let body_start = header_end + 4;
+    let mut body = buffer.get(body_start..).unwrap_or_default().to_vec();
+    while body.len() < content_length {
+        let read = stream
+            .read(&mut temp)
+            .map_err(|e| format!("HTTP body read failed: {e}"))?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&temp[..read]);
+    }
+    body.truncate(content_length);
 
-fn save_config_to_db(state: &AppState, config: &AppConfig) -> Result<(), String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    upsert_app_config_in_conn(&conn, config).map_err(|e| format!("Config write failed: {e}"))
+    Ok(HttpRequest { method, path, body })
 }
 
-fn get_app_meta_value(conn: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
-    conn.query_row(
-        "SELECT value FROM app_meta WHERE key = ?1",
-        params![key],
-        |row| row.get(0),
-    )
-    .optional()
+fn find_header_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(4).position(|window| window == b"\r\n\r\n")
 }
 
-fn set_app_meta_value(conn: &Connection, key: &str, value: &str) -> rusqlite::Result<()> {
-    conn.execute(
-        "INSERT INTO app_meta (key, value) VALUES (?1, ?2)
-         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
-        params![key, value],
-    )?;
-    Ok(())
+fn write_options_response(stream: &mut TcpStream) -> Result<(), String> {
+    let response = concat!(
+        "HTTP/1.1 204 No Content\r\n",
+        "Access-Control-Allow-Origin: *\r\n",
+        "Access-Control-Allow-Methods: POST, OPTIONS\r\n",
+        "Access-Control-Allow-Headers: Content-Type\r\n",
+        "Access-Control-Max-Age: 86400\r\n",
+        "Content-Length: 0\r\n",
+        "Connection: close\r\n",
+        "\r\n"
+    );
+    stream
+        .write_all(response.as_bytes())
+        .map_err(|e| format!("HTTP response write failed: {e}"))
 }
 
-fn migrate_legacy_config_json(app: &AppHandle, conn: &Connection) -> Result<(), String> {
-    let already_migrated = get_app_meta_value(conn, LEGACY_CONFIG_MIGRATION_KEY)
-        .map_err(|e| format!("Config migration check failed: {e}"))?
-        .as_deref()
-        == Some("1");
-
-    if already_migrated {
-        return Ok(());
-    }
-
-    if let Some(config) = load_legacy_config_json(app) {
-        upsert_app_config_in_conn(conn, &config)
-            .map_err(|e| format!("Config migration failed: {e}"))?;
-    }
-
-    set_app_meta_value(conn, LEGACY_CONFIG_MIGRATION_KEY, "1")
-        .map_err(|e| format!("Config migration marker failed: {e}"))?;
-    Ok(())
+fn write_json_response(
+    stream: &mut TcpStream,
+    status_code: u16,
+    body: &serde_json::Value,
+) -> Result<(), String> {
+    let status_text = match status_code {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "OK",
+    };
+    let body = serde_json::to_string(body).map_err(|e| format!("JSON encode failed: {e}"))?;
+    let response = format!(
+        concat!(
+            "HTTP/1.1 {} {}\r\n",
+            "Content-Type: application/json\r\n",
+            "Access-Control-Allow-Origin: *\r\n",
+            "Access-Control-Allow-Methods: POST, OPTIONS\r\n",
+            "Access-Control-Allow-Headers: Content-Type\r\n",
+            "Access-Control-Max-Age: 86400\r\n",
+            "Content-Length: {}\r\n",
+            "Connection: close\r\n",
+            "\r\n",
+            "{}"
+        ),
+        status_code,
+        status_text,
+        body.as_bytes().len(),
+        body
+    );
+    stream
+        .write_all(response.as_bytes())
+        .map_err(|e| format!("HTTP response write failed: {e}"))
 }
 
-fn link_dump_db_path(app: &AppHandle) -> Result<PathBuf, String> {
-    let dir = tauri::api::path::app_data_dir(&app.config()).ok_or("Data directory unavailable")?;
-    fs::create_dir_all(&dir).map_err(|e| format!("Data dir create failed: {e}"))?;
-    Ok(dir.join("pinefetch.sqlite"))
-}
+fn handle_add_youtube_link(
+    app: &AppHandle,
+    state: &AppState,
+    stream: &mut TcpStream,
+    body: &[u8],
+) -> Result<(), String> {
+    let parsed = serde_json::from_slice::<AddYoutubeLinkRequestBody>(body);
+    let parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            return write_json_response(
+                stream,
+                400,
+                &json!({ "ok": false, "error": "Invalid request body" }),
+            );
+        }
+    };
 
-fn open_link_dump_db(app: &AppHandle) -> Result<Connection, String> {
-    let path = link_dump_db_path(app)?;
-    let conn = Connection::open(path).map_err(|e| format!("SQLite open failed: {e}"))?;
-    run_link_dump_migrations(&conn)
-        .map_err(|e| format!("Link Dump SQLite migration failed: {e}"))?;
-    println!("SQLite migration completed");
-    Ok(conn)
-}
+    let Some(_valid_secret) = validate_link_dump_secret(state, parsed.secret.as_deref())? else {
+        println!("Link Dump request rejected");
+        return write_json_response(
+            stream,
+            401,
+            &json!({ "ok": false, "error": "Unauthorized" }),
+        );
+    };
 
-fn run_link_dump_migrations(conn: &Connection) -> rusqlite::Result<()> {
-    conn.execute_batch(
-        r#"
-        CREATE TABLE IF NOT EXISTS link_dump_settings (
-            id INTEGER PRIMARY KEY CHECK (id = 1),
-            server_enabled INTEGER NOT NULL DEFAULT 1,
-            host TEXT NOT NULL DEFAULT '127.0.0.1',
-            port INTEGER NOT NULL DEFAULT 2255,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+    let Some(url) = parsed.url.as_deref().and_then(normalize_youtube_url) else {
+        return write_json_response(
+            stream,
+            400,
+            &json!({ "ok": false, "error": "Invalid YouTube URL" }),
         );
+    };
 
-        INSERT OR IGNORE INTO link_dump_settings (
-            id,
-            server_enabled,
-            host,
-            port,
-            created_at,
-            updated_at
-        ) VALUES (
-            1,
-            1,
-            '127.0.0.1',
-            2255,
-            datetime('now'),
-            datetime('now')
+    let Ok(preset) = link_dump_preset(state, parsed.preset.as_deref()) else {
+        return write_json_response(
+            stream,
+            400,
+            &json!({ "ok": false, "error": "Unknown preset" }),
         );
+    };
 
-        CREATE TABLE IF NOT EXISTS app_config (
-            id INTEGER PRIMARY KEY CHECK (id = 1),
-            yt_dlp_path TEXT,
-            default_output_dir TEXT,
-            selected_preset_key TEXT,
-            magic_import_enabled INTEGER NOT NULL DEFAULT 1,
-            cut_at_timestamp_enabled INTEGER NOT NULL DEFAULT 1,
-            last_download_url TEXT,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+    let mut summary = LinkDumpQueueSummary {
+        received: 1,
+        added: 0,
+        skipped: 0,
+        invalid: 0,
+    };
+    if add_normalized_youtube_urls_to_queue(app, state, &[url], &preset, &mut summary).is_err() {
+        return write_json_response(
+            stream,
+            500,
+            &json!({ "ok": false, "error": "Internal server error" }),
         );
+    }
+    println!("Link Dump request accepted");
+    println!("Added {} links to queue", summary.added);
+    write_json_response(
+        stream,
+        200,
+        &json!({
+            "ok": true,
+            "added": summary.added,
+            "skipped": summary.skipped,
+            "message": format!("Added {} YouTube link{} to queue.", summary.added, if summary.added == 1 { "" } else { "s" }),
+        }),
+    )
+}
 
-        INSERT OR IGNORE INTO app_config (
-            id,
-            yt_dlp_path,
-            default_output_dir,
-            selected_preset_key,
-            magic_import_enabled,
-            cut_at_timestamp_enabled,
-            last_download_url,
-            created_at,
-            updated_at
-        ) VALUES (
-            1,
-            NULL,
-            NULL,
-            'best',
-            1,
-            1,
-            NULL,
-            datetime('now'),
-            datetime('now')
+fn handle_add_youtube_links(
+    app: &AppHandle,
+    state: &AppState,
+    stream: &mut TcpStream,
+    body: &[u8],
+) -> Result<(), String> {
+    let parsed = serde_json::from_slice::<AddYoutubeLinksRequestBody>(body);
+    let parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            return write_json_response(
+                stream,
+                400,
+                &json!({ "ok": false, "error": "Invalid request body" }),
+            );
+        }
+    };
+
+    let Some(_valid_secret) = validate_link_dump_secret(state, parsed.secret.as_deref())? else {
+        println!("Link Dump request rejected");
+        return write_json_response(
+            stream,
+            401,
+            &json!({ "ok": false, "error": "Unauthorized" }),
         );
+    };
 
-        CREATE TABLE IF NOT EXISTS app_meta (
-            key TEXT PRIMARY KEY,
-            value TEXT NOT NULL
+    let urls = parsed.urls.unwrap_or_default();
+    if urls.is_empty() {
+        return write_json_response(
+            stream,
+            400,
+            &json!({ "ok": false, "error": "No valid YouTube URLs" }),
         );
+    }
 
-        CREATE TABLE IF NOT EXISTS link_dump_secrets (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            secret_hash TEXT NOT NULL UNIQUE,
-            created_at TEXT NOT NULL,
-            last_used_at TEXT,
-            revoked_at TEXT,
-            deleted_at TEXT
+    let Ok(preset) = link_dump_preset(state, parsed.preset.as_deref()) else {
+        return write_json_response(
+            stream,
+            400,
+            &json!({ "ok": false, "error": "Unknown preset" }),
         );
+    };
 
-        DROP TABLE IF EXISTS link_dump_request_log;
+    let mut summary = LinkDumpQueueSummary {
+        received: urls.len(),
+        added: 0,
+        skipped: 0,
+        invalid: 0,
+    };
+    let mut seen = std::collections::HashSet::new();
+    let mut normalized_urls = Vec::new();
 
-        CREATE TABLE IF NOT EXISTS history_entries (
-            id TEXT PRIMARY KEY,
-            url TEXT NOT NULL,
-            title TEXT,
-            uploader TEXT,
-            filename TEXT,
-            thumbnail TEXT,
-            upload_date TEXT,
-            timestamp INTEGER,
-            duration_seconds INTEGER,
-            file_size_bytes INTEGER,
-            medium TEXT,
-            source TEXT,
-            platform TEXT,
-            output_path TEXT,
-            created_at INTEGER NOT NULL,
-            completed_at INTEGER
-        );
+    for raw_url in urls.iter().take(LINK_DUMP_MAX_BATCH_SIZE) {
+        let Some(normalized) = normalize_youtube_url(raw_url) else {
+            summary.invalid += 1;
+            continue;
+        };
+        if !seen.insert(normalized.key.clone()) {
+            summary.skipped += 1;
+            continue;
+        }
+        normalized_urls.push(normalized);
+    }
 
-        CREATE INDEX IF NOT EXISTS idx_link_dump_secrets_active
-            ON link_dump_secrets(revoked_at, deleted_at);
+    if urls.len() > LINK_DUMP_MAX_BATCH_SIZE {
+        summary.skipped += urls.len() - LINK_DUMP_MAX_BATCH_SIZE;
+    }
 
-        CREATE INDEX IF NOT EXISTS idx_history_entries_completed_at
-            ON history_entries(completed_at, created_at);
-        "#,
-    )?;
+    if normalized_urls.is_empty() {
+        return write_json_response(
+            stream,
+            400,
+            &json!({ "ok": false, "error": "No valid YouTube URLs" }),
+        );
+    }
 
-    ensure_history_entries_timestamp_column(conn)?;
-    ensure_history_entries_duration_seconds_column(conn)?;
-    ensure_history_entries_file_size_bytes_column(conn)?;
-    ensure_history_entries_text_column(conn, "uploader")?;
-    ensure_history_entries_text_column(conn, "medium")?;
-    ensure_history_entries_text_column(conn, "source")?;
-    backfill_history_sources(conn)?;
-    Ok(())
+    if add_normalized_youtube_urls_to_queue(app, state, &normalized_urls, &preset, &mut summary)
+        .is_err()
+    {
+        return write_json_response(
+            stream,
+            500,
+            &json!({ "ok": false, "error": "Internal server error" }),
+        );
+    }
+
+    println!("Link Dump request accepted");
+    println!("Added {} links to queue", summary.added);
+    write_json_response(
+        stream,
+        200,
+        &json!({
+            "ok": true,
+            "received": summary.received,
+            "added": summary.added,
+            "skipped": summary.skipped,
+            "invalid": summary.invalid,
+            "message": format!("Added {} YouTube link{} to queue.", summary.added, if summary.added == 1 { "" } else { "s" }),
+        }),
+    )
 }
 
-fn backfill_history_sources(conn: &Connection) -> rusqlite::Result<()> {
-    let entries = {
-        let mut stmt = conn.prepare(
-            "SELECT id, url FROM history_entries WHERE source IS NULL OR TRIM(source) = ''",
-        )?;
-        let rows = stmt.query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-        })?;
-        rows.collect::<rusqlite::Result<Vec<_>>>()?
-    };
+fn add_normalized_youtube_urls_to_queue(
+    app: &AppHandle,
+    state: &AppState,
+    normalized_urls: &[NormalizedYoutubeUrl],
+    preset: &Preset,
+    summary: &mut LinkDumpQueueSummary,
+) -> Result<(), String> {
+    let mut queued_keys = queued_youtube_keys(state)?;
+    let mut jobs = Vec::new();
 
-    for (id, url) in entries {
-        if let Some(source) = source_from_url(&url) {
-            conn.execute(
-                "UPDATE history_entries SET source = ?1 WHERE id = ?2",
-                params![source, id],
-            )?;
+    for normalized in normalized_urls {
+        if !queued_keys.insert(normalized.key.clone()) {
+            summary.skipped += 1;
+            continue;
         }
+
+        let request = build_link_dump_download_request(state, normalized, preset)?;
+        jobs.push(build_download_job(state, request)?);
     }
 
+    let added_count = jobs.len();
+    enqueue_download_jobs(app, state, jobs)?;
+    summary.added += added_count;
     Ok(())
 }
 
-fn ensure_history_entries_timestamp_column(conn: &Connection) -> rusqlite::Result<()> {
-    ensure_history_entries_integer_column(conn, "timestamp")
+// Links use the preset named in the request body, or the selected one.
+fn link_dump_preset(state: &AppState, preset_key: Option<&str>) -> Result<Preset, String> {
+    let preset_key = match preset_key {
+        Some(preset_key) => Some(preset_key.to_string()),
+        None => state
+            .config
+            .lock()
+            .map_err(|_| "Config lock poisoned")?
+            .selected_preset_key
+            .clone(),
+    };
+    download_preset_for_key(state, preset_key.as_deref())
 }
 
-fn ensure_history_entries_duration_seconds_column(conn: &Connection) -> rusqlite::Result<()> {
-    ensure_history_entries_integer_column(conn, "duration_seconds")
-}
+fn build_link_dump_download_request(
+    state: &AppState,
+    normalized: &NormalizedYoutubeUrl,
+    preset: &Preset,
+) -> Result<DownloadRequest, String> {
+    let cut_at_timestamp_enabled = state
+        .config
+        .lock()
+        .map_err(|_| "Config lock poisoned")?
+        .cut_at_timestamp_enabled;
 
-fn ensure_history_entries_file_size_bytes_column(conn: &Connection) -> rusqlite::Result<()> {
-    ensure_history_entries_integer_column(conn, "file_size_bytes")
+    Ok(DownloadRequest {
+        url: normalized.url.clone(),
+        format: preset.format.clone(),
+        output_dir: None,
+        extract_audio: preset.extract_audio,
+        audio_format: preset.audio_format.clone(),
+        transcribe_text: preset.transcribe_text,
+        cut_at_timestamp_enabled,
+        cut_start_time: None,
+        cut_end_time: None,
+        ranges: Vec::new(),
+        cut_mode: CutMode::default(),
+        cut_precision: None,
+        video_format_id: None,
+        audio_format_id: None,
+        output_template: preset.output_template.clone(),
+        filename_suffix: preset.filename_suffix.clone(),
+        priority: 0,
+        not_before: None,
+        rate_limit_kib: None,
+        title: None,
+        uploader: None,
+        thumbnail: youtube_thumbnail_url_from_normalized(normalized),
+        upload_date: None,
+        timestamp: None,
+        duration_seconds: None,
+        force_download: false,
+        subtitles: preset.subtitles.clone(),
+        embed: preset.embed,
+        sponsorblock: preset.sponsorblock.clone(),
+        split_chapters: false,
+    })
 }
 
-fn ensure_history_entries_integer_column(
-    conn: &Connection,
-    column_name: &str,
-) -> rusqlite::Result<()> {
-    ensure_history_entries_column(conn, column_name, "INTEGER")
+fn queued_youtube_keys(state: &AppState) -> Result<std::collections::HashSet<String>, String> {
+    let queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
+    Ok(queue
+        .iter()
+        .filter_map(|job| normalize_youtube_url(&job.url).map(|normalized| normalized.key))
+        .collect())
 }
 
-fn ensure_history_entries_text_column(
-    conn: &Connection,
-    column_name: &str,
-) -> rusqlite::Result<()> {
-    ensure_history_entries_column(conn, column_name, "TEXT")
-}
+fn normalize_youtube_url(input: &str) -> Option<NormalizedYoutubeUrl> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let parsed = url::Url::parse(trimmed).ok()?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return None;
+    }
 
-fn ensure_history_entries_column(
-    conn: &Connection,
-    column_name: &str,
-    column_type: &str,
-) -> rusqlite::Result<()> {
-    let mut stmt = conn.prepare("PRAGMA table_info(history_entries)")?;
-    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
-    for column in columns {
-        if column? == column_name {
-            return Ok(());
+    let host = parsed
+        .host_str()?
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    let host_without_www = host.strip_prefix("www.").unwrap_or(&host);
+    let path_parts = parsed
+        .path_segments()
+        .map(|segments| segments.collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let video_id = if host_without_www == "youtu.be" {
+        path_parts.first().map(|part| (*part).to_string())
+    } else if matches!(
+        host_without_www,
+        "youtube.com" | "m.youtube.com" | "music.youtube.com"
+    ) {
+        match path_parts
+            .first()
+            .map(|part| part.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("watch") => parsed.query_pairs().find_map(|(name, value)| {
+                if name == "v" {
+                    Some(value.into_owned())
+                } else {
+                    None
+                }
+            }),
+            Some("shorts") | Some("live") | Some("embed") | Some("v") => {
+                path_parts.get(1).map(|part| (*part).to_string())
+            }
+            _ => None,
         }
-    }
-    drop(stmt);
+    } else {
+        None
+    }?;
 
-    conn.execute(
-        &format!("ALTER TABLE history_entries ADD COLUMN {column_name} {column_type}"),
-        [],
-    )?;
-    Ok(())
-}
+    if !is_plausible_youtube_video_id(&video_id) {
+        return None;
+    }
 
-fn get_link_dump_settings_from_conn(conn: &Connection) -> rusqlite::Result<LinkDumpSettings> {
-    conn.query_row(
-        "SELECT server_enabled, host, port, created_at, updated_at FROM link_dump_settings WHERE id = 1",
-        [],
-        |row| {
-            Ok(LinkDumpSettings {
-                server_enabled: row.get::<_, i64>(0)? != 0,
-                host: row.get(1)?,
-                port: row.get::<_, i64>(2)? as u16,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        },
-    )
+    Some(NormalizedYoutubeUrl {
+        url: format!("https://www.youtube.com/watch?v={video_id}"),
+        key: format!("youtube:{video_id}"),
+    })
 }
 
-fn get_link_dump_settings(state: &AppState) -> Result<LinkDumpSettings, String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    get_link_dump_settings_from_conn(&conn)
-        .map_err(|e| format!("Link Dump settings read failed: {e}"))
+fn is_plausible_youtube_video_id(video_id: &str) -> bool {
+    let len = video_id.len();
+    (6..=64).contains(&len)
+        && video_id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
 }
 
-fn update_link_dump_settings_in_db(
-    state: &AppState,
-    patch: LinkDumpSettingsPatch,
-) -> Result<LinkDumpSettings, String> {
-    let mut current = get_link_dump_settings(state)?;
-    if let Some(enabled) = patch.server_enabled {
-        current.server_enabled = enabled;
-    }
-    if let Some(host) = patch.host {
-        let trimmed = host.trim();
-        if !trimmed.is_empty() {
-            current.host = normalize_link_dump_host(trimmed);
-        }
-    }
-    if let Some(port) = patch.port {
-        if port == 0 {
-            return Err("Port must be between 1 and 65535".to_string());
-        }
-        current.port = port;
-    }
-
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    conn.execute(
-        "UPDATE link_dump_settings
-         SET server_enabled = ?1, host = ?2, port = ?3, updated_at = datetime('now')
-         WHERE id = 1",
-        params![
-            if current.server_enabled { 1 } else { 0 },
-            current.host,
-            i64::from(current.port)
-        ],
-    )
-    .map_err(|e| format!("Link Dump settings update failed: {e}"))?;
-    get_link_dump_settings_from_conn(&conn)
-        .map_err(|e| format!("Link Dump settings read failed: {e}"))
+fn youtube_thumbnail_url_from_normalized(normalized: &NormalizedYoutubeUrl) -> Option<String> {
+    normalized
+        .key
+        .strip_prefix("youtube:")
+        .map(|video_id| format!("https://i.ytimg.com/vi/{video_id}/mqdefault.jpg"))
 }
 
-fn list_link_dump_secrets_from_conn(
-    conn: &Connection,
-) -> rusqlite::Result<Vec<LinkDumpSecretView>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, name, created_at, last_used_at, revoked_at, deleted_at
-         FROM link_dump_secrets
-         WHERE deleted_at IS NULL
-         ORDER BY created_at DESC",
-    )?;
-    let rows = stmt.query_map([], |row| {
-        let revoked_at: Option<String> = row.get(4)?;
-        let deleted_at: Option<String> = row.get(5)?;
-        let status = if deleted_at.is_some() {
-            "deleted"
-        } else if revoked_at.is_some() {
-            "revoked"
-        } else {
-            "active"
-        };
-        Ok(LinkDumpSecretView {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            created_at: row.get(2)?,
-            last_used_at: row.get(3)?,
-            revoked_at,
-            deleted_at,
-            status: status.to_string(),
+fn main() {
+    let context = tauri::generate_context!();
+    tauri::Builder::default()
+        .setup(|app| {
+            let db = open_link_dump_db(&app.handle())?;
+            migrate_legacy_config_json(&app.handle(), &db)?;
+            let config = load_config_from_db(&db)?;
+            let state = AppState::new(config, db);
+            migrate_legacy_history_json(&app.handle(), &state)?;
+            app.manage(state);
+            let state = app.state::<AppState>();
+            if let Err(err) = restore_persisted_queue(&app.handle(), state.inner()) {
+                eprintln!("Queue restore failed: {err}");
+            }
+            spawn_queue_scheduler(app.handle());
+            spawn_subscription_poller(app.handle());
+            if let Err(err) = seed_download_archive(&app.handle(), state.inner()) {
+                eprintln!("Download archive seed failed: {err}");
+            }
+            let _ = start_link_dump_server_from_settings(&app.handle(), state.inner());
+            Ok(())
         })
-    })?;
-
-    rows.collect()
+        .invoke_handler(tauri::generate_handler![
+            get_config,
+            set_config,
+            set_selected_preset_key,
+            list_presets,
+            create_preset,
+            update_preset,
+            clone_preset,
+            delete_preset,
+            pick_preset_bundle_file,
+            pick_preset_export_path,
+            export_presets,
+            import_presets,
+            preview_output_filename,
+            cache_last_download_url,
+            pick_output_dir,
+            pick_txt_file,
+            open_folder,
+            open_file_path,
+            read_clipboard_text,
+            load_info,
+            load_playlist,
+            enqueue_playlist,
+            get_yt_dlp_installed_version,
+            get_queue_status,
+            get_queue,
+            set_queue_auto_start,
+            start_queue,
+            set_queue_paused,
+            pause_download,
+            resume_download,
+            move_queue_job,
+            move_queue_job_to_top,
+            move_queue_job_to_bottom,
+            set_queue_job_priority,
+            list_failed_jobs,
+            requeue_failed_job,
+            requeue_all_failed,
+            delete_failed_job,
+            get_job,
+            enqueue_download,
+            cancel_download,
+            get_history,
+            get_history_stats,
+            remove_history_entry,
+            clear_history,
+            get_link_dump_overview,
+            update_link_dump_settings,
+            create_link_dump_secret,
+            revoke_link_dump_secret,
+            delete_link_dump_secret,
+            restart_link_dump_server,
+            list_subscriptions,
+            add_subscription,
+            remove_subscription,
+            set_subscription_paused,
+            check_subscription_now,
+            list_cookie_profiles,
+            save_cookie_profile,
+            delete_cookie_profile,
+            validate_cookies_file,
+            pick_cookies_file
+        ])
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                let state = app_handle.state::<AppState>();
+                stop_link_dump_server(state.inner());
+            }
+        });
 }
 
-fn list_link_dump_secrets(state: &AppState) -> Result<Vec<LinkDumpSecretView>, String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    list_link_dump_secrets_from_conn(&conn)
-        .map_err(|e| format!("Link Dump secrets read failed: {e}"))
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn next_link_dump_secret_name(conn: &Connection) -> rusqlite::Result<String> {
-    let count: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM link_dump_secrets WHERE deleted_at IS NULL",
-        [],
-        |row| row.get(0),
-    )?;
-    Ok(format!("Link Dump Connection {}", count + 1))
-}
+    fn link_dump_test_state_with_config(config: AppConfig) -> AppState {
+        let conn = Connection::open_in_memory().unwrap();
+        run_link_dump_migrations(&conn).unwrap();
+        AppState::new(config, conn)
+    }
 
-fn generate_link_dump_secret_value() -> Result<String, String> {
-    let mut bytes = [0_u8; 32];
-    getrandom::getrandom(&mut bytes).map_err(|e| format!("Secret generation failed: {e}"))?;
-    Ok(format!("pfld_{}", URL_SAFE_NO_PAD.encode(bytes)))
-}
+    fn link_dump_test_state() -> AppState {
+        link_dump_test_state_with_config(AppConfig::default())
+    }
 
-fn hash_link_dump_secret(secret: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(secret.as_bytes());
-    bytes_to_hex(&hasher.finalize())
-}
+    #[test]
+    fn rejects_remote_open_targets() {
+        let err = canonical_existing_local_path("https://example.com").unwrap_err();
+        assert!(err.contains("local filesystem"));
+    }
 
-fn bytes_to_hex(bytes: &[u8]) -> String {
-    let mut out = String::with_capacity(bytes.len() * 2);
-    for byte in bytes {
-        out.push_str(&format!("{byte:02x}"));
+    #[test]
+    fn canonicalizes_existing_local_paths() {
+        let temp_dir = std::env::temp_dir();
+        let canonical = fs::canonicalize(&temp_dir).unwrap();
+
+        let resolved = canonical_existing_local_path(temp_dir.to_string_lossy().as_ref())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(resolved, canonical.to_string_lossy());
     }
-    out
-}
 
-fn constant_time_eq_str(left: &str, right: &str) -> bool {
-    let left = left.as_bytes();
-    let right = right.as_bytes();
-    let max_len = left.len().max(right.len());
-    let mut diff = left.len() ^ right.len();
+    #[cfg(unix)]
+    fn write_fake_ffmpeg_tool(path: &Path, exit_code: i32) {
+        use std::os::unix::fs::PermissionsExt;
 
-    for index in 0..max_len {
-        let left_byte = left.get(index).copied().unwrap_or(0);
-        let right_byte = right.get(index).copied().unwrap_or(0);
-        diff |= usize::from(left_byte ^ right_byte);
+        fs::write(path, format!("#!/bin/sh\nexit {exit_code}\n")).unwrap();
+        let mut permissions = fs::metadata(path).unwrap().permissions();
+        permissions.set_mode(0o755);
+        fs::set_permissions(path, permissions).unwrap();
     }
 
-    diff == 0
-}
+    #[cfg(unix)]
+    #[test]
+    fn normalizes_only_usable_ffmpeg_locations() {
+        let dir = std::env::temp_dir().join(format!("pinefetch-ffmpeg-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        write_fake_ffmpeg_tool(&dir.join(ffmpeg_tool_name()), 0);
+        write_fake_ffmpeg_tool(&dir.join(ffprobe_tool_name()), 0);
 
-fn create_link_dump_secret_in_db(
-    state: &AppState,
-    name: Option<String>,
-) -> Result<GeneratedLinkDumpSecret, String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    let clean_name = name
-        .as_deref()
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .map(str::to_string)
-        .map(Ok)
-        .unwrap_or_else(|| next_link_dump_secret_name(&conn))
-        .map_err(|e| format!("Link Dump name generation failed: {e}"))?;
+        assert_eq!(
+            normalize_ffmpeg_location(&dir).as_deref(),
+            Some(dir.to_string_lossy().as_ref())
+        );
 
-    let secret = generate_link_dump_secret_value()?;
-    let secret_hash = hash_link_dump_secret(&secret);
-    let id = Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT INTO link_dump_secrets (id, name, secret_hash, created_at)
-         VALUES (?1, ?2, ?3, datetime('now'))",
-        params![id, clean_name, secret_hash],
-    )
-    .map_err(|e| format!("Link Dump secret create failed: {e}"))?;
+        write_fake_ffmpeg_tool(&dir.join(ffprobe_tool_name()), 1);
 
-    let connection = conn
-        .query_row(
-            "SELECT id, name, created_at, last_used_at, revoked_at, deleted_at
-             FROM link_dump_secrets
-             WHERE id = ?1",
-            params![id],
-            |row| {
-                Ok(LinkDumpSecretView {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    created_at: row.get(2)?,
-                    last_used_at: row.get(3)?,
-                    revoked_at: row.get(4)?,
-                    deleted_at: row.get(5)?,
-                    status: "active".to_string(),
-                })
-            },
-        )
-        .map_err(|e| format!("Link Dump secret read failed: {e}"))?;
+        assert!(normalize_ffmpeg_location(&dir).is_none());
 
-    Ok(GeneratedLinkDumpSecret { secret, connection })
-}
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-fn revoke_link_dump_secret_in_db(state: &AppState, id: &str) -> Result<(), String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    conn.execute(
-        "UPDATE link_dump_secrets
-         SET revoked_at = COALESCE(revoked_at, datetime('now'))
-         WHERE id = ?1 AND deleted_at IS NULL",
-        params![id],
-    )
-    .map_err(|e| format!("Link Dump secret revoke failed: {e}"))?;
-    Ok(())
-}
+    #[test]
+    fn extracts_numeric_query_timestamp() {
+        let timestamp =
+            extract_url_start_timestamp("https://youtu.be/-PrRmxn2U-w?si=gsvuw4hTglBy5UQd&t=61");
 
-fn delete_link_dump_secret_in_db(state: &AppState, id: &str) -> Result<(), String> {
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    conn.execute(
-        "UPDATE link_dump_secrets
-         SET deleted_at = COALESCE(deleted_at, datetime('now'))
-         WHERE id = ?1",
-        params![id],
-    )
-    .map_err(|e| format!("Link Dump secret delete failed: {e}"))?;
-    Ok(())
-}
+        assert_eq!(timestamp, Some(61.0));
+    }
 
-fn validate_link_dump_secret(
-    state: &AppState,
-    secret: Option<&str>,
-) -> Result<Option<ValidSecretResult>, String> {
-    let Some(secret) = secret.map(str::trim).filter(|value| !value.is_empty()) else {
-        return Ok(None);
-    };
+    #[test]
+    fn extracts_compact_query_timestamp() {
+        let timestamp = extract_url_start_timestamp("https://example.com/watch?v=abc&t=1h2m3s");
 
-    let candidate_hash = hash_link_dump_secret(secret);
-    let conn = state.db.lock().map_err(|_| "SQLite lock poisoned")?;
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, name, secret_hash
-             FROM link_dump_secrets
-             WHERE revoked_at IS NULL AND deleted_at IS NULL",
-        )
-        .map_err(|e| format!("Link Dump secret validation failed: {e}"))?;
-    let rows = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-            ))
-        })
-        .map_err(|e| format!("Link Dump secret validation failed: {e}"))?;
+        assert_eq!(timestamp, Some(3723.0));
+    }
 
-    let mut matched: Option<ValidSecretResult> = None;
-    for row in rows {
-        let (id, name, stored_hash) =
-            row.map_err(|e| format!("Link Dump secret validation failed: {e}"))?;
-        if constant_time_eq_str(&candidate_hash, &stored_hash) {
-            matched = Some(ValidSecretResult { id, name });
-        }
+    #[test]
+    fn extracts_fragment_timestamp() {
+        let timestamp = extract_url_start_timestamp("https://example.com/video#t=01:02");
+
+        assert_eq!(timestamp, Some(62.0));
     }
 
-    if let Some(valid) = matched.as_ref() {
-        conn.execute(
-            "UPDATE link_dump_secrets SET last_used_at = datetime('now') WHERE id = ?1",
-            params![valid.id],
-        )
-        .map_err(|e| format!("Link Dump secret last-used update failed: {e}"))?;
+    #[test]
+    fn uses_requested_cut_start_time_when_provided() {
+        let timestamp = resolve_cut_start_time(true, Some(61.0), "https://example.com/video");
+
+        assert_eq!(timestamp, Some(61.0));
     }
 
-    Ok(matched)
-}
+    #[test]
+    fn disables_requested_cut_start_time() {
+        let timestamp = resolve_cut_start_time(false, Some(61.0), "https://example.com/video?t=62");
+
+        assert_eq!(timestamp, None);
+    }
+
+    #[test]
+    fn ignores_invalid_or_zero_timestamp() {
+        assert_eq!(
+            extract_url_start_timestamp("https://example.com/video?t=abc"),
+            None
+        );
+        assert_eq!(
+            extract_url_start_timestamp("https://example.com/video?t=0"),
+            None
+        );
+    }
+
+    #[test]
+    fn formats_yt_dlp_timestamp_without_extra_zeroes() {
+        assert_eq!(format_yt_dlp_timestamp(61.0), "61");
+        assert_eq!(format_yt_dlp_timestamp(61.5), "61.5");
+    }
 
-fn snapshot_link_dump_server_status(state: &AppState) -> LinkDumpServerStatus {
-    state
-        .link_dump_server
-        .lock()
-        .map(|runtime| runtime.status.clone())
-        .unwrap_or_default()
-}
+    #[test]
+    fn appends_filename_suffix_before_extension() {
+        let template =
+            build_output_template("/tmp/pinefetch", DEFAULT_OUTPUT_TEMPLATE, Some("__max"));
 
-fn emit_link_dump_server_status(app: &AppHandle, state: &AppState) {
-    let _ = app.emit_all(
-        "link-dump:server-status",
-        snapshot_link_dump_server_status(state),
-    );
-}
+        assert!(template.ends_with("%(title)s - %(uploader)s - %(id)s__max.%(ext)s"));
+    }
 
-#[tauri::command]
-fn get_link_dump_overview(state: State<AppState>) -> Result<LinkDumpOverview, String> {
-    Ok(LinkDumpOverview {
-        settings: get_link_dump_settings(state.inner())?,
-        secrets: list_link_dump_secrets(state.inner())?,
-        server_status: snapshot_link_dump_server_status(state.inner()),
-    })
-}
+    #[test]
+    fn validates_output_templates() {
+        let normalize = |raw: &str| normalize_output_template(Some(raw));
 
-#[tauri::command]
-fn update_link_dump_settings(
-    app: AppHandle,
-    state: State<AppState>,
-    patch: LinkDumpSettingsPatch,
-) -> Result<LinkDumpOverview, String> {
-    let settings = update_link_dump_settings_in_db(state.inner(), patch)?;
-    let server_status = restart_link_dump_server_internal(&app, state.inner())?;
-    Ok(LinkDumpOverview {
-        settings,
-        secrets: list_link_dump_secrets(state.inner())?,
-        server_status,
-    })
-}
+        assert_eq!(normalize("  ").unwrap(), None);
+        assert_eq!(
+            normalize(DEFAULT_OUTPUT_TEMPLATE).unwrap().as_deref(),
+            Some(DEFAULT_OUTPUT_TEMPLATE)
+        );
+        assert_eq!(
+            normalize("%(uploader|Unknown)s / %(upload_date>%Y)s/%(duration)05d - %(title)s 100%%")
+                .unwrap()
+                .as_deref(),
+            Some("%(uploader|Unknown)s/%(upload_date>%Y)s/%(duration)05d - %(title)s 100%%")
+        );
 
-#[tauri::command]
-fn create_link_dump_secret(
-    state: State<AppState>,
-    name: Option<String>,
-) -> Result<GeneratedLinkDumpSecret, String> {
-    create_link_dump_secret_in_db(state.inner(), name)
-}
+        for invalid in [
+            "%(title)s.%(ext)s",
+            "%(filepath)s",
+            "/%(title)s",
+            "../%(title)s",
+            "%(uploader)s//%(title)s",
+            "%(uploader)s/videos",
+            "%(title)s: %(id)s",
+            "%(title)s 100%",
+            "%(title",
+            "%(title)x",
+            "%(title)05s",
+            "%(title>%Y)s",
+            "%(upload_date>%Y/%m)s",
+            "%(uploader|a/b)s",
+        ] {
+            assert!(normalize(invalid).is_err(), "{invalid}");
+        }
+    }
 
-#[tauri::command]
-fn revoke_link_dump_secret(
-    state: State<AppState>,
-    id: String,
-) -> Result<Vec<LinkDumpSecretView>, String> {
-    revoke_link_dump_secret_in_db(state.inner(), &id)?;
-    list_link_dump_secrets(state.inner())
-}
+    #[test]
+    fn custom_output_templates_keep_suffix_and_subfolders() {
+        let mut job = download_job_fixture("job-1");
+        job.output_template = "%(uploader)s/%(upload_date>%Y)s/%(title)s".to_string();
+        job.filename_suffix = Some("_best".to_string());
+        job.cut_mode = CutMode::Range;
+        job.cut_start_time = Some(30.0);
+        job.cut_end_time = Some(90.0);
+
+        let expected = PathBuf::from("/tmp")
+            .join("%(uploader)s/%(upload_date>%Y)s/%(title)s_best_t30-90.%(ext)s");
+        assert_eq!(build_job_output_template(&job), expected.to_string_lossy());
+
+        job.playlist_title = Some("Talks".to_string());
+        job.playlist_index = Some(7);
+        job.playlist_in_output_template = true;
+        let expected = PathBuf::from("/tmp")
+            .join("Talks")
+            .join("%(uploader)s/%(upload_date>%Y)s")
+            .join("007 - %(title)s_best_t30-90.%(ext)s");
+        assert_eq!(build_job_output_template(&job), expected.to_string_lossy());
+    }
 
-#[tauri::command]
-fn delete_link_dump_secret(
-    state: State<AppState>,
-    id: String,
-) -> Result<Vec<LinkDumpSecretView>, String> {
-    delete_link_dump_secret_in_db(state.inner(), &id)?;
-    list_link_dump_secrets(state.inner())
-}
+    #[test]
+    fn resolves_output_template_from_request_or_config() {
+        let state = link_dump_test_state();
+        let mut request = clip_request("https://example.com/v", serde_json::json!([]));
+        assert_eq!(
+            build_download_job(&state, request.clone())
+                .unwrap()
+                .output_template,
+            DEFAULT_OUTPUT_TEMPLATE
+        );
 
-#[tauri::command]
-fn restart_link_dump_server(
-    app: AppHandle,
-    state: State<AppState>,
-) -> Result<LinkDumpServerStatus, String> {
-    restart_link_dump_server_internal(&app, state.inner())
-}
+        state.config.lock().unwrap().output_template = Some("%(channel)s/%(title)s".to_string());
+        assert_eq!(
+            build_download_job(&state, request.clone())
+                .unwrap()
+                .output_template,
+            "%(channel)s/%(title)s"
+        );
 
-fn normalize_link_dump_host(host: &str) -> String {
-    if host.trim() == "127.0.1" {
-        return LINK_DUMP_DEFAULT_HOST.to_string();
+        request.output_template = Some("%(id)s".to_string());
+        assert_eq!(
+            build_download_job(&state, request.clone())
+                .unwrap()
+                .output_template,
+            "%(id)s"
+        );
+
+        request.output_template = Some("%(ext)s".to_string());
+        assert!(build_download_job(&state, request).is_err());
     }
-    host.trim().to_string()
-}
 
-fn is_allowed_link_dump_host(host: &str) -> bool {
-    let normalized = normalize_link_dump_host(host);
-    if normalized.eq_ignore_ascii_case("localhost") {
-        return true;
+    #[test]
+    fn previews_extracted_audio_with_the_container_extension() {
+        let path = "/tmp/out/Talk - Pine - abc123def45.webm";
+        assert_eq!(
+            extracted_audio_output_path(path, "vorbis"),
+            "/tmp/out/Talk - Pine - abc123def45.ogg"
+        );
+        assert_eq!(
+            extracted_audio_output_path(path, "alac"),
+            "/tmp/out/Talk - Pine - abc123def45.m4a"
+        );
+        assert_eq!(
+            extracted_audio_output_path(path, "aac"),
+            "/tmp/out/Talk - Pine - abc123def45.m4a"
+        );
+        assert_eq!(
+            extracted_audio_output_path(path, "mp3"),
+            "/tmp/out/Talk - Pine - abc123def45.mp3"
+        );
+        assert_eq!(extracted_audio_output_path(path, "best"), path);
     }
-    normalized
-        .parse::<std::net::IpAddr>()
-        .map(|addr| addr.is_loopback())
-        .unwrap_or(false)
-}
 
-fn link_dump_server_url(settings: &LinkDumpSettings) -> String {
-    format!("http://{}:{}", settings.host, settings.port)
-}
+    #[test]
+    fn preview_info_gives_every_format_a_url() {
+        let info: InfoResponse = serde_json::from_value(serde_json::json!({
+            "title": "Talk",
+            "uploader": "Pine",
+            "duration": 61,
+            "thumbnail": null,
+            "upload_date": "20240102",
+            "timestamp": null,
+            "formats": [
+                { "format_id": "137", "ext": "mp4", "vcodec": "avc1", "acodec": "none", "height": 1080, "width": 1920, "fps": 30.0 },
+                { "format_id": null, "ext": "mhtml", "vcodec": "none", "acodec": "none", "height": null, "width": null, "fps": null },
+            ],
+            "description": null,
+            "id": "abc123def45",
+            "extractor_key": "Youtube",
+        }))
+        .unwrap();
 
-fn restart_link_dump_server_internal(
-    app: &AppHandle,
-    state: &AppState,
-) -> Result<LinkDumpServerStatus, String> {
-    stop_link_dump_server(state);
-    start_link_dump_server_from_settings(app, state)
-}
+        let value = build_preview_info_json(&info);
+        assert_eq!(value["id"], "abc123def45");
+        assert_eq!(value["extractor"], "youtube");
+        assert!(value.get("timestamp").is_none());
+        assert!(value.get("webpage_url").is_none());
+        let formats = value["formats"].as_array().unwrap();
+        assert_eq!(formats.len(), 1);
+        assert_eq!(formats[0]["format_id"], "137");
+        assert!(formats[0]["url"].is_string());
+        assert!(formats[0].get("filesize").is_none());
+
+        let value = build_preview_info_json(&InfoResponse {
+            formats: None,
+            id: None,
+            ..info
+        });
+        assert_eq!(value["id"], "preview");
+        assert!(value["url"].is_string());
+        assert_eq!(value["ext"], "mp4");
+    }
 
-fn stop_link_dump_server(state: &AppState) {
-    let handle = {
-        let Ok(mut runtime) = state.link_dump_server.lock() else {
-            return;
-        };
-        if let Some(shutdown) = runtime.shutdown.take() {
-            shutdown.store(true, Ordering::SeqCst);
-        }
-        runtime.handle.take()
-    };
+    #[test]
+    fn playlist_jobs_write_playlist_folder_and_index_into_template() {
+        let mut job = download_job_fixture("job-1");
+        job.playlist_title = Some("Talks: 2024/25 100%".to_string());
+        job.playlist_index = Some(7);
+        assert!(build_job_output_template(&job).ends_with("%(id)s.%(ext)s"));
+        assert!(!build_job_output_template(&job).contains("Talks"));
 
-    if let Some(handle) = handle {
-        let _ = handle.join();
+        job.playlist_in_output_template = true;
+        let template = build_job_output_template(&job);
+        let expected = PathBuf::from("/tmp")
+            .join("Talks_ 2024_25 100%%")
+            .join("007 - %(title)s - %(uploader)s - %(id)s.%(ext)s");
+        assert_eq!(template, expected.to_string_lossy());
     }
 
-    if let Ok(mut runtime) = state.link_dump_server.lock() {
-        runtime.status.status = "stopped".to_string();
-        runtime.status.error_message = None;
-    }
-}
+    #[test]
+    fn parses_flat_playlist_entries_with_titles_and_thumbnails() {
+        let value = serde_json::json!({
+            "_type": "playlist",
+            "id": "PL123",
+            "title": "Talks",
+            "channel": "PineFetch",
+            "entries": [
+                {
+                    "id": "abc123",
+                    "ie_key": "Youtube",
+                    "url": "https://www.youtube.com/watch?v=abc123",
+                    "title": "First",
+                    "duration": 61.0,
+                    "thumbnails": [
+                        { "url": "https://i.ytimg.com/vi/abc123/default.jpg" },
+                        { "url": "https://i.ytimg.com/vi/abc123/hqdefault.jpg" }
+                    ]
+                },
+                { "id": "def456", "ie_key": "Youtube", "title": "Second" },
+                { "id": "broken", "ie_key": "Generic" },
+                {
+                    "_type": "playlist",
+                    "entries": [
+                        { "url": "https://example.com/third", "title": "Third" }
+                    ]
+                }
+            ]
+        });
 
-fn set_link_dump_server_status(
-    app: &AppHandle,
-    state: &AppState,
-    status: LinkDumpServerStatus,
-) -> LinkDumpServerStatus {
-    if let Ok(mut runtime) = state.link_dump_server.lock() {
-        runtime.status = status.clone();
+        let playlist = parse_playlist_response(&value).unwrap();
+
+        assert_eq!(playlist.title.as_deref(), Some("Talks"));
+        assert_eq!(playlist.uploader.as_deref(), Some("PineFetch"));
+        assert_eq!(
+            playlist
+                .entries
+                .iter()
+                .map(|entry| (entry.index, entry.url.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "https://www.youtube.com/watch?v=abc123"),
+                (2, "https://www.youtube.com/watch?v=def456"),
+                (3, "https://example.com/third"),
+            ]
+        );
+        assert_eq!(
+            playlist.entries[0].thumbnail.as_deref(),
+            Some("https://i.ytimg.com/vi/abc123/hqdefault.jpg")
+        );
+        assert_eq!(playlist.entries[0].duration, Some(61));
+        assert!(parse_playlist_response(&serde_json::json!({ "id": "abc123" })).is_err());
     }
-    emit_link_dump_server_status(app, state);
-    status
-}
 
-fn start_link_dump_server_from_settings(
-    app: &AppHandle,
-    state: &AppState,
-) -> Result<LinkDumpServerStatus, String> {
-    let mut settings = get_link_dump_settings(state)?;
-    settings.host = normalize_link_dump_host(&settings.host);
-    let url = link_dump_server_url(&settings);
+    #[test]
+    fn selects_inclusive_playlist_range() {
+        let entries = (1..=30)
+            .map(|index| PlaylistEntry {
+                index,
+                id: None,
+                url: format!("https://example.com/{index}"),
+                title: None,
+                uploader: None,
+                thumbnail: None,
+                duration: None,
+            })
+            .collect::<Vec<_>>();
+        let options = PlaylistEnqueueOptions {
+            start: Some(5),
+            end: Some(20),
+            include_playlist_in_filename: false,
+        };
 
-    if !settings.server_enabled {
-        let status = LinkDumpServerStatus {
-            status: "stopped".to_string(),
-            url,
-            error_message: None,
+        let selected = select_playlist_entries(entries.clone(), &options).unwrap();
+        assert_eq!(selected.len(), 16);
+        assert_eq!(selected.first().map(|entry| entry.index), Some(5));
+        assert_eq!(selected.last().map(|entry| entry.index), Some(20));
+
+        let open_ended = PlaylistEnqueueOptions {
+            start: Some(25),
+            ..PlaylistEnqueueOptions::default()
         };
-        return Ok(set_link_dump_server_status(app, state, status));
-    }
+        assert_eq!(
+            select_playlist_entries(entries.clone(), &open_ended)
+                .unwrap()
+                .len(),
+            6
+        );
 
-    if !is_allowed_link_dump_host(&settings.host) {
-        let status = LinkDumpServerStatus {
-            status: "error".to_string(),
-            url,
-            error_message: Some("Link Dump Server only supports loopback hosts.".to_string()),
+        let reversed = PlaylistEnqueueOptions {
+            start: Some(9),
+            end: Some(3),
+            ..PlaylistEnqueueOptions::default()
         };
-        return Ok(set_link_dump_server_status(app, state, status));
+        assert!(select_playlist_entries(entries, &reversed).is_err());
     }
 
-    let bind_addr = format!("{}:{}", settings.host, settings.port);
-    let listener = match TcpListener::bind(&bind_addr) {
-        Ok(listener) => listener,
-        Err(err) => {
-            let message = if err.kind() == std::io::ErrorKind::AddrInUse {
-                format!(
-                    "Link Dump Server could not start. Port {} is already in use.",
-                    settings.port
-                )
-            } else {
-                format!("Link Dump Server could not start: {err}")
-            };
-            let status = LinkDumpServerStatus {
-                status: "error".to_string(),
-                url,
-                error_message: Some(message),
-            };
-            return Ok(set_link_dump_server_status(app, state, status));
-        }
-    };
-
-    listener
-        .set_nonblocking(true)
-        .map_err(|e| format!("Link Dump listener setup failed: {e}"))?;
-
-    let shutdown = Arc::new(AtomicBool::new(false));
-    let shutdown_thread = shutdown.clone();
-    let app_handle = app.clone();
-    let url_for_thread = url.clone();
-    let handle = thread::spawn(move || {
-        println!("Server started on {bind_addr}");
-        while !shutdown_thread.load(Ordering::SeqCst) {
-            match listener.accept() {
-                Ok((stream, _)) => {
-                    let request_app = app_handle.clone();
-                    thread::spawn(move || {
-                        if let Err(err) = handle_link_dump_stream(stream, request_app) {
-                            eprintln!("Link Dump request rejected: {err}");
-                        }
-                    });
-                }
-                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
-                    thread::sleep(Duration::from_millis(50));
-                }
-                Err(err) => {
-                    let state = app_handle.state::<AppState>();
-                    let status = LinkDumpServerStatus {
-                        status: "error".to_string(),
-                        url: url_for_thread.clone(),
-                        error_message: Some(format!("Link Dump Server stopped: {err}")),
-                    };
-                    let _ = set_link_dump_server_status(&app_handle, &state, status);
-                    break;
-                }
-            }
-        }
-    });
+    #[test]
+    fn subscription_entries_skip_known_and_filtered_videos() {
+        let entry = |index: u32, id: &str, title: &str, duration: Option<i64>| PlaylistEntry {
+            index,
+            id: Some(id.to_string()),
+            url: format!("https://www.youtube.com/watch?v={id}"),
+            title: Some(title.to_string()),
+            uploader: None,
+            thumbnail: None,
+            duration,
+        };
+        let entries = vec![
+            entry(1, "new001", "Weekly Podcast 12", Some(3600)),
+            entry(2, "seen01", "Weekly Podcast 11", Some(3600)),
+            entry(3, "short1", "Podcast teaser", Some(40)),
+            entry(4, "live01", "Weekly Podcast LIVE", None),
+            entry(5, "old001", "Weekly Podcast 10", Some(3600)),
+        ];
+        let filters = normalize_subscription_filters(SubscriptionFilters {
+            title_includes: Some(" podcast ".to_string()),
+            title_excludes: Some("live".to_string()),
+            min_duration_seconds: Some(60),
+            max_items_per_check: 4,
+            ..SubscriptionFilters::default()
+        });
+        let mut known_keys = HashSet::from([download_dedupe_key("https://youtu.be/seen01")]);
 
-    let status = LinkDumpServerStatus {
-        status: "running".to_string(),
-        url,
-        error_message: None,
-    };
-    {
-        let mut runtime = state
-            .link_dump_server
-            .lock()
-            .map_err(|_| "Link Dump server lock poisoned")?;
-        runtime.status = status.clone();
-        runtime.shutdown = Some(shutdown);
-        runtime.handle = Some(handle);
+        let selected = select_subscription_entries(entries.clone(), &filters, &mut known_keys);
+        assert_eq!(
+            selected
+                .iter()
+                .map(|entry| entry.id.as_deref().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["new001"]
+        );
+
+        assert!(select_subscription_entries(entries, &filters, &mut known_keys).is_empty());
     }
-    emit_link_dump_server_status(app, state);
-    Ok(status)
-}
 
-fn handle_link_dump_stream(mut stream: TcpStream, app: AppHandle) -> Result<(), String> {
-    let request = match read_http_request(&mut stream) {
-        Ok(request) => request,
-        Err(err) => {
-            let _ = write_json_response(
-                &mut stream,
-                400,
-                &json!({ "ok": false, "error": "Bad request" }),
-            );
-            return Err(err);
-        }
-    };
+    #[test]
+    fn seeds_download_archive_with_missing_youtube_history() {
+        let urls = vec![
+            "https://youtu.be/abc123?t=5".to_string(),
+            "https://www.youtube.com/watch?v=def456".to_string(),
+            "https://www.youtube.com/shorts/def456".to_string(),
+            "https://vimeo.com/12345".to_string(),
+        ];
 
-    let path = request.path.split('?').next().unwrap_or("").to_string();
-    if request.method == "OPTIONS" {
-        if is_link_dump_endpoint(&path) {
-            return write_options_response(&mut stream);
+        assert_eq!(
+            missing_download_archive_lines("youtube abc123\n", &urls),
+            vec!["youtube def456"]
+        );
+        assert!(missing_download_archive_lines("youtube abc123\nyoutube def456", &urls).is_empty());
+        assert!(is_download_archive_skip_line(
+            "[download] def456: Example has already been recorded in the archive"
+        ));
+    }
+
+    #[test]
+    fn seeds_download_archive_only_from_full_default_profile_downloads() {
+        let state = link_dump_test_state();
+        let archived = |id: &str, url: &str, archive: Option<&str>| HistoryEntry {
+            url: url.to_string(),
+            download_archive: archive.map(str::to_string),
+            ..history_entry_fixture(id)
+        };
+        for entry in [
+            archived(
+                "full",
+                "https://www.youtube.com/watch?v=abc123",
+                Some(DEFAULT_DOWNLOAD_ARCHIVE_FILE),
+            ),
+            archived("clip", "https://www.youtube.com/watch?v=clip12", None),
+            archived(
+                "audio",
+                "https://www.youtube.com/watch?v=audio1",
+                Some("download-archive-0123456789abcdef.txt"),
+            ),
+        ] {
+            insert_history_entry_in_db(&state, &entry).unwrap();
         }
-        return write_json_response(
-            &mut stream,
-            404,
-            &json!({ "ok": false, "error": "Not found" }),
+
+        let conn = state.db.lock().unwrap();
+        assert_eq!(
+            list_archived_history_urls_from_conn(&conn, DEFAULT_DOWNLOAD_ARCHIVE_FILE).unwrap(),
+            vec!["https://www.youtube.com/watch?v=abc123"]
         );
     }
 
-    if request.method != "POST" {
-        return write_json_response(
-            &mut stream,
-            405,
-            &json!({ "ok": false, "error": "Method not allowed" }),
+    #[test]
+    fn scopes_download_archive_per_output_profile() {
+        let mut video = download_job_fixture("video");
+        video.format = DEFAULT_DOWNLOAD_ARCHIVE_FORMAT.to_string();
+        assert_eq!(download_archive_file_name(&video), "download-archive.txt");
+
+        let mut audio = video.clone();
+        audio.extract_audio = true;
+        audio.audio_format = Some("mp3".to_string());
+        let mut opus = audio.clone();
+        opus.audio_format = Some("opus".to_string());
+        let mut picked = video.clone();
+        picked.format = "137+140".to_string();
+        let mut subtitled = video.clone();
+        subtitled.subtitles.write_subs = true;
+        subtitled.subtitles.languages = vec!["en".to_string()];
+
+        let names = [&video, &audio, &opus, &picked, &subtitled]
+            .iter()
+            .map(|job| download_archive_file_name(job))
+            .collect::<HashSet<_>>();
+        assert_eq!(names.len(), 5);
+        assert_eq!(
+            download_archive_file_name(&audio),
+            download_archive_file_name(&audio.clone())
         );
     }
 
-    let state = app.state::<AppState>();
-    match path.as_str() {
-        "/addYoutubeLinkToQueue/" | "/addYoutubeLinkToQueue" => {
-            handle_add_youtube_link(&app, state.inner(), &mut stream, &request.body)
-        }
-        "/addYoutubeLinksToQueue/" | "/addYoutubeLinksToQueue" => {
-            handle_add_youtube_links(&app, state.inner(), &mut stream, &request.body)
-        }
-        _ => write_json_response(
-            &mut stream,
-            404,
-            &json!({ "ok": false, "error": "Not found" }),
-        ),
+    #[test]
+    fn validates_netscape_cookie_files() {
+        let valid = "# Netscape HTTP Cookie File\n\
+            # comment\n\
+            .youtube.com\tTRUE\t/\tTRUE\t1893456000\tSID\tabc\n\
+            #HttpOnly_.youtube.com\tTRUE\t/\tTRUE\t0\tHSID\tdef\n\
+            www.example.com\tFALSE\t/\tFALSE\t\tsession\t\n";
+        let summary = parse_netscape_cookies(valid).unwrap();
+        assert_eq!(summary.cookie_count, 3);
+        assert_eq!(summary.domains, vec!["example.com", "youtube.com"]);
+
+        assert!(
+            parse_netscape_cookies(".youtube.com\tTRUE\t/\tTRUE\t0\tSID\tabc\n")
+                .unwrap_err()
+                .contains("header")
+        );
+        assert!(parse_netscape_cookies(
+            "# Netscape HTTP Cookie File\n.youtube.com TRUE / TRUE 0 SID abc\n"
+        )
+        .unwrap_err()
+        .starts_with("Line 2"));
+        assert!(parse_netscape_cookies("# Netscape HTTP Cookie File\n").is_err());
     }
-}
 
-fn is_link_dump_endpoint(path: &str) -> bool {
-    matches!(
-        path,
-        "/addYoutubeLinkToQueue/"
-            | "/addYoutubeLinkToQueue"
-            | "/addYoutubeLinksToQueue/"
-            | "/addYoutubeLinksToQueue"
-    )
-}
+    #[test]
+    fn cookies_copy_is_private_and_removed_on_drop() {
+        let source =
+            std::env::temp_dir().join(format!("pinefetch-test-cookies-{}.txt", Uuid::new_v4()));
+        fs::write(&source, "# Netscape HTTP Cookie File\n").unwrap();
+
+        let copy = CookiesCopy::create(source.to_str().unwrap()).unwrap();
+        let copy_path = PathBuf::from(copy.path());
+        assert_ne!(copy_path, source);
+        fs::write(&copy_path, "rewritten by yt-dlp").unwrap();
+        drop(copy);
+
+        assert!(!copy_path.exists());
+        assert_eq!(
+            fs::read_to_string(&source).unwrap(),
+            "# Netscape HTTP Cookie File\n"
+        );
+        fs::remove_file(&source).unwrap();
+    }
 
-fn read_http_request(stream: &mut TcpStream) -> Result<HttpRequest, String> {
-    stream
-        .set_read_timeout(Some(Duration::from_secs(5)))
-        .map_err(|e| format!("Read timeout setup failed: {e}"))?;
-    let mut buffer = Vec::new();
-    let mut temp = [0_u8; 4096];
-    let header_end;
+    #[test]
+    fn selects_most_specific_cookie_profile_for_url() {
+        let profile = |id: &str, patterns: &[&str]| CookieProfile {
+            id: id.to_string(),
+            name: id.to_string(),
+            cookies_path: format!("/tmp/{id}.txt"),
+            host_patterns: patterns
+                .iter()
+                .filter_map(|pattern| normalize_cookie_host_pattern(pattern))
+                .collect(),
+            created_at: 0,
+        };
+        let profiles = vec![
+            profile("youtube", &["*.youtube.com", "youtu.be"]),
+            profile("music", &["music.youtube.com"]),
+        ];
 
-    loop {
-        let read = stream
-            .read(&mut temp)
-            .map_err(|e| format!("HTTP request read failed: {e}"))?;
-        if read == 0 {
-            return Err("HTTP request closed before headers".to_string());
-        }
-        buffer.extend_from_slice(&temp[..read]);
-        if buffer.len() > LINK_DUMP_MAX_BODY_BYTES {
-            return Err("HTTP request too large".to_string());
-        }
-        if let Some(index) = find_header_end(&buffer) {
-            header_end = index;
-            break;
-        }
+        let select =
+            |url: &str| select_cookie_profile(profiles.clone(), url).map(|profile| profile.id);
+        assert_eq!(
+            select("https://www.youtube.com/watch?v=abc123").as_deref(),
+            Some("youtube")
+        );
+        assert_eq!(
+            select("https://youtu.be/abc123").as_deref(),
+            Some("youtube")
+        );
+        assert_eq!(
+            select("https://music.youtube.com/watch?v=abc123").as_deref(),
+            Some("music")
+        );
+        assert_eq!(select("https://notyoutube.com/watch?v=abc123"), None);
+        assert_eq!(select("https://vimeo.com/12345"), None);
     }
 
-    let header_text = std::str::from_utf8(&buffer[..header_end])
-        .map_err(|_| "HTTP headers are not UTF-8".to_string())?;
-    let mut lines = header_text.split("\r\n");
-    let request_line = lines
-        .next()
-        .ok_or_else(|| "Missing HTTP request line".to_string())?;
-    let mut request_parts = request_line.split_whitespace();
-    let method = request_parts
-        .next()
-        .ok_or_else(|| "Missing HTTP method".to_string())?
-        .to_string();
-    let path = request_parts
-        .next()
-        .ok_or_else(|| "Missing HTTP path".to_string())?
-        .to_string();
+    #[test]
+    fn stores_subscriptions_and_tracks_checks() {
+        let state = link_dump_test_state();
+        let subscription = Subscription {
+            id: "sub-1".to_string(),
+            url: "https://www.youtube.com/@example/videos".to_string(),
+            title: None,
+            preset_key: "audio_mp3".to_string(),
+            output_dir: None,
+            filters: SubscriptionFilters::default(),
+            paused: false,
+            last_checked_at: None,
+            last_error: None,
+            created_at: 1_000,
+        };
+        insert_subscription_in_db(&state, &subscription).unwrap();
+        assert!(is_subscription_due(&subscription, 1_000));
 
-    let mut content_length = 0_usize;
-    for line in lines {
-        if let Some((name, value)) = line.split_once(':') {
-            if name.trim().eq_ignore_ascii_case("content-length") {
-                content_length = value
-                    .trim()
-                    .parse::<usize>()
-                    .map_err(|_| "Invalid Content-Length".to_string())?;
-            }
+        record_subscription_check_in_db(&state, "sub-1", 5_000, Some("Example"), None).unwrap();
+        let stored = get_subscription_from_db(&state, "sub-1").unwrap();
+        assert_eq!(stored.title.as_deref(), Some("Example"));
+        assert_eq!(stored.last_checked_at, Some(5_000));
+        assert_eq!(
+            stored.filters.max_items_per_check,
+            SUBSCRIPTION_DEFAULT_MAX_ITEMS
+        );
+        assert!(!is_subscription_due(&stored, 6_000));
+        assert!(is_subscription_due(
+            &stored,
+            5_000 + SUBSCRIPTION_CHECK_INTERVAL_MILLIS
+        ));
+
+        update_subscription_paused_in_db(&state, "sub-1", true).unwrap();
+        let paused = get_subscription_from_db(&state, "sub-1").unwrap();
+        assert!(!is_subscription_due(
+            &paused,
+            5_000 + SUBSCRIPTION_CHECK_INTERVAL_MILLIS
+        ));
+
+        {
+            let conn = state.db.lock().unwrap();
+            conn.execute(
+                "INSERT INTO history_entries (id, url, created_at) VALUES ('h1', 'https://youtu.be/abc123', 1)",
+                [],
+            )
+            .unwrap();
         }
+        assert!(known_download_keys(&state)
+            .unwrap()
+            .contains("youtube:abc123"));
+
+        state.running_jobs.lock().unwrap().insert(
+            "job-1".to_string(),
+            RunningJob {
+                url: "https://www.youtube.com/watch?v=def456".to_string(),
+                child: None,
+                shared_rate_limit_kib: None,
+            },
+        );
+        record_failed_job_in_db(
+            &state,
+            &DownloadJob {
+                url: "https://youtu.be/ghi789".to_string(),
+                ..download_job_fixture("job-2")
+            },
+            &JobState::Cancelled { cancelled_at: 2 },
+        )
+        .unwrap();
+        let keys = known_download_keys(&state).unwrap();
+        assert!(keys.contains("youtube:def456"));
+        assert!(!keys.contains("youtube:ghi789"));
+
+        delete_subscription_from_db(&state, "sub-1").unwrap();
+        assert!(list_subscriptions_from_db(&state).unwrap().is_empty());
     }
 
-    if content_length > LINK_DUMP_MAX_BODY_BYTES {
-        return Err("HTTP body too large".to_string());
+    #[test]
+    fn parses_structured_progress_lines() {
+        let video = parse_yt_dlp_progress_line(
+            "job-1",
+            r#"pinefetch-progress avc1.640028 none {"status": "downloading", "downloaded_bytes": 524288, "total_bytes": 2097152, "speed": 1572864.0, "eta": 75}"#,
+        )
+        .unwrap();
+        assert_eq!(video.percent, Some(25.0));
+        assert_eq!(video.speed.as_deref(), Some("1.50MiB/s"));
+        assert_eq!(video.eta.as_deref(), Some("01:15"));
+        assert_eq!(video.total_bytes, Some(2_097_152));
+        assert_eq!(video.stream, Some(DownloadStream::Video));
+
+        let hls = parse_yt_dlp_progress_line(
+            "job-1",
+            r#"pinefetch-progress NA mp4a.40.2 {"status": "downloading", "downloaded_bytes": 1000, "total_bytes": null, "fragment_index": 3, "fragment_count": 12, "speed": null, "eta": null}"#,
+        )
+        .unwrap();
+        assert_eq!(hls.percent, Some(25.0));
+        assert_eq!(hls.fragment_count, Some(12));
+        assert_eq!(hls.speed, None);
+        assert_eq!(hls.stream, Some(DownloadStream::Audio));
+
+        let finished = parse_yt_dlp_progress_line(
+            "job-1",
+            r#"pinefetch-progress vp9 opus {"status": "finished", "downloaded_bytes": 10}"#,
+        )
+        .unwrap();
+        assert_eq!(finished.percent, Some(100.0));
+
+        assert!(parse_yt_dlp_progress_line(
+            "job-1",
+            "[download]  25.0% of 2.00MiB at 1.50MiB/s ETA 00:01"
+        )
+        .is_none());
+        assert_eq!(format_eta_seconds(3725), "1:02:05");
     }
 
-    let body_start = header_end + 4;
-    let mut body = buffer.get(body_start..).unwrap_or_default().to_vec();
-    while body.len() < content_length {
-        let read = stream
-            .read(&mut temp)
-            .map_err(|e| format!("HTTP body read failed: {e}"))?;
-        if read == 0 {
-            break;
-        }
-        body.extend_from_slice(&temp[..read]);
+    #[test]
+    fn parses_yt_dlp_filepath_output() {
+        assert_eq!(
+            parse_yt_dlp_filepath("/tmp/pinefetch/video.mp4").as_deref(),
+            Some("/tmp/pinefetch/video.mp4")
+        );
+        assert_eq!(
+            parse_yt_dlp_filepath("[download] Destination: /tmp/pinefetch/video.f398.mp4")
+                .as_deref(),
+            Some("/tmp/pinefetch/video.f398.mp4")
+        );
+        assert_eq!(
+            parse_yt_dlp_filepath("[Merger] Merging formats into \"/tmp/pinefetch/video.webm\"")
+                .as_deref(),
+            Some("/tmp/pinefetch/video.webm")
+        );
+        assert_eq!(parse_yt_dlp_filepath("[download] 100% of 1MiB"), None);
+        assert_eq!(parse_yt_dlp_filepath("NA"), None);
+        assert_eq!(parse_yt_dlp_filepath("https://example.com/video"), None);
+    }
+
+    #[test]
+    fn selects_last_existing_output_path() {
+        let dir = std::env::temp_dir().join(format!("pinefetch-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let first_path = dir.join("first.mp4");
+        let final_path = dir.join("final.mp3");
+        fs::write(&first_path, b"first").unwrap();
+        fs::write(&final_path, b"final").unwrap();
+
+        let candidates = vec![
+            first_path.to_string_lossy().to_string(),
+            dir.join("missing.webm").to_string_lossy().to_string(),
+            final_path.to_string_lossy().to_string(),
+        ];
+        let expected = final_path.to_string_lossy().to_string();
+
+        assert_eq!(
+            select_existing_output_path(&candidates).as_deref(),
+            Some(expected.as_str())
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn selects_largest_format_part_when_final_output_is_missing() {
+        let dir = std::env::temp_dir().join(format!("pinefetch-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let audio_part = dir.join("Example - Uploader - id__max.f251.webm");
+        let video_part = dir.join("Example - Uploader - id__max.f398.mp4");
+        fs::write(&audio_part, b"audio").unwrap();
+        fs::write(&video_part, b"larger video part").unwrap();
+
+        let candidates = vec![
+            audio_part.to_string_lossy().to_string(),
+            video_part.to_string_lossy().to_string(),
+        ];
+        let expected = video_part.to_string_lossy().to_string();
+
+        assert_eq!(
+            select_existing_output_path(&candidates).as_deref(),
+            Some(expected.as_str())
+        );
+
+        let _ = fs::remove_dir_all(&dir);
     }
-    body.truncate(content_length);
 
-    Ok(HttpRequest { method, path, body })
-}
+    #[test]
+    fn finds_related_format_parts_from_expected_output_path() {
+        let dir = std::env::temp_dir().join(format!("pinefetch-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let expected = dir.join("Example - Uploader - id__max.webm");
+        let video_part = dir.join("Example - Uploader - id__max.f398.mp4");
+        fs::write(&video_part, b"video").unwrap();
 
-fn find_header_end(buffer: &[u8]) -> Option<usize> {
-    buffer.windows(4).position(|window| window == b"\r\n\r\n")
-}
+        let related = related_existing_output_paths(&expected);
 
-fn write_options_response(stream: &mut TcpStream) -> Result<(), String> {
-    let response = concat!(
-        "HTTP/1.1 204 No Content\r\n",
-        "Access-Control-Allow-Origin: *\r\n",
-        "Access-Control-Allow-Methods: POST, OPTIONS\r\n",
-        "Access-Control-Allow-Headers: Content-Type\r\n",
-        "Access-Control-Max-Age: 86400\r\n",
-        "Content-Length: 0\r\n",
-        "Connection: close\r\n",
-        "\r\n"
-    );
-    stream
-        .write_all(response.as_bytes())
-        .map_err(|e| format!("HTTP response write failed: {e}"))
-}
+        assert_eq!(related, vec![video_part.to_string_lossy().to_string()]);
 
-fn write_json_response(
-    stream: &mut TcpStream,
-    status_code: u16,
-    body: &serde_json::Value,
-) -> Result<(), String> {
-    let status_text = match status_code {
-        200 => "OK",
-        204 => "No Content",
-        400 => "Bad Request",
-        401 => "Unauthorized",
-        404 => "Not Found",
-        405 => "Method Not Allowed",
-        500 => "Internal Server Error",
-        _ => "OK",
-    };
-    let body = serde_json::to_string(body).map_err(|e| format!("JSON encode failed: {e}"))?;
-    let response = format!(
-        concat!(
-            "HTTP/1.1 {} {}\r\n",
-            "Content-Type: application/json\r\n",
-            "Access-Control-Allow-Origin: *\r\n",
-            "Access-Control-Allow-Methods: POST, OPTIONS\r\n",
-            "Access-Control-Allow-Headers: Content-Type\r\n",
-            "Access-Control-Max-Age: 86400\r\n",
-            "Content-Length: {}\r\n",
-            "Connection: close\r\n",
-            "\r\n",
-            "{}"
-        ),
-        status_code,
-        status_text,
-        body.as_bytes().len(),
-        body
-    );
-    stream
-        .write_all(response.as_bytes())
-        .map_err(|e| format!("HTTP response write failed: {e}"))
-}
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-fn handle_add_youtube_link(
-    app: &AppHandle,
-    state: &AppState,
-    stream: &mut TcpStream,
-    body: &[u8],
-) -> Result<(), String> {
-    let parsed = serde_json::from_slice::<AddYoutubeLinkRequestBody>(body);
-    let parsed = match parsed {
-        Ok(parsed) => parsed,
-        Err(_) => {
-            return write_json_response(
-                stream,
-                400,
-                &json!({ "ok": false, "error": "Invalid request body" }),
-            );
-        }
-    };
+    #[test]
+    fn finds_subtitle_files_next_to_output_and_ignores_them_as_media() {
+        let dir = std::env::temp_dir().join(format!("pinefetch-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let media = dir.join("Example - Uploader - id.mp4");
+        let english = dir.join("Example - Uploader - id.en.srt");
+        let french = dir.join("Example - Uploader - id.fr.vtt");
+        fs::write(&media, b"video").unwrap();
+        fs::write(&english, b"1").unwrap();
+        fs::write(&french, b"WEBVTT").unwrap();
+        fs::write(dir.join("Other - Uploader - id.en.srt"), b"1").unwrap();
 
-    let Some(_valid_secret) = validate_link_dump_secret(state, parsed.secret.as_deref())? else {
-        println!("Link Dump request rejected");
-        return write_json_response(
-            stream,
-            401,
-            &json!({ "ok": false, "error": "Unauthorized" }),
+        assert_eq!(
+            find_subtitle_files(&media),
+            vec![
+                english.to_string_lossy().to_string(),
+                french.to_string_lossy().to_string(),
+            ]
         );
-    };
-
-    let Some(url) = parsed.url.as_deref().and_then(normalize_youtube_url) else {
-        return write_json_response(
-            stream,
-            400,
-            &json!({ "ok": false, "error": "Invalid YouTube URL" }),
+        assert_eq!(
+            related_existing_output_paths(&media),
+            vec![media.to_string_lossy().to_string()]
         );
-    };
 
-    let mut summary = LinkDumpQueueSummary {
-        received: 1,
-        added: 0,
-        skipped: 0,
-        invalid: 0,
-    };
-    if add_normalized_youtube_urls_to_queue(app, state, &[url], &mut summary).is_err() {
-        return write_json_response(
-            stream,
-            500,
-            &json!({ "ok": false, "error": "Internal server error" }),
-        );
+        let _ = fs::remove_dir_all(&dir);
     }
-    println!("Link Dump request accepted");
-    println!("Added {} links to queue", summary.added);
-    write_json_response(
-        stream,
-        200,
-        &json!({
-            "ok": true,
-            "added": summary.added,
-            "skipped": summary.skipped,
-            "message": format!("Added {} YouTube link{} to queue.", summary.added, if summary.added == 1 { "" } else { "s" }),
-        }),
-    )
-}
 
-fn handle_add_youtube_links(
-    app: &AppHandle,
-    state: &AppState,
-    stream: &mut TcpStream,
-    body: &[u8],
-) -> Result<(), String> {
-    let parsed = serde_json::from_slice::<AddYoutubeLinksRequestBody>(body);
-    let parsed = match parsed {
-        Ok(parsed) => parsed,
-        Err(_) => {
-            return write_json_response(
-                stream,
-                400,
-                &json!({ "ok": false, "error": "Invalid request body" }),
-            );
-        }
-    };
+    #[test]
+    fn builds_embed_args_with_audio_tags_from_job() {
+        let mut job = download_job_fixture("embed");
+        job.extract_audio = true;
+        job.title = Some("Intro".to_string());
+        job.uploader = Some("Band: Live 100%".to_string());
+        job.upload_date = Some("20240501".to_string());
+        job.embed = EmbedOptions {
+            metadata: true,
+            thumbnail: true,
+            chapters: false,
+        };
 
-    let Some(_valid_secret) = validate_link_dump_secret(state, parsed.secret.as_deref())? else {
-        println!("Link Dump request rejected");
-        return write_json_response(
-            stream,
-            401,
-            &json!({ "ok": false, "error": "Unauthorized" }),
+        assert_eq!(
+            embed_args(&job),
+            vec![
+                "--embed-metadata",
+                "--parse-metadata",
+                "pinefetch-tag Intro:pinefetch-tag (?P<meta_title>.+)",
+                "--parse-metadata",
+                "pinefetch-tag Band\\: Live 100%%:pinefetch-tag (?P<meta_artist>.+)",
+                "--parse-metadata",
+                "pinefetch-tag 2024-05-01:pinefetch-tag (?P<meta_date>.+)",
+                "--embed-thumbnail",
+            ]
         );
-    };
 
-    let urls = parsed.urls.unwrap_or_default();
-    if urls.is_empty() {
-        return write_json_response(
-            stream,
-            400,
-            &json!({ "ok": false, "error": "No valid YouTube URLs" }),
+        job.extract_audio = false;
+        job.embed.chapters = true;
+        assert_eq!(
+            embed_args(&job),
+            vec!["--embed-metadata", "--embed-thumbnail", "--embed-chapters"]
         );
+        job.embed = EmbedOptions::NONE;
+        assert!(embed_args(&job).is_empty());
     }
 
-    let mut summary = LinkDumpQueueSummary {
-        received: urls.len(),
-        added: 0,
-        skipped: 0,
-        invalid: 0,
-    };
-    let mut seen = std::collections::HashSet::new();
-    let mut normalized_urls = Vec::new();
+    #[test]
+    fn history_stats_subtract_removed_sponsorblock_segments() {
+        let state = link_dump_test_state();
+        let segments = parse_sponsorblock_segments_line(
+            r#"pinefetch-sponsorblock [{"start_time": 10.0, "end_time": 40.0, "category": "sponsor", "type": "skip"}, {"start_time": 30.0, "end_time": 50.5, "category": "selfpromo", "type": "skip"}, {"start_time": 60.0, "end_time": 60.0, "category": "poi_highlight", "type": "poi"}]"#,
+        )
+        .unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(removed_segments_duration_seconds(&segments), 41);
+        assert_eq!(
+            parse_sponsorblock_segments_line("pinefetch-sponsorblock NA"),
+            Some(Vec::new())
+        );
 
-    for raw_url in urls.iter().take(LINK_DUMP_MAX_BATCH_SIZE) {
-        let Some(normalized) = normalize_youtube_url(raw_url) else {
-            summary.invalid += 1;
-            continue;
+        let entry = HistoryEntry {
+            url: "https://www.youtube.com/watch?v=abc123".to_string(),
+            duration_seconds: Some(600),
+            removed_segments: segments.clone(),
+            ..history_entry_fixture("history-sponsorblock")
         };
-        if !seen.insert(normalized.key.clone()) {
-            summary.skipped += 1;
-            continue;
-        }
-        normalized_urls.push(normalized);
-    }
+        insert_history_entry_in_db(&state, &entry).unwrap();
 
-    if urls.len() > LINK_DUMP_MAX_BATCH_SIZE {
-        summary.skipped += urls.len() - LINK_DUMP_MAX_BATCH_SIZE;
+        let entries = list_history_entries_from_db(&state).unwrap();
+        assert_eq!(entries[0].removed_segments, segments);
+        assert_eq!(
+            get_history_stats_from_db(&state)
+                .unwrap()
+                .total_duration_seconds,
+            559
+        );
     }
 
-    if normalized_urls.is_empty() {
-        return write_json_response(
-            stream,
-            400,
-            &json!({ "ok": false, "error": "No valid YouTube URLs" }),
+    #[test]
+    fn builds_chapter_output_template_from_the_video_template() {
+        assert_eq!(
+            build_chapter_output_template("/tmp/out/%(title)s - %(uploader)s - %(id)s.%(ext)s"),
+            "/tmp/out/%(title)s - %(uploader)s - %(id)s/%(section_number)03d - %(section_title)s.%(ext)s"
         );
     }
 
-    if add_normalized_youtube_urls_to_queue(app, state, &normalized_urls, &mut summary).is_err() {
-        return write_json_response(
-            stream,
-            500,
-            &json!({ "ok": false, "error": "Internal server error" }),
+    #[test]
+    fn maps_split_chapter_files_to_chapters() {
+        let files = split_chapter_files_fixture();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "/tmp/out/Mix/001 - Intro.mp3");
+        assert_eq!(
+            files[0]
+                .chapter
+                .as_ref()
+                .and_then(|chapter| chapter.title.as_deref()),
+            Some("Intro")
         );
+        assert_eq!(files[1].path, "/tmp/out/Mix/002 - Outro.mp3");
     }
 
-    println!("Link Dump request accepted");
-    println!("Added {} links to queue", summary.added);
-    write_json_response(
-        stream,
-        200,
-        &json!({
-            "ok": true,
-            "received": summary.received,
-            "added": summary.added,
-            "skipped": summary.skipped,
-            "invalid": summary.invalid,
-            "message": format!("Added {} YouTube link{} to queue.", summary.added, if summary.added == 1 { "" } else { "s" }),
-        }),
-    )
-}
+    #[test]
+    fn chapter_history_entries_link_to_their_parent() {
+        let parent = chapter_parent_fixture();
+        let chapters = chapter_history_entries(&parent, &split_chapter_files_fixture());
 
-fn add_normalized_youtube_urls_to_queue(
-    app: &AppHandle,
-    state: &AppState,
-    normalized_urls: &[NormalizedYoutubeUrl],
-    summary: &mut LinkDumpQueueSummary,
-) -> Result<(), String> {
-    let mut queued_keys = queued_youtube_keys(state)?;
-    let mut jobs = Vec::new();
+        assert_eq!(chapters[0].title.as_deref(), Some("Intro"));
+        assert_eq!(chapters[0].duration_seconds, Some(95));
+        assert_eq!(chapters[1].title.as_deref(), Some("Mix (chapter 2)"));
+        assert_eq!(chapters[1].filename.as_deref(), Some("002 - Outro.mp3"));
+        assert_eq!(chapters[1].parent_id.as_deref(), Some("history-mix"));
+    }
 
-    for normalized in normalized_urls {
-        if !queued_keys.insert(normalized.key.clone()) {
-            summary.skipped += 1;
-            continue;
+    #[test]
+    fn chapter_history_entries_skip_stats_and_go_with_their_parent() {
+        let state = link_dump_test_state();
+        let parent = chapter_parent_fixture();
+        insert_history_entry_in_db(&state, &parent).unwrap();
+        for entry in chapter_history_entries(&parent, &split_chapter_files_fixture()) {
+            insert_history_entry_in_db(&state, &entry).unwrap();
+        }
+
+        let stats = get_history_stats_from_db(&state).unwrap();
+        assert_eq!(stats.video_count, 1);
+        assert_eq!(stats.total_duration_seconds, 300);
+
+        delete_history_entry_from_db(&state, "history-mix").unwrap();
+        assert!(list_history_entries_from_db(&state).unwrap().is_empty());
+    }
+
+    fn split_chapter_files_fixture() -> Vec<ChapterFile> {
+        let mut capture = SplitChaptersCapture::default();
+        for line in [
+            "[SplitChapters] Chapter 002; Destination: /tmp/out/Mix/002 - Outro.mp3",
+            "[SplitChapters] Chapter 001; Destination: /tmp/out/Mix/001 - Intro.mp3",
+            "[SplitChapters] Splitting video by chapters; 2 chapters found",
+        ] {
+            capture.files.extend(parse_split_chapter_line(line));
+        }
+        capture.chapters = parse_info_chapters(&serde_json::json!([
+            { "title": "Intro", "start_time": 0.0, "end_time": 95.4 },
+            { "title": "", "start_time": 95.4, "end_time": 300.0 },
+        ]));
+        capture.chapter_files()
+    }
+
+    fn chapter_parent_fixture() -> HistoryEntry {
+        HistoryEntry {
+            url: "https://www.youtube.com/watch?v=mix123".to_string(),
+            title: Some("Mix".to_string()),
+            uploader: Some("DJ".to_string()),
+            filename: Some("Mix.mp3".to_string()),
+            duration_seconds: Some(300),
+            file_size_bytes: Some(3_000),
+            medium: Some("audio".to_string()),
+            source: Some("youtube".to_string()),
+            platform: Some("youtube".to_string()),
+            output_path: Some("/tmp/out/Mix.mp3".to_string()),
+            ..history_entry_fixture("history-mix")
         }
+    }
 
-        let request = build_link_dump_download_request(state, normalized)?;
-        jobs.push(build_download_job(state, request)?);
+    #[test]
+    fn builds_sponsorblock_args_from_options() {
+        let options = normalize_sponsorblock_options(SponsorBlockOptions {
+            mode: SponsorBlockMode::Remove,
+            categories: vec!["Sponsor, selfpromo".to_string(), "sponsor".to_string()],
+        })
+        .unwrap();
+
+        assert_eq!(
+            sponsorblock_args(&options),
+            vec![
+                "--sponsorblock-remove",
+                "sponsor,selfpromo",
+                "--print",
+                "after_move:pinefetch-sponsorblock %(sponsorblock_chapters)j",
+            ]
+        );
+        assert_eq!(
+            sponsorblock_args(&SponsorBlockOptions {
+                mode: SponsorBlockMode::Mark,
+                categories: Vec::new(),
+            }),
+            vec!["--sponsorblock-mark", "default"]
+        );
+        assert!(sponsorblock_args(&SponsorBlockOptions::default()).is_empty());
+        assert!(normalize_sponsorblock_options(SponsorBlockOptions {
+            mode: SponsorBlockMode::Remove,
+            categories: vec!["poi_highlight".to_string()],
+        })
+        .is_err());
     }
 
-    let added_count = jobs.len();
-    enqueue_download_jobs(app, state, jobs)?;
-    summary.added += added_count;
-    Ok(())
-}
+    #[test]
+    fn builds_subtitle_args_from_options() {
+        let options = normalize_subtitle_options(SubtitleOptions {
+            write_subs: true,
+            write_auto_subs: true,
+            languages: vec![" en.* , de".to_string(), "de".to_string()],
+            convert_format: Some("SRT".to_string()),
+            embed: true,
+        })
+        .unwrap();
 
-fn build_link_dump_download_request(
-    state: &AppState,
-    normalized: &NormalizedYoutubeUrl,
-) -> Result<DownloadRequest, String> {
-    let (preset, cut_at_timestamp_enabled) = {
-        let cfg = state.config.lock().map_err(|_| "Config lock poisoned")?;
-        (
-            download_preset_for_key(cfg.selected_preset_key.as_deref()),
-            cfg.cut_at_timestamp_enabled,
+        assert_eq!(
+            subtitle_args(&options, false),
+            vec![
+                "--write-subs",
+                "--write-auto-subs",
+                "--sub-langs",
+                "en.*,de",
+                "--convert-subs",
+                "srt",
+                "--embed-subs",
+            ]
+        );
+        assert!(!subtitle_args(&options, true).contains(&"--embed-subs".to_string()));
+        assert!(subtitle_args(&SubtitleOptions::default(), false).is_empty());
+        assert!(normalize_subtitle_options(SubtitleOptions {
+            convert_format: Some("ass".to_string()),
+            ..SubtitleOptions::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_unsafe_filename_suffix() {
+        assert_eq!(
+            normalize_filename_suffix(Some("__max")),
+            Some("__max".to_string())
+        );
+        assert_eq!(normalize_filename_suffix(Some("../max")), None);
+        assert_eq!(normalize_filename_suffix(Some("")), None);
+    }
+
+    #[test]
+    fn normalizes_unknown_selected_preset_to_best() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_link_dump_migrations(&conn).unwrap();
+        upsert_app_config_in_conn(
+            &conn,
+            &AppConfig {
+                selected_preset_key: Some("missing".to_string()),
+                ..AppConfig::default()
+            },
         )
-    };
+        .unwrap();
+        let config = load_config_from_db(&conn).unwrap();
 
-    Ok(DownloadRequest {
-        url: normalized.url.clone(),
-        format: preset.format.to_string(),
-        output_dir: None,
-        extract_audio: preset.extract_audio,
-        audio_format: preset.audio_format.map(str::to_string),
-        transcribe_text: preset.transcribe_text,
-        cut_at_timestamp_enabled,
-        cut_start_time: None,
-        filename_suffix: preset.filename_suffix.map(str::to_string),
-        title: None,
-        uploader: None,
-        thumbnail: youtube_thumbnail_url_from_normalized(normalized),
-        upload_date: None,
-        timestamp: None,
-        duration_seconds: None,
-    })
-}
+        assert_eq!(
+            config.selected_preset_key.as_deref(),
+            Some(DEFAULT_DOWNLOAD_PRESET_KEY)
+        );
+    }
 
-fn queued_youtube_keys(state: &AppState) -> Result<std::collections::HashSet<String>, String> {
-    let queue = state.queue.lock().map_err(|_| "Queue lock poisoned")?;
-    Ok(queue
-        .iter()
-        .filter_map(|job| normalize_youtube_url(&job.url).map(|normalized| normalized.key))
-        .collect())
-}
+    #[test]
+    fn clamps_max_concurrent_downloads() {
+        let config = normalize_app_config(AppConfig {
+            max_concurrent_downloads: 0,
+            ..AppConfig::default()
+        });
+        assert_eq!(config.max_concurrent_downloads, 1);
 
-fn normalize_youtube_url(input: &str) -> Option<NormalizedYoutubeUrl> {
-    let trimmed = input.trim();
-    if trimmed.is_empty() {
-        return None;
+        let config = normalize_app_config(AppConfig {
+            max_concurrent_downloads: 64,
+            ..AppConfig::default()
+        });
+        assert_eq!(
+            config.max_concurrent_downloads,
+            MAX_CONCURRENT_DOWNLOADS_LIMIT
+        );
     }
-    let parsed = url::Url::parse(trimmed).ok()?;
-    if parsed.scheme() != "http" && parsed.scheme() != "https" {
-        return None;
+
+    #[test]
+    fn queue_status_reports_active_and_max_slots() {
+        let state = link_dump_test_state_with_config(AppConfig {
+            max_concurrent_downloads: 4,
+            ..AppConfig::default()
+        });
+        *state.active_workers.lock().unwrap() = 2;
+
+        let status = snapshot_queue_status(&state).unwrap();
+
+        assert!(status.worker_running);
+        assert_eq!(status.active_slots, 2);
+        assert_eq!(status.max_slots, 4);
     }
 
-    let host = parsed
-        .host_str()?
-        .trim_end_matches('.')
-        .to_ascii_lowercase();
-    let host_without_www = host.strip_prefix("www.").unwrap_or(&host);
-    let path_parts = parsed
-        .path_segments()
-        .map(|segments| segments.collect::<Vec<_>>())
-        .unwrap_or_default();
+    #[test]
+    fn app_config_defaults_are_loaded_from_sqlite() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_link_dump_migrations(&conn).unwrap();
 
-    let video_id = if host_without_www == "youtu.be" {
-        path_parts.first().map(|part| (*part).to_string())
-    } else if matches!(
-        host_without_www,
-        "youtube.com" | "m.youtube.com" | "music.youtube.com"
-    ) {
-        match path_parts
-            .first()
-            .map(|part| part.to_ascii_lowercase())
-            .as_deref()
-        {
-            Some("watch") => parsed.query_pairs().find_map(|(name, value)| {
-                if name == "v" {
-                    Some(value.into_owned())
-                } else {
-                    None
-                }
-            }),
-            Some("shorts") | Some("live") | Some("embed") | Some("v") => {
-                path_parts.get(1).map(|part| (*part).to_string())
-            }
-            _ => None,
-        }
-    } else {
-        None
-    }?;
+        let config = load_config_from_db(&conn).unwrap();
 
-    if !is_plausible_youtube_video_id(&video_id) {
-        return None;
+        assert_eq!(
+            config.selected_preset_key.as_deref(),
+            Some(DEFAULT_DOWNLOAD_PRESET_KEY)
+        );
+        assert!(config.magic_import_enabled);
+        assert!(config.cut_at_timestamp_enabled);
+        assert!(config.yt_dlp_path.is_none());
+        assert!(config.default_output_dir.is_none());
+        assert!(config.last_download_url.is_none());
+        assert_eq!(config.cut_precision, CutPrecision::Fast);
     }
 
-    Some(NormalizedYoutubeUrl {
-        url: format!("https://www.youtube.com/watch?v={video_id}"),
-        key: format!("youtube:{video_id}"),
-    })
-}
+    #[test]
+    fn app_config_is_stored_in_sqlite() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_link_dump_migrations(&conn).unwrap();
+        let config = AppConfig {
+            yt_dlp_path: Some("/opt/pinefetch/yt-dlp".to_string()),
+            default_output_dir: Some("/Users/example/Downloads".to_string()),
+            selected_preset_key: Some("audio_mp3".to_string()),
+            magic_import_enabled: false,
+            cut_at_timestamp_enabled: false,
+            last_download_url: Some("https://example.com/watch".to_string()),
+            resume_queue_on_startup: false,
+            max_concurrent_downloads: 3,
+            retry_max_attempts: 5,
+            retry_base_delay_seconds: 30,
+            retry_max_delay_seconds: 900,
+            download_window_enabled: true,
+            download_window_start: "22:30".to_string(),
+            download_window_end: "06:15".to_string(),
+            rate_limit_kib: Some(2048),
+            download_window_rate_limit_kib: None,
+            throttled_rate_kib: Some(100),
+            cut_precision: CutPrecision::Accurate,
+            output_template: Some("%(uploader)s/%(title)s".to_string()),
+        };
 
-fn is_plausible_youtube_video_id(video_id: &str) -> bool {
-    let len = video_id.len();
-    (6..=64).contains(&len)
-        && video_id
-            .bytes()
-            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
-}
+        upsert_app_config_in_conn(&conn, &config).unwrap();
+        let loaded = load_config_from_db(&conn).unwrap();
 
-fn youtube_thumbnail_url_from_normalized(normalized: &NormalizedYoutubeUrl) -> Option<String> {
-    normalized
-        .key
-        .strip_prefix("youtube:")
-        .map(|video_id| format!("https://i.ytimg.com/vi/{video_id}/mqdefault.jpg"))
-}
+        assert_eq!(loaded.yt_dlp_path.as_deref(), Some("/opt/pinefetch/yt-dlp"));
+        assert_eq!(
+            loaded.default_output_dir.as_deref(),
+            Some("/Users/example/Downloads")
+        );
+        assert_eq!(loaded.selected_preset_key.as_deref(), Some("audio_mp3"));
+        assert!(!loaded.magic_import_enabled);
+        assert!(!loaded.cut_at_timestamp_enabled);
+        assert_eq!(
+            loaded.last_download_url.as_deref(),
+            Some("https://example.com/watch")
+        );
+        assert!(!loaded.resume_queue_on_startup);
+        assert_eq!(loaded.max_concurrent_downloads, 3);
+        assert_eq!(loaded.retry_max_attempts, 5);
+        assert_eq!(loaded.retry_base_delay_seconds, 30);
+        assert_eq!(loaded.retry_max_delay_seconds, 900);
+        assert!(loaded.download_window_enabled);
+        assert_eq!(loaded.download_window_start, "22:30");
+        assert_eq!(loaded.download_window_end, "06:15");
+        assert_eq!(loaded.rate_limit_kib, Some(2048));
+        assert_eq!(loaded.download_window_rate_limit_kib, None);
+        assert_eq!(loaded.throttled_rate_kib, Some(100));
+        assert_eq!(loaded.cut_precision, CutPrecision::Accurate);
+        assert_eq!(
+            loaded.output_template.as_deref(),
+            Some("%(uploader)s/%(title)s")
+        );
+    }
 
-fn main() {
-    let context = tauri::generate_context!();
-    tauri::Builder::default()
-        .setup(|app| {
-            let db = open_link_dump_db(&app.handle())?;
-            migrate_legacy_config_json(&app.handle(), &db)?;
-            let config = load_config_from_db(&db)?;
-            let state = AppState::new(config, db);
-            migrate_legacy_history_json(&app.handle(), &state)?;
-            app.manage(state);
-            let state = app.state::<AppState>();
-            let _ = start_link_dump_server_from_settings(&app.handle(), state.inner());
-            Ok(())
-        })
-        .invoke_handler(tauri::generate_handler![
-            get_config,
-            set_config,
-            set_selected_preset_key,
-            cache_last_download_url,
-            pick_output_dir,
-            pick_txt_file,
-            open_folder,
-            open_file_path,
-            read_clipboard_text,
-            load_info,
-            get_yt_dlp_installed_version,
-            get_queue_status,
-            set_queue_auto_start,
-            start_queue,
-            enqueue_download,
-            cancel_download,
-            get_history,
-            get_history_stats,
-            remove_history_entry,
-            clear_history,
-            get_link_dump_overview,
-            update_link_dump_settings,
-            create_link_dump_secret,
-            revoke_link_dump_secret,
-            delete_link_dump_secret,
-            restart_link_dump_server
-        ])
-        .build(context)
-        .expect("error while building tauri application")
-        .run(|app_handle, event| {
-            if let tauri::RunEvent::ExitRequested { .. } = event {
-                let state = app_handle.state::<AppState>();
-                stop_link_dump_server(state.inner());
-            }
+    #[test]
+    fn link_dump_request_uses_selected_preset() {
+        let state = link_dump_test_state_with_config(AppConfig {
+            selected_preset_key: Some("audio_mp3".to_string()),
+            ..AppConfig::default()
         });
-}
+        let normalized = normalize_youtube_url("https://youtu.be/abc123").unwrap();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let preset = link_dump_preset(&state, None).unwrap();
+        let request = build_link_dump_download_request(&state, &normalized, &preset).unwrap();
 
-    fn link_dump_test_state_with_config(config: AppConfig) -> AppState {
-        let conn = Connection::open_in_memory().unwrap();
-        run_link_dump_migrations(&conn).unwrap();
-        AppState::new(config, conn)
+        assert_eq!(request.url, "https://www.youtube.com/watch?v=abc123");
+        assert_eq!(request.format, "ba/b");
+        assert!(request.extract_audio);
+        assert_eq!(request.audio_format.as_deref(), Some("mp3"));
+        assert!(!request.transcribe_text);
+        assert_eq!(request.filename_suffix, None);
     }
 
-    fn link_dump_test_state() -> AppState {
-        link_dump_test_state_with_config(AppConfig::default())
+    fn user_preset(key: &str) -> Preset {
+        serde_json::from_value(serde_json::json!({
+            "key": key,
+            "label": "Podcast",
+            "format": "ba/b",
+            "extract_audio": true,
+            "audio_format": "m4a",
+            "filename_suffix": "_pod",
+        }))
+        .unwrap()
     }
 
     #[test]
-    fn rejects_remote_open_targets() {
-        let err = canonical_existing_local_path("https://example.com").unwrap_err();
-        assert!(err.contains("local filesystem"));
+    fn seeds_builtin_presets_as_read_only() {
+        let state = link_dump_test_state();
+        let presets = list_presets_from_db(&state).unwrap();
+
+        assert_eq!(
+            presets
+                .iter()
+                .map(|preset| preset.key.as_str())
+                .collect::<Vec<_>>(),
+            BUILTIN_PRESETS
+                .iter()
+                .map(|preset| preset.key)
+                .collect::<Vec<_>>()
+        );
+        assert!(presets.iter().all(|preset| preset.builtin));
+
+        let err = save_user_preset_in_db(&state, user_preset("best"), true).unwrap_err();
+        assert!(err.contains("read-only"));
+        assert!(delete_user_preset_in_db(&state, "audio_mp3").is_err());
+        assert!(download_preset_for_key(&state, Some("missing")).is_err());
     }
 
     #[test]
-    fn canonicalizes_existing_local_paths() {
-        let temp_dir = std::env::temp_dir();
-        let canonical = fs::canonicalize(&temp_dir).unwrap();
+    fn stores_updates_and_deletes_user_presets() {
+        let state = link_dump_test_state();
 
-        let resolved = canonical_existing_local_path(temp_dir.to_string_lossy().as_ref())
-            .unwrap()
-            .unwrap();
+        let created = save_user_preset_in_db(&state, user_preset("podcast"), false).unwrap();
+        assert!(!created.builtin);
+        assert!(save_user_preset_in_db(&state, user_preset("podcast"), false).is_err());
+        assert!(save_user_preset_in_db(&state, user_preset("other"), true).is_err());
 
-        assert_eq!(resolved, canonical.to_string_lossy());
+        let updated = save_user_preset_in_db(
+            &state,
+            Preset {
+                audio_format: Some("opus".to_string()),
+                ..user_preset("podcast")
+            },
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            download_preset_for_key(&state, Some("podcast")).unwrap(),
+            updated
+        );
+
+        let preset = link_dump_preset(&state, Some("podcast")).unwrap();
+        let normalized = normalize_youtube_url("https://youtu.be/abc123").unwrap();
+        let request = build_link_dump_download_request(&state, &normalized, &preset).unwrap();
+        assert_eq!(request.audio_format.as_deref(), Some("opus"));
+        assert_eq!(request.filename_suffix.as_deref(), Some("_pod"));
+        assert!(link_dump_preset(&state, Some("missing")).is_err());
+
+        delete_user_preset_in_db(&state, "podcast").unwrap();
+        assert!(download_preset_for_key(&state, Some("podcast")).is_err());
     }
 
-    #[cfg(unix)]
-    fn write_fake_ffmpeg_tool(path: &Path, exit_code: i32) {
-        use std::os::unix::fs::PermissionsExt;
+    #[test]
+    fn exports_and_imports_preset_bundles() {
+        let source = link_dump_test_state();
+        save_user_preset_in_db(&source, user_preset("podcast"), false).unwrap();
+        let bundle = build_preset_bundle(&source, None).unwrap();
+        assert_eq!(bundle.version, PRESET_BUNDLE_VERSION);
+        assert_eq!(bundle.presets.len(), 1);
+        assert!(bundle.presets[0].get("builtin").is_none());
+        let raw = serde_json::to_string(&bundle).unwrap();
+
+        let target = link_dump_test_state();
+        let report = import_preset_bundle(&target, &raw, PresetConflictPolicy::Skip).unwrap();
+        assert_eq!(report.added, vec!["podcast"]);
+        assert_eq!(
+            download_preset_for_key(&target, Some("podcast")).unwrap(),
+            download_preset_for_key(&source, Some("podcast")).unwrap()
+        );
 
-        fs::write(path, format!("#!/bin/sh\nexit {exit_code}\n")).unwrap();
-        let mut permissions = fs::metadata(path).unwrap().permissions();
-        permissions.set_mode(0o755);
-        fs::set_permissions(path, permissions).unwrap();
+        let report = import_preset_bundle(&target, &raw, PresetConflictPolicy::Skip).unwrap();
+        assert!(report.added.is_empty());
+        assert_eq!(report.rejected[0].key, "podcast");
+        assert!(report.rejected[0].reason.contains("already exists"));
+
+        let report = import_preset_bundle(&target, &raw, PresetConflictPolicy::Overwrite).unwrap();
+        assert_eq!(report.updated, vec!["podcast"]);
+
+        let report = import_preset_bundle(&target, &raw, PresetConflictPolicy::Rename).unwrap();
+        assert_eq!(report.added, vec!["podcast-2"]);
     }
 
-    #[cfg(unix)]
     #[test]
-    fn normalizes_only_usable_ffmpeg_locations() {
-        let dir = std::env::temp_dir().join(format!("pinefetch-ffmpeg-test-{}", Uuid::new_v4()));
-        fs::create_dir_all(&dir).unwrap();
-        write_fake_ffmpeg_tool(&dir.join(ffmpeg_tool_name()), 0);
-        write_fake_ffmpeg_tool(&dir.join(ffprobe_tool_name()), 0);
+    fn renamed_preset_keys_stay_within_the_length_limit() {
+        let state = link_dump_test_state();
+        let long_key = "a".repeat(PRESET_KEY_MAX_LENGTH);
+        save_user_preset_in_db(&state, user_preset(&long_key), false).unwrap();
+
+        let key = next_free_preset_key(&state, &long_key).unwrap();
+        assert_eq!(key.len(), PRESET_KEY_MAX_LENGTH);
+        assert!(key.ends_with("-2"));
+        assert!(normalize_preset(user_preset(&key)).is_ok());
+
+        for number in 2..=PRESET_KEY_RENAME_LIMIT {
+            save_user_preset_in_db(&state, user_preset(&format!("podcast-{number}")), false)
+                .unwrap();
+        }
+        assert!(next_free_preset_key(&state, "podcast").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_bundle_entries_individually() {
+        let state = link_dump_test_state();
+        let raw = serde_json::json!({
+            "kind": PRESET_BUNDLE_KIND,
+            "version": 1,
+            "presets": [
+                { "key": "ok", "format": "ba/b" },
+                { "key": "ok", "format": "b" },
+                { "key": "bad-suffix", "format": "b", "filename_suffix": "../x" },
+                { "key": "best", "format": "b" },
+                { "format": "b" },
+            ],
+        })
+        .to_string();
 
+        let report = import_preset_bundle(&state, &raw, PresetConflictPolicy::Overwrite).unwrap();
+
+        assert_eq!(report.added, vec!["ok"]);
+        assert!(report.updated.is_empty());
         assert_eq!(
-            normalize_ffmpeg_location(&dir).as_deref(),
-            Some(dir.to_string_lossy().as_ref())
+            report
+                .rejected
+                .iter()
+                .map(|rejected| rejected.key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ok", "bad-suffix", "best", "#5"]
         );
+        assert!(report.rejected[2].reason.contains("read-only"));
 
-        write_fake_ffmpeg_tool(&dir.join(ffprobe_tool_name()), 1);
+        let newer = serde_json::json!({ "kind": PRESET_BUNDLE_KIND, "version": 2, "presets": [] });
+        assert!(
+            import_preset_bundle(&state, &newer.to_string(), PresetConflictPolicy::Skip)
+                .unwrap_err()
+                .contains("version 2")
+        );
+        assert!(import_preset_bundle(&state, "{}", PresetConflictPolicy::Skip).is_err());
+    }
 
-        assert!(normalize_ffmpeg_location(&dir).is_none());
+    #[test]
+    fn validates_user_presets() {
+        let preset = normalize_preset(Preset {
+            key: " podcast ".to_string(),
+            label: String::new(),
+            audio_format: Some("mp3".to_string()),
+            extract_audio: false,
+            ..user_preset("podcast")
+        })
+        .unwrap();
+        assert_eq!(preset.key, "podcast");
+        assert_eq!(preset.label, "podcast");
+        assert_eq!(preset.audio_format, None);
+
+        assert!(normalize_preset(user_preset("Bad Key")).is_err());
+        assert!(normalize_preset(Preset {
+            filename_suffix: Some("../up".to_string()),
+            ..user_preset("podcast")
+        })
+        .is_err());
+        assert!(normalize_preset(Preset {
+            audio_format: Some("exe".to_string()),
+            ..user_preset("podcast")
+        })
+        .is_err());
+        assert!(normalize_preset(Preset {
+            extract_audio: false,
+            transcribe_text: true,
+            ..user_preset("podcast")
+        })
+        .is_err());
+        assert!(normalize_preset(Preset {
+            format: " ".to_string(),
+            ..user_preset("podcast")
+        })
+        .is_err());
+        assert!(normalize_preset(Preset {
+            output_template: Some("../%(title)s".to_string()),
+            ..user_preset("podcast")
+        })
+        .is_err());
+    }
 
-        let _ = fs::remove_dir_all(&dir);
+    #[test]
+    fn local_cut_output_path_drops_the_source_marker() {
+        let path = build_local_cut_output_path(Path::new(
+            "/tmp/Title - Uploader - id_best_t13.source.webm",
+        ))
+        .unwrap();
+        assert_eq!(
+            path.to_string_lossy(),
+            "/tmp/Title - Uploader - id_best_t13.webm"
+        );
+
+        let path =
+            build_local_cut_output_path(Path::new("/tmp/Title - Uploader - id_best_t13.webm"))
+                .unwrap();
+        assert_eq!(
+            path.to_string_lossy(),
+            "/tmp/Title - Uploader - id_best_t13.webm"
+        );
     }
 
     #[test]
-    fn extracts_numeric_query_timestamp() {
-        let timestamp =
-            extract_url_start_timestamp("https://youtu.be/-PrRmxn2U-w?si=gsvuw4hTglBy5UQd&t=61");
+    fn local_cut_ranges_download_to_separate_files() {
+        let state = link_dump_test_state();
+        let mut request = clip_request(
+            "https://example.com/video.mp4",
+            serde_json::json!([
+                { "start": 30, "end": 90 },
+                { "start": 600 },
+            ]),
+        );
+        request.cut_mode = CutMode::Local;
+
+        let jobs = build_download_jobs(&state, request).unwrap();
+        let templates = jobs
+            .iter()
+            .map(build_job_output_template)
+            .collect::<Vec<_>>();
+
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs.iter().all(|job| job.cut_mode == CutMode::Local));
+        assert!(templates[0].ends_with("%(id)s_best_t30-90.source.%(ext)s"));
+        assert!(templates[1].ends_with("%(id)s_best_t600.source.%(ext)s"));
+
+        let range_job = DownloadJob {
+            cut_mode: CutMode::Range,
+            ..jobs[0].clone()
+        };
+        assert_ne!(build_job_output_template(&range_job), templates[0]);
+        assert_eq!(
+            existing_output_candidates_from_expected("/tmp/out/a_best_t30-90.source.mp4", &jobs[0])
+                [0..2],
+            [
+                "/tmp/out/a_best_t30-90.mp4".to_string(),
+                "/tmp/out/a_best_t30-90.source.mp4".to_string(),
+            ]
+        );
+    }
 
-        assert_eq!(timestamp, Some(61.0));
+    fn clip_request(url: &str, ranges: serde_json::Value) -> DownloadRequest {
+        serde_json::from_value(serde_json::json!({
+            "url": url,
+            "format": "best",
+            "output_dir": "/tmp/out",
+            "extract_audio": false,
+            "audio_format": null,
+            "transcribe_text": false,
+            "filename_suffix": "_best",
+            "ranges": ranges,
+        }))
+        .unwrap()
     }
 
     #[test]
-    fn extracts_compact_query_timestamp() {
-        let timestamp = extract_url_start_timestamp("https://example.com/watch?v=abc&t=1h2m3s");
+    fn resolves_clip_ranges_from_url_end_syntax() {
+        let ranges = |url: &str| resolve_clip_ranges(&clip_request(url, serde_json::json!([])));
 
-        assert_eq!(timestamp, Some(3723.0));
+        assert_eq!(
+            ranges("https://example.com/video.mp4#t=30,90").unwrap(),
+            vec![ClipRange {
+                start: 30.0,
+                end: Some(90.0)
+            }]
+        );
+        assert_eq!(
+            ranges("https://www.youtube.com/embed/abc123def45?start=30&end=1:30").unwrap(),
+            vec![ClipRange {
+                start: 30.0,
+                end: Some(90.0)
+            }]
+        );
+        assert_eq!(
+            ranges("https://example.com/video.mp4#t=npt:,45").unwrap(),
+            vec![ClipRange {
+                start: 0.0,
+                end: Some(45.0)
+            }]
+        );
+        assert_eq!(
+            extract_url_start_timestamp("https://example.com/video.mp4#t=30,90"),
+            Some(30.0)
+        );
+        assert!(ranges("https://example.com/video.mp4").unwrap().is_empty());
+        assert!(ranges("https://example.com/video.mp4#t=90,30").is_err());
     }
 
     #[test]
-    fn extracts_fragment_timestamp() {
-        let timestamp = extract_url_start_timestamp("https://example.com/video#t=01:02");
+    fn parses_extended_format_fields() {
+        let format = parse_info_format(&serde_json::json!({
+            "format_id": "251-drc",
+            "ext": "webm",
+            "vcodec": "none",
+            "acodec": "opus",
+            "filesize": null,
+            "filesize_approx": 3_456_789,
+            "tbr": 131.2,
+            "abr": 131.2,
+            "vbr": 0,
+            "language": "de",
+            "dynamic_range": null,
+            "protocol": "https",
+            "format_note": "German (dubbed), medium",
+        }));
+
+        assert_eq!(format.format_id.as_deref(), Some("251-drc"));
+        assert_eq!(format.filesize, None);
+        assert_eq!(format.filesize_approx, Some(3_456_789));
+        assert_eq!(format.abr, Some(131.2));
+        assert_eq!(format.vbr, None);
+        assert_eq!(format.language.as_deref(), Some("de"));
+        assert_eq!(format.dynamic_range, None);
+        assert_eq!(format.protocol.as_deref(), Some("https"));
+    }
 
-        assert_eq!(timestamp, Some(62.0));
+    #[test]
+    fn resolves_explicit_format_ids() {
+        let request = |video: Option<&str>, audio: Option<&str>| {
+            let mut request = clip_request("https://example.com/v", serde_json::json!([]));
+            request.video_format_id = video.map(str::to_string);
+            request.audio_format_id = audio.map(str::to_string);
+            resolve_request_format(&request)
+        };
+
+        assert_eq!(request(None, None).unwrap(), "best");
+        assert_eq!(request(Some("137"), Some("251-1")).unwrap(), "137+251-1");
+        assert_eq!(request(Some("137"), None).unwrap(), "137+ba/137");
+        assert_eq!(request(None, Some(" 140 ")).unwrap(), "140");
+        assert_eq!(request(Some(""), None).unwrap(), "best");
+        assert!(request(Some("137+140"), None).is_err());
+        assert!(request(None, Some("ba[language=de]")).is_err());
     }
 
     #[test]
-    fn uses_requested_cut_start_time_when_provided() {
-        let timestamp = resolve_cut_start_time(true, Some(61.0), "https://example.com/video");
+    fn builds_one_job_per_clip_range() {
+        let state = link_dump_test_state();
+        let request = clip_request(
+            "https://www.youtube.com/watch?v=abc123def45",
+            serde_json::json!([
+                { "start": 30, "end": 90 },
+                { "start": 600 },
+                { "start": 30, "end": 90 },
+            ]),
+        );
 
-        assert_eq!(timestamp, Some(61.0));
+        let jobs = build_download_jobs(&state, request).unwrap();
+
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs[0].batch_id.is_some());
+        assert_eq!(jobs[0].batch_id, jobs[1].batch_id);
+        assert_eq!(
+            (jobs[0].cut_start_time, jobs[0].cut_end_time),
+            (Some(30.0), Some(90.0))
+        );
+        assert_eq!(
+            (jobs[1].cut_start_time, jobs[1].cut_end_time),
+            (Some(600.0), None)
+        );
+        assert!(build_job_output_template(&jobs[0]).ends_with("%(id)s_best_t30-90.%(ext)s"));
+        assert!(build_job_output_template(&jobs[1]).ends_with("%(id)s_best_t600.%(ext)s"));
     }
 
     #[test]
-    fn disables_requested_cut_start_time() {
-        let timestamp = resolve_cut_start_time(false, Some(61.0), "https://example.com/video?t=62");
+    fn builds_download_section_and_range_output_path() {
+        assert_eq!(download_section_spec(90.0, Some(150.5)), "*90-150.5");
+        assert_eq!(download_section_spec(10_200.0, None), "*10200-inf");
 
-        assert_eq!(timestamp, None);
+        assert_eq!(
+            format_cut_range_filename_suffix(90.0, Some(150.5)),
+            "_t90-150_5"
+        );
+        assert_eq!(format_cut_range_filename_suffix(13.0, None), "_t13");
     }
 
     #[test]
-    fn ignores_invalid_or_zero_timestamp() {
+    fn accurate_cut_reencodes_media_streams_with_source_codecs() {
+        let probe = parse_media_probe(
+            r#"{
+                "streams": [
+                    {"index": 0, "codec_name": "vp9", "codec_type": "video", "disposition": {"attached_pic": 0}},
+                    {"index": 1, "codec_name": "opus", "codec_type": "audio", "disposition": {"attached_pic": 0}, "tags": {"BPS": "131072"}},
+                    {"index": 2, "codec_name": "webvtt", "codec_type": "subtitle", "disposition": {"attached_pic": 0}},
+                    {"index": 3, "codec_name": "mjpeg", "codec_type": "video", "disposition": {"attached_pic": 1}}
+                ],
+                "format": {"duration": "212.480000"}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(probe.duration_seconds, Some(212.48));
+        let encoders = ["libvpx-vp9", "libopus"]
+            .into_iter()
+            .map(str::to_string)
+            .collect::<HashSet<_>>();
         assert_eq!(
-            extract_url_start_timestamp("https://example.com/video?t=abc"),
-            None
+            accurate_cut_codec_args(&probe.streams, &encoders).unwrap(),
+            vec![
+                "-c:0",
+                "libvpx-vp9",
+                "-crf:0",
+                "31",
+                "-b:0",
+                "0",
+                "-c:1",
+                "libopus",
+                "-b:1",
+                "131072",
+                "-c:2",
+                "copy",
+                "-c:3",
+                "copy"
+            ]
+        );
+
+        let unknown = ProbedStream {
+            index: 0,
+            codec_type: "video".to_string(),
+            codec_name: "prores".to_string(),
+            attached_pic: false,
+            bit_rate: None,
+        };
+        assert!(accurate_cut_codec_args(&[unknown], &encoders).is_err());
+    }
+
+    #[test]
+    fn accurate_cut_needs_an_installed_encoder() {
+        let encoders = parse_ffmpeg_encoders(
+            "Encoders:\n V..... = Video\n A..... = Audio\n ------\n V....D libx264              libx264 H.264 / AVC\n V....D libaom-av1           libaom AV1\n A....D aac                  AAC (Advanced Audio Coding)\n",
         );
         assert_eq!(
-            extract_url_start_timestamp("https://example.com/video?t=0"),
-            None
+            encoders,
+            ["libx264", "libaom-av1", "aac"]
+                .into_iter()
+                .map(str::to_string)
+                .collect::<HashSet<_>>()
+        );
+
+        let stream = |codec_name: &str| ProbedStream {
+            index: 0,
+            codec_type: "video".to_string(),
+            codec_name: codec_name.to_string(),
+            attached_pic: false,
+            bit_rate: Some(4_000_000),
+        };
+        assert_eq!(
+            accurate_cut_codec_args(&[stream("h264")], &encoders).unwrap(),
+            vec!["-c:0", "libx264", "-b:0", "4000000"]
+        );
+        assert_eq!(
+            accurate_cut_codec_args(&[stream("av1")], &encoders).unwrap_err(),
+            "ffmpeg has no libsvtav1 encoder for av1 streams"
+        );
+    }
+
+    #[test]
+    fn parses_ffmpeg_progress_blocks() {
+        let mut block = FfmpegProgressBlock::default();
+        assert!(!block.apply_line("frame=120"));
+        assert!(!block.apply_line("out_time_us=15000000"));
+        assert!(!block.apply_line("speed=2.5x"));
+        assert!(block.apply_line("progress=continue"));
+
+        assert_eq!(block.percent(Some(60.0)), Some(25.0));
+        assert_eq!(block.eta_seconds(Some(60.0)), Some(18.0));
+        assert_eq!(block.percent(None), None);
+
+        assert!(!block.apply_line("out_time_ms=75000000"));
+        assert!(!block.apply_line("speed=N/A"));
+        assert!(block.apply_line("progress=end"));
+        assert_eq!(block.percent(Some(60.0)), Some(100.0));
+        assert_eq!(block.eta_seconds(Some(60.0)), None);
+    }
+
+    #[test]
+    fn sanitizes_decimal_timestamp_suffix() {
+        assert_eq!(format_timestamp_filename_suffix(13.5), "_t13_5");
+    }
+
+    #[test]
+    fn extracts_source_from_url_without_subdomain_or_tld() {
+        assert_eq!(
+            source_from_url("https://www.youtube.com/watch?v=abc123").as_deref(),
+            Some("youtube")
+        );
+        assert_eq!(
+            source_from_url("https://media.linkedin.com/posts/123").as_deref(),
+            Some("linkedin")
+        );
+        assert_eq!(
+            source_from_url("https://video.example.co.uk/watch/123").as_deref(),
+            Some("example")
+        );
+        assert_eq!(
+            source_from_url("https://youtu.be/abc123").as_deref(),
+            Some("youtube")
         );
+        assert_eq!(source_from_url("not-a-url"), None);
+    }
+
+    #[test]
+    fn derives_medium_from_download_job() {
+        let mut job = DownloadJob {
+            id: "job-1".to_string(),
+            url: "https://example.com/video".to_string(),
+            format: "best".to_string(),
+            output_dir: "/tmp".to_string(),
+            extract_audio: false,
+            audio_format: None,
+            transcribe_text: false,
+            title: None,
+            uploader: None,
+            thumbnail: None,
+            upload_date: None,
+            timestamp: None,
+            duration_seconds: None,
+            cut_start_time: None,
+            cut_end_time: None,
+            cut_mode: CutMode::default(),
+            cut_precision: CutPrecision::default(),
+            output_template: default_output_template(),
+            filename_suffix: None,
+            paused: false,
+            priority: 0,
+            retry_count: 0,
+            retry_at: None,
+            not_before: None,
+            rate_limit_kib: None,
+            batch_id: None,
+            playlist_title: None,
+            playlist_index: None,
+            playlist_in_output_template: false,
+            force_download: false,
+            subtitles: SubtitleOptions::default(),
+            embed: EmbedOptions::default(),
+            sponsorblock: SponsorBlockOptions::default(),
+            split_chapters: false,
+        };
+
+        assert_eq!(medium_for_job(&job), "video");
+        job.extract_audio = true;
+        assert_eq!(medium_for_job(&job), "audio");
+        job.transcribe_text = true;
+        assert_eq!(medium_for_job(&job), "transcript");
+    }
+
+    fn history_entry_fixture(id: &str) -> HistoryEntry {
+        HistoryEntry {
+            id: id.to_string(),
+            url: format!("https://example.com/video/{id}"),
+            title: None,
+            uploader: None,
+            filename: None,
+            thumbnail: None,
+            upload_date: None,
+            timestamp: None,
+            duration_seconds: None,
+            file_size_bytes: None,
+            medium: Some("video".to_string()),
+            source: None,
+            platform: None,
+            output_path: None,
+            subtitle_paths: Vec::new(),
+            removed_segments: Vec::new(),
+            parent_id: None,
+            download_archive: None,
+            created_at: 1,
+            completed_at: Some(1),
+        }
+    }
+
+    fn download_job_fixture(id: &str) -> DownloadJob {
+        DownloadJob {
+            id: id.to_string(),
+            url: format!("https://example.com/video/{id}"),
+            format: "best".to_string(),
+            output_dir: "/tmp".to_string(),
+            extract_audio: false,
+            audio_format: None,
+            transcribe_text: false,
+            title: None,
+            uploader: None,
+            thumbnail: None,
+            upload_date: None,
+            timestamp: None,
+            duration_seconds: None,
+            cut_start_time: None,
+            cut_end_time: None,
+            cut_mode: CutMode::default(),
+            cut_precision: CutPrecision::default(),
+            output_template: default_output_template(),
+            filename_suffix: None,
+            paused: false,
+            priority: 0,
+            retry_count: 0,
+            retry_at: None,
+            not_before: None,
+            rate_limit_kib: None,
+            batch_id: None,
+            playlist_title: None,
+            playlist_index: None,
+            playlist_in_output_template: false,
+            force_download: false,
+            subtitles: SubtitleOptions::default(),
+            embed: EmbedOptions::default(),
+            sponsorblock: SponsorBlockOptions::default(),
+            split_chapters: false,
+        }
+    }
+
+    #[test]
+    fn queue_jobs_are_restored_from_sqlite_with_interrupted_job_first() {
+        let state = link_dump_test_state();
+        let jobs = ["job-1", "job-2", "job-3"]
+            .into_iter()
+            .map(download_job_fixture)
+            .collect::<Vec<_>>();
+
+        insert_queue_jobs_in_db(&state, &jobs).unwrap();
+        update_queue_job_state_in_db(&state, "job-2", "running").unwrap();
+        delete_queue_job_from_db(&state, "job-3").unwrap();
+        insert_queue_jobs_in_db(&state, &[download_job_fixture("job-4")]).unwrap();
+
+        let restored = restore_queue_jobs_from_db(&state).unwrap();
+        let ids = restored
+            .iter()
+            .map(|job| job.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["job-2", "job-1", "job-4"]);
+        assert_eq!(restored[0].url, "https://example.com/video/job-2");
+
+        let conn = state.db.lock().unwrap();
+        let running_count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM queue_jobs WHERE state = 'running'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(running_count, 0);
     }
 
     #[test]
-    fn formats_yt_dlp_timestamp_without_extra_zeroes() {
-        assert_eq!(format_yt_dlp_timestamp(61.0), "61");
-        assert_eq!(format_yt_dlp_timestamp(61.5), "61.5");
+    fn paused_jobs_keep_their_place_but_are_skipped_by_workers() {
+        let mut paused = download_job_fixture("job-1");
+        paused.paused = true;
+        let queue = VecDeque::from(vec![
+            paused,
+            download_job_fixture("job-2"),
+            download_job_fixture("job-3"),
+        ]);
+
+        assert_eq!(next_runnable_job_index(&queue, 0), Some(1));
+        assert_eq!(runnable_job_count(&queue, 0), 2);
     }
 
     #[test]
-    fn appends_filename_suffix_before_extension() {
-        let template = build_output_template("/tmp/pinefetch", Some("__max"));
+    fn higher_priority_jobs_run_before_older_jobs() {
+        let mut urgent = download_job_fixture("job-3");
+        urgent.priority = 5;
+        let queue = VecDeque::from(vec![
+            download_job_fixture("job-1"),
+            download_job_fixture("job-2"),
+            urgent,
+        ]);
 
-        assert!(template.ends_with("%(title)s - %(uploader)s - %(id)s__max.%(ext)s"));
+        assert_eq!(next_runnable_job_index(&queue, 0), Some(2));
     }
 
     #[test]
-    fn parses_yt_dlp_filepath_output() {
-        assert_eq!(
-            parse_yt_dlp_filepath("/tmp/pinefetch/video.mp4").as_deref(),
-            Some("/tmp/pinefetch/video.mp4")
-        );
-        assert_eq!(
-            parse_yt_dlp_filepath("[download] Destination: /tmp/pinefetch/video.f398.mp4")
-                .as_deref(),
-            Some("/tmp/pinefetch/video.f398.mp4")
-        );
-        assert_eq!(
-            parse_yt_dlp_filepath("[Merger] Merging formats into \"/tmp/pinefetch/video.webm\"")
-                .as_deref(),
-            Some("/tmp/pinefetch/video.webm")
-        );
-        assert_eq!(parse_yt_dlp_filepath("[download] 100% of 1MiB"), None);
-        assert_eq!(parse_yt_dlp_filepath("NA"), None);
-        assert_eq!(parse_yt_dlp_filepath("https://example.com/video"), None);
+    fn jobs_waiting_for_retry_are_skipped_until_due() {
+        let mut waiting = download_job_fixture("job-1");
+        waiting.retry_at = Some(5_000);
+        let queue = VecDeque::from(vec![waiting, download_job_fixture("job-2")]);
+
+        assert_eq!(next_runnable_job_index(&queue, 4_999), Some(1));
+        assert_eq!(next_runnable_job_index(&queue, 5_000), Some(0));
     }
 
     #[test]
-    fn selects_last_existing_output_path() {
-        let dir = std::env::temp_dir().join(format!("pinefetch-test-{}", Uuid::new_v4()));
-        fs::create_dir_all(&dir).unwrap();
-        let first_path = dir.join("first.mp4");
-        let final_path = dir.join("final.mp3");
-        fs::write(&first_path, b"first").unwrap();
-        fs::write(&final_path, b"final").unwrap();
-
-        let candidates = vec![
-            first_path.to_string_lossy().to_string(),
-            dir.join("missing.webm").to_string_lossy().to_string(),
-            final_path.to_string_lossy().to_string(),
-        ];
-        let expected = final_path.to_string_lossy().to_string();
-
-        assert_eq!(
-            select_existing_output_path(&candidates).as_deref(),
-            Some(expected.as_str())
-        );
+    fn scheduled_jobs_wait_for_their_start_time() {
+        let mut scheduled = download_job_fixture("job-1");
+        scheduled.not_before = Some(10_000);
+        let queue = VecDeque::from(vec![scheduled]);
 
-        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(next_runnable_job_index(&queue, 9_999), None);
+        assert_eq!(next_runnable_job_index(&queue, 10_000), Some(0));
     }
 
     #[test]
-    fn selects_largest_format_part_when_final_output_is_missing() {
-        let dir = std::env::temp_dir().join(format!("pinefetch-test-{}", Uuid::new_v4()));
-        fs::create_dir_all(&dir).unwrap();
-        let audio_part = dir.join("Example - Uploader - id__max.f251.webm");
-        let video_part = dir.join("Example - Uploader - id__max.f398.mp4");
-        fs::write(&audio_part, b"audio").unwrap();
-        fs::write(&video_part, b"larger video part").unwrap();
-
-        let candidates = vec![
-            audio_part.to_string_lossy().to_string(),
-            video_part.to_string_lossy().to_string(),
-        ];
-        let expected = video_part.to_string_lossy().to_string();
-
-        assert_eq!(
-            select_existing_output_path(&candidates).as_deref(),
-            Some(expected.as_str())
-        );
+    fn download_window_wraps_past_midnight() {
+        let start = parse_clock_minutes("23:00").unwrap();
+        let end = parse_clock_minutes("07:00").unwrap();
 
-        let _ = fs::remove_dir_all(&dir);
+        assert!(is_within_time_window(start, end, 23 * 60));
+        assert!(is_within_time_window(start, end, 3 * 60));
+        assert!(!is_within_time_window(start, end, 7 * 60));
+        assert!(!is_within_time_window(start, end, 12 * 60));
+        assert!(is_within_time_window(9 * 60, 17 * 60, 12 * 60));
+        assert!(is_within_time_window(start, start, 12 * 60));
+        assert_eq!(parse_clock_minutes("24:00"), None);
+        assert_eq!(parse_clock_minutes("7"), None);
     }
 
     #[test]
-    fn finds_related_format_parts_from_expected_output_path() {
-        let dir = std::env::temp_dir().join(format!("pinefetch-test-{}", Uuid::new_v4()));
-        fs::create_dir_all(&dir).unwrap();
-        let expected = dir.join("Example - Uploader - id__max.webm");
-        let video_part = dir.join("Example - Uploader - id__max.f398.mp4");
-        fs::write(&video_part, b"video").unwrap();
-
-        let related = related_existing_output_paths(&expected);
+    fn shared_rate_limit_is_split_across_busy_slots() {
+        assert_eq!(split_rate_limit_kib(4000, 1, 4), 4000);
+        assert_eq!(split_rate_limit_kib(4000, 2, 4), 2000);
+        assert_eq!(split_rate_limit_kib(4000, 10, 4), 1000);
+        assert_eq!(split_rate_limit_kib(4000, 0, 4), 4000);
+        assert_eq!(split_rate_limit_kib(2, 4, 4), 1);
+    }
 
-        assert_eq!(related, vec![video_part.to_string_lossy().to_string()]);
+    #[test]
+    fn invalid_download_window_times_keep_it_open() {
+        let state = link_dump_test_state_with_config(AppConfig {
+            download_window_enabled: true,
+            download_window_start: "25:00".to_string(),
+            download_window_end: "06:00".to_string(),
+            rate_limit_kib: Some(1000),
+            download_window_rate_limit_kib: Some(50),
+            ..AppConfig::default()
+        });
 
-        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(is_within_download_window(&state), Ok(None));
+        assert!(is_download_window_open(&state));
+        assert_eq!(shared_rate_limit_share_kib(&state), Some(1000));
     }
 
     #[test]
-    fn rejects_unsafe_filename_suffix() {
+    fn rebalances_downloads_whose_shared_rate_changed() {
+        let state = link_dump_test_state();
+        let running = [
+            ("downloading", Some(500), true),
+            ("own-limit", None, true),
+            ("cutting", Some(500), false),
+            ("current", Some(1000), true),
+            ("nudged", Some(900), true),
+        ];
+        for (id, shared_rate_limit_kib, downloading) in running {
+            state.running_jobs.lock().unwrap().insert(
+                id.to_string(),
+                RunningJob {
+                    url: format!("https://example.com/video/{id}"),
+                    child: None,
+                    shared_rate_limit_kib,
+                },
+            );
+            let job_state = if downloading {
+                JobState::Downloading { started_at: 1 }
+            } else {
+                JobState::Cutting { started_at: 1 }
+            };
+            record_job_state(&state, id, job_state);
+        }
+
         assert_eq!(
-            normalize_filename_suffix(Some("__max")),
-            Some("__max".to_string())
+            rebalance_candidates(&state, Some(1000)),
+            vec!["downloading"]
         );
-        assert_eq!(normalize_filename_suffix(Some("../max")), None);
-        assert_eq!(normalize_filename_suffix(Some("")), None);
+        let mut candidates = rebalance_candidates(&state, Some(750));
+        candidates.sort();
+        assert_eq!(candidates, vec!["current", "downloading"]);
+        let mut candidates = rebalance_candidates(&state, None);
+        candidates.sort();
+        assert_eq!(candidates, vec!["current", "downloading", "nudged"]);
     }
 
     #[test]
-    fn normalizes_unknown_selected_preset_to_best() {
-        let config = normalize_app_config(AppConfig {
-            selected_preset_key: Some("missing".to_string()),
+    fn per_job_rate_limit_overrides_the_shared_cap() {
+        let state = link_dump_test_state_with_config(AppConfig {
+            rate_limit_kib: Some(4000),
+            max_concurrent_downloads: 2,
             ..AppConfig::default()
         });
-
-        assert_eq!(
-            config.selected_preset_key.as_deref(),
-            Some(DEFAULT_DOWNLOAD_PRESET_KEY)
+        let mut job = download_job_fixture("job-1");
+        state.running_jobs.lock().unwrap().insert(
+            job.id.clone(),
+            RunningJob {
+                url: job.url.clone(),
+                child: None,
+                shared_rate_limit_kib: None,
+            },
         );
+        state
+            .queue
+            .lock()
+            .unwrap()
+            .push_back(download_job_fixture("job-2"));
+
+        assert_eq!(resolve_job_rate_limit_kib(&state, &job), Some(2000));
+
+        job.rate_limit_kib = Some(300);
+        assert_eq!(resolve_job_rate_limit_kib(&state, &job), Some(300));
     }
 
     #[test]
-    fn app_config_defaults_are_loaded_from_sqlite() {
-        let conn = Connection::open_in_memory().unwrap();
-        run_link_dump_migrations(&conn).unwrap();
-
-        let config = load_config_from_db(&conn).unwrap();
+    fn classifies_yt_dlp_failures_from_stderr() {
+        let lines = |line: &str| vec![line.to_string()];
 
         assert_eq!(
-            config.selected_preset_key.as_deref(),
-            Some(DEFAULT_DOWNLOAD_PRESET_KEY)
+            classify_yt_dlp_failure(&lines(
+                "ERROR: [youtube] abc: Unable to download webpage: HTTP Error 429: Too Many Requests"
+            )),
+            YtDlpFailure::Retryable("rate limited")
+        );
+        assert_eq!(
+            classify_yt_dlp_failure(&lines(
+                "ERROR: [download] Got error: [Errno 104] Connection reset by peer"
+            )),
+            YtDlpFailure::Retryable("connection reset")
+        );
+        assert_eq!(
+            classify_yt_dlp_failure(&lines("ERROR: fragment 12 not found, unable to continue")),
+            YtDlpFailure::Retryable("fragment download failed")
+        );
+        assert_eq!(
+            classify_yt_dlp_failure(&lines(
+                "ERROR: [download] Giving up after 10 fragment retries"
+            )),
+            YtDlpFailure::Retryable("fragment download failed")
+        );
+        assert_eq!(
+            classify_yt_dlp_failure(&lines(
+                "ERROR: unable to open fragment file: [Errno 28] No space left on device"
+            )),
+            YtDlpFailure::Unknown
+        );
+        assert_eq!(
+            classify_yt_dlp_failure(&lines("ERROR: [youtube] abc: Private video. Sign in")),
+            YtDlpFailure::Permanent("video is private")
+        );
+        assert_eq!(
+            classify_yt_dlp_failure(&lines(
+                "ERROR: [youtube] abc: Video unavailable. This video has been removed"
+            )),
+            YtDlpFailure::Permanent("video is unavailable")
+        );
+        assert_eq!(
+            classify_yt_dlp_failure(&lines("ERROR: something else")),
+            YtDlpFailure::Unknown
         );
-        assert!(config.magic_import_enabled);
-        assert!(config.cut_at_timestamp_enabled);
-        assert!(config.yt_dlp_path.is_none());
-        assert!(config.default_output_dir.is_none());
-        assert!(config.last_download_url.is_none());
     }
 
     #[test]
-    fn app_config_is_stored_in_sqlite() {
-        let conn = Connection::open_in_memory().unwrap();
-        run_link_dump_migrations(&conn).unwrap();
+    fn retry_backoff_doubles_up_to_the_cap() {
         let config = AppConfig {
-            yt_dlp_path: Some("/opt/pinefetch/yt-dlp".to_string()),
-            default_output_dir: Some("/Users/example/Downloads".to_string()),
-            selected_preset_key: Some("audio_mp3".to_string()),
-            magic_import_enabled: false,
-            cut_at_timestamp_enabled: false,
-            last_download_url: Some("https://example.com/watch".to_string()),
+            retry_max_attempts: 4,
+            retry_base_delay_seconds: 10,
+            retry_max_delay_seconds: 50,
+            ..AppConfig::default()
         };
 
-        upsert_app_config_in_conn(&conn, &config).unwrap();
-        let loaded = load_config_from_db(&conn).unwrap();
+        assert_eq!(retry_backoff_seconds(&config, 1), Some(10));
+        assert_eq!(retry_backoff_seconds(&config, 2), Some(20));
+        assert_eq!(retry_backoff_seconds(&config, 3), Some(40));
+        assert_eq!(retry_backoff_seconds(&config, 4), Some(50));
+        assert_eq!(retry_backoff_seconds(&config, 5), None);
+    }
 
-        assert_eq!(loaded.yt_dlp_path.as_deref(), Some("/opt/pinefetch/yt-dlp"));
-        assert_eq!(
-            loaded.default_output_dir.as_deref(),
-            Some("/Users/example/Downloads")
-        );
-        assert_eq!(loaded.selected_preset_key.as_deref(), Some("audio_mp3"));
-        assert!(!loaded.magic_import_enabled);
-        assert!(!loaded.cut_at_timestamp_enabled);
-        assert_eq!(
-            loaded.last_download_url.as_deref(),
-            Some("https://example.com/watch")
-        );
+    #[test]
+    fn moves_queue_jobs_to_requested_index() {
+        let mut queue = VecDeque::from(vec![
+            download_job_fixture("job-1"),
+            download_job_fixture("job-2"),
+            download_job_fixture("job-3"),
+        ]);
+
+        assert!(move_job_in_queue(&mut queue, "job-3", 0));
+        assert!(move_job_in_queue(&mut queue, "job-1", usize::MAX));
+        assert!(!move_job_in_queue(&mut queue, "missing", 0));
+
+        let ids = queue.iter().map(|job| job.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["job-3", "job-2", "job-1"]);
     }
 
     #[test]
-    fn link_dump_request_uses_selected_preset() {
-        let state = link_dump_test_state_with_config(AppConfig {
-            selected_preset_key: Some("audio_mp3".to_string()),
-            ..AppConfig::default()
-        });
-        let normalized = normalize_youtube_url("https://youtu.be/abc123").unwrap();
+    fn paused_queue_jobs_are_restored_with_their_flag() {
+        let state = link_dump_test_state();
+        let mut job = download_job_fixture("job-1");
+        insert_queue_jobs_in_db(&state, &[job.clone()]).unwrap();
+        job.paused = true;
+        update_queue_job_in_db(&state, &job, "paused").unwrap();
 
-        let request = build_link_dump_download_request(&state, &normalized).unwrap();
+        let restored = restore_queue_jobs_from_db(&state).unwrap();
 
-        assert_eq!(request.url, "https://www.youtube.com/watch?v=abc123");
-        assert_eq!(request.format, "ba/b");
-        assert!(request.extract_audio);
-        assert_eq!(request.audio_format.as_deref(), Some("mp3"));
-        assert!(!request.transcribe_text);
-        assert_eq!(request.filename_suffix, None);
+        assert_eq!(restored.len(), 1);
+        assert!(restored[0].paused);
     }
 
     #[test]
-    fn appends_timestamp_suffix_to_cut_output_path() {
-        let path = build_timestamp_cut_output_path(
-            Path::new("/tmp/Title - Uploader - id_best.webm"),
-            13.0,
+    fn failed_jobs_keep_exit_code_and_stderr_until_taken() {
+        let state = link_dump_test_state();
+        let mut job = download_job_fixture("job-1");
+        job.retry_count = 2;
+        let stderr = vec!["ERROR: HTTP Error 429: Too Many Requests".to_string()];
+
+        let outcome = JobState::Failed {
+            failed_at: 1_000,
+            error: JobError::yt_dlp(1, Some("rate limited"), &stderr),
+        };
+
+        record_failed_job_in_db(&state, &job, &outcome).unwrap();
+        record_failed_job_in_db(
+            &state,
+            &download_job_fixture("job-2"),
+            &JobState::Cancelled {
+                cancelled_at: 2_000,
+            },
         )
         .unwrap();
 
-        assert_eq!(
-            path.to_string_lossy(),
-            "/tmp/Title - Uploader - id_best_t13.webm"
-        );
+        let failed = list_failed_jobs_from_db(&state, None).unwrap();
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0].id, "job-2");
+        assert_eq!(failed[1].state, outcome);
+        assert_eq!(failed[1].job.retry_count, 2);
+
+        let selected = list_failed_jobs_from_db(&state, Some("job-1")).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(list_failed_jobs_from_db(&state, None).unwrap().len(), 2);
+
+        delete_failed_jobs_from_db(&state, &["job-1".to_string()]).unwrap();
+        assert_eq!(list_failed_jobs_from_db(&state, None).unwrap().len(), 1);
+
+        delete_failed_jobs_from_db(&state, &["job-2".to_string()]).unwrap();
+        assert!(list_failed_jobs_from_db(&state, None).unwrap().is_empty());
     }
 
     #[test]
-    fn sanitizes_decimal_timestamp_suffix() {
-        assert_eq!(format_timestamp_filename_suffix(13.5), "_t13_5");
+    fn cancelling_a_queued_job_keeps_it_requeueable() {
+        let state = link_dump_test_state();
+        let mut job = download_job_fixture("job-1");
+        job.retry_count = 1;
+        job.retry_at = Some(5_000);
+        state.queue.lock().unwrap().push_back(job.clone());
+        insert_queue_jobs_in_db(&state, &[job]).unwrap();
+
+        let cancelled = cancel_queued_job(&state, "job-1").unwrap();
+        assert!(matches!(cancelled, Some(JobState::Cancelled { .. })));
+        assert!(state.queue.lock().unwrap().is_empty());
+        assert!(cancel_queued_job(&state, "job-1").unwrap().is_none());
+
+        let failed = list_failed_jobs_from_db(&state, None).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].id, "job-1");
+        assert_eq!(Some(failed[0].state.clone()), cancelled);
     }
 
     #[test]
-    fn extracts_source_from_url_without_subdomain_or_tld() {
+    fn failed_jobs_serialize_state_at_the_top_level() {
+        let failed = FailedJob {
+            id: "job-2".to_string(),
+            url: "https://example.com/v".to_string(),
+            state: JobState::Cancelled {
+                cancelled_at: 2_000,
+            },
+            job: download_job_fixture("job-2"),
+        };
+
+        let value = serde_json::to_value(&failed).unwrap();
+        assert_eq!(value["state"], "cancelled");
+        assert_eq!(value["cancelled_at"], 2_000);
+        let restored: FailedJob = serde_json::from_value(value).unwrap();
+        assert_eq!(restored.state, failed.state);
+    }
+
+    #[test]
+    fn job_state_events_use_a_stable_tagged_shape() {
+        let event = DownloadStateEvent {
+            id: "job-1".to_string(),
+            state: JobState::Failed {
+                failed_at: 42,
+                error: JobError::new(JobErrorKind::Transcription, "whisper crashed"),
+            },
+        };
+
+        let value = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(value["id"], "job-1");
+        assert_eq!(value["state"], "failed");
+        assert_eq!(value["failed_at"], 42);
+        assert_eq!(value["error"]["kind"], "transcription");
+        assert_eq!(value["error"]["message"], "whisper crashed");
         assert_eq!(
-            source_from_url("https://www.youtube.com/watch?v=abc123").as_deref(),
-            Some("youtube")
+            serde_json::to_value(JobState::FetchingMetadata { started_at: 1 }).unwrap()["state"],
+            "fetching-metadata"
         );
+    }
+
+    #[test]
+    fn detects_job_phase_from_yt_dlp_output() {
         assert_eq!(
-            source_from_url("https://media.linkedin.com/posts/123").as_deref(),
-            Some("linkedin")
+            yt_dlp_line_phase("[download]  12.5% of 10.00MiB at 1.00MiB/s ETA 00:09"),
+            Some(YtDlpPhase::Downloading)
         );
         assert_eq!(
-            source_from_url("https://video.example.co.uk/watch/123").as_deref(),
-            Some("example")
+            yt_dlp_line_phase("[Merger] Merging formats into \"clip.mp4\""),
+            Some(YtDlpPhase::Merging)
         );
         assert_eq!(
-            source_from_url("https://youtu.be/abc123").as_deref(),
-            Some("youtube")
+            yt_dlp_line_phase("[youtube] abc: Downloading webpage"),
+            None
         );
-        assert_eq!(source_from_url("not-a-url"), None);
     }
 
     #[test]
-    fn derives_medium_from_download_job() {
-        let mut job = DownloadJob {
-            id: "job-1".to_string(),
-            url: "https://example.com/video".to_string(),
-            format: "best".to_string(),
-            output_dir: "/tmp".to_string(),
-            extract_audio: false,
-            audio_format: None,
-            transcribe_text: false,
-            title: None,
-            uploader: None,
-            thumbnail: None,
-            upload_date: None,
-            timestamp: None,
-            duration_seconds: None,
-            cut_start_time: None,
-            filename_suffix: None,
-        };
+    fn job_lifecycle_keeps_every_transition() {
+        let state = link_dump_test_state();
 
-        assert_eq!(medium_for_job(&job), "video");
-        job.extract_audio = true;
-        assert_eq!(medium_for_job(&job), "audio");
-        job.transcribe_text = true;
-        assert_eq!(medium_for_job(&job), "transcript");
+        record_job_state(&state, "job-1", JobState::Queued { queued_at: 1 });
+        record_job_state(&state, "job-1", JobState::Downloading { started_at: 2 });
+        record_job_state(
+            &state,
+            "job-1",
+            JobState::Done {
+                finished_at: 3,
+                output_path: None,
+                output_paths: Vec::new(),
+            },
+        );
+
+        let lifecycles = state.job_lifecycles.lock().unwrap();
+        let lifecycle = lifecycles.get("job-1").unwrap();
+        assert_eq!(lifecycle.transitions.len(), 3);
+        assert!(matches!(
+            lifecycle.state,
+            JobState::Done { finished_at: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn job_lifecycles_evict_the_oldest_finished_jobs() {
+        let mut lifecycles = JobLifecycles::default();
+        lifecycles.record("running", JobState::Downloading { started_at: 1 });
+        for index in 0..=FINISHED_JOB_LIFECYCLES_LIMIT {
+            lifecycles.record(
+                &format!("job-{index}"),
+                JobState::Cancelled { cancelled_at: 2 },
+            );
+        }
+
+        assert!(lifecycles.get("running").is_some());
+        assert!(lifecycles.get("job-0").is_none());
+        assert!(lifecycles.get("job-1").is_some());
+        assert_eq!(lifecycles.entries.len(), FINISHED_JOB_LIFECYCLES_LIMIT + 1);
+
+        // job-1 leaves the finished list, so job-2 is evicted only on the second new job.
+        lifecycles.record("job-1", JobState::Queued { queued_at: 3 });
+        lifecycles.record("job-extra-1", JobState::Cancelled { cancelled_at: 4 });
+        assert!(lifecycles.get("job-2").is_some());
+        lifecycles.record("job-extra-2", JobState::Cancelled { cancelled_at: 5 });
+        assert!(lifecycles.get("job-1").is_some());
+        assert!(lifecycles.get("job-2").is_none());
     }
 
     #[test]
@@ -4620,7 +12298,6 @@ mod tests {
     fn history_entries_are_stored_in_sqlite_with_file_metadata() {
         let state = link_dump_test_state();
         let entry = HistoryEntry {
-            id: "history-1".to_string(),
             url: "https://www.youtube.com/watch?v=abc123".to_string(),
             title: Some("Example title".to_string()),
             uploader: Some("Example uploader".to_string()),
@@ -4634,8 +12311,10 @@ mod tests {
             source: Some("youtube".to_string()),
             platform: Some("youtube".to_string()),
             output_path: Some("/tmp/Example title - Uploader - abc123.mp4".to_string()),
+            subtitle_paths: vec!["/tmp/Example title - Uploader - abc123.en.srt".to_string()],
             created_at: 1_700_000_000_000,
             completed_at: Some(1_700_000_000_100),
+            ..history_entry_fixture("history-1")
         };
 
         insert_history_entry_in_db(&state, &entry).unwrap();
@@ -4657,6 +12336,10 @@ mod tests {
         assert_eq!(entries[0].upload_date.as_deref(), Some("20240501"));
         assert_eq!(entries[0].timestamp, Some(1_714_560_000));
         assert_eq!(entries[0].duration_seconds, Some(754));
+        assert_eq!(
+            entries[0].subtitle_paths,
+            vec!["/tmp/Example title - Uploader - abc123.en.srt".to_string()]
+        );
         assert_eq!(entries[0].file_size_bytes, Some(42_000_000));
         assert_eq!(entries[0].medium.as_deref(), Some("video"));
         assert_eq!(entries[0].source.as_deref(), Some("youtube"));
@@ -4674,22 +12357,13 @@ mod tests {
         for index in 0..55 {
             let timestamp = 1_700_000_000_000 + index;
             let entry = HistoryEntry {
-                id: format!("history-{index:02}"),
-                url: format!("https://example.com/video/{index}"),
                 title: Some(format!("Example {index}")),
-                uploader: None,
                 filename: Some(format!("example-{index}.mp4")),
-                thumbnail: None,
-                upload_date: None,
-                timestamp: None,
-                duration_seconds: None,
-                file_size_bytes: None,
-                medium: Some("video".to_string()),
                 source: Some("example".to_string()),
                 platform: Some("example".to_string()),
-                output_path: None,
                 created_at: timestamp,
                 completed_at: Some(timestamp),
+                ..history_entry_fixture(&format!("history-{index:02}"))
             };
             insert_history_entry_in_db(&state, &entry).unwrap();
         }
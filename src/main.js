@@ -7,6 +7,8 @@ const state = Object.seal({
     queueIds: [],
     queueAutoStartEnabled: true,
     queueWorkerRunning: false,
+    queuePaused: false,
+    queueWindowOpen: true,
     suppressedJobIds: new Set(),
     selectedId: null,
     contextMenuJobId: null,
@@ -16,6 +18,7 @@ const state = Object.seal({
     config: null,
     linkDump: null,
     generatedLinkDumpSecret: null,
+    editingPreset: null,
     activeView: 'download',
     historyOffset: 0,
     historyHasMore: false,
@@ -25,16 +28,33 @@ const els = Object.seal({
     magicImportTrigger: document.getElementById('magicImportTrigger'),
     magicImportEnabled: document.getElementById('magicImportEnabled'),
     cutAtTimestampEnabled: document.getElementById('cutAtTimestampEnabled'),
+    cutPrecisionAccurate: document.getElementById('cutPrecisionAccurate'),
+    outputTemplateInput: document.getElementById('outputTemplateInput'),
+    resumeQueueOnStartup: document.getElementById('resumeQueueOnStartup'),
     urlInput: document.getElementById('urlInput'),
     loadInfoBtn: document.getElementById('loadInfoBtn'),
     startDownloadBtn: document.getElementById('startDownloadBtn'),
     importTxtBtn: document.getElementById('importTxtBtn'),
+    queuePlaylistBtn: document.getElementById('queuePlaylistBtn'),
+    playlistRangeInput: document.getElementById('playlistRangeInput'),
+    clipRangesInput: document.getElementById('clipRangesInput'),
+    playlistInFilename: document.getElementById('playlistInFilename'),
+    forceDownload: document.getElementById('forceDownload'),
+    splitChapters: document.getElementById('splitChapters'),
     txtImportStatus: document.getElementById('txtImportStatus'),
     pickDirBtn: document.getElementById('pickDirBtn'),
     saveSettingsBtn: document.getElementById('saveSettingsBtn'),
     openFolderBtn: document.getElementById('openFolderBtn'),
     outputDir: document.getElementById('outputDir'),
     ytDlpPath: document.getElementById('ytDlpPath'),
+    maxConcurrentDownloads: document.getElementById('maxConcurrentDownloads'),
+    retryMaxAttempts: document.getElementById('retryMaxAttempts'),
+    downloadWindowEnabled: document.getElementById('downloadWindowEnabled'),
+    downloadWindowStart: document.getElementById('downloadWindowStart'),
+    downloadWindowEnd: document.getElementById('downloadWindowEnd'),
+    rateLimitKib: document.getElementById('rateLimitKib'),
+    downloadWindowRateLimitKib: document.getElementById('downloadWindowRateLimitKib'),
+    scheduleAtInput: document.getElementById('scheduleAtInput'),
     ytDlpInstalledVersion: document.getElementById('ytDlpInstalledVersion'),
     ytDlpLatestVersion: document.getElementById('ytDlpLatestVersion'),
     linkDumpServerStatusBadge: document.getElementById('linkDumpServerStatusBadge'),
@@ -53,14 +73,49 @@ const els = Object.seal({
     linkDumpSecretList: document.getElementById('linkDumpSecretList'),
     linkDumpSecretHint: document.getElementById('linkDumpSecretHint'),
     linkDumpSecretStatus: document.getElementById('linkDumpSecretStatus'),
+    presetKeyInput: document.getElementById('presetKeyInput'),
+    presetLabelInput: document.getElementById('presetLabelInput'),
+    presetFormatInput: document.getElementById('presetFormatInput'),
+    presetAudioFormatInput: document.getElementById('presetAudioFormatInput'),
+    presetFilenameSuffixInput: document.getElementById('presetFilenameSuffixInput'),
+    presetOutputTemplateInput: document.getElementById('presetOutputTemplateInput'),
+    presetTranscribeTextInput: document.getElementById('presetTranscribeTextInput'),
+    savePresetBtn: document.getElementById('savePresetBtn'),
+    resetPresetFormBtn: document.getElementById('resetPresetFormBtn'),
+    presetConflictPolicy: document.getElementById('presetConflictPolicy'),
+    importPresetsBtn: document.getElementById('importPresetsBtn'),
+    exportPresetsBtn: document.getElementById('exportPresetsBtn'),
+    presetList: document.getElementById('presetList'),
+    presetStatus: document.getElementById('presetStatus'),
+    subscriptionUrl: document.getElementById('subscriptionUrl'),
+    addSubscriptionBtn: document.getElementById('addSubscriptionBtn'),
+    subscriptionList: document.getElementById('subscriptionList'),
+    subscriptionHint: document.getElementById('subscriptionHint'),
+    subscriptionStatus: document.getElementById('subscriptionStatus'),
+    cookieProfileName: document.getElementById('cookieProfileName'),
+    cookieProfilePath: document.getElementById('cookieProfilePath'),
+    cookieProfileHosts: document.getElementById('cookieProfileHosts'),
+    pickCookiesFileBtn: document.getElementById('pickCookiesFileBtn'),
+    saveCookieProfileBtn: document.getElementById('saveCookieProfileBtn'),
+    cookieProfileList: document.getElementById('cookieProfileList'),
+    cookieProfileHint: document.getElementById('cookieProfileHint'),
+    cookieProfileStatus: document.getElementById('cookieProfileStatus'),
     presetSelect: document.getElementById('presetSelect'),
     infoTitle: document.getElementById('infoTitle'),
     infoUploader: document.getElementById('infoUploader'),
     infoDuration: document.getElementById('infoDuration'),
+    infoSubtitles: document.getElementById('infoSubtitles'),
+    infoChapters: document.getElementById('infoChapters'),
+    infoFilename: document.getElementById('infoFilename'),
     infoThumb: document.getElementById('infoThumb'),
+    formatPicker: document.getElementById('formatPicker'),
+    videoFormatSelect: document.getElementById('videoFormatSelect'),
+    audioFormatSelect: document.getElementById('audioFormatSelect'),
     queueList: document.getElementById('queueList'),
     queueBadge: document.getElementById('queueBadge'),
     queueAutoStartBtn: document.getElementById('queueAutoStartBtn'),
+    pauseQueueBtn: document.getElementById('pauseQueueBtn'),
+    requeueFailedBtn: document.getElementById('requeueFailedBtn'),
     startQueueBtn: document.getElementById('startQueueBtn'),
     queueModeHint: document.getElementById('queueModeHint'),
     clearQueueBtn: document.getElementById('clearQueueBtn'),
@@ -92,69 +147,43 @@ const els = Object.seal({
     clearHistoryBtn: document.getElementById('clearHistoryBtn'),
     queueContextMenu: document.getElementById('queueContextMenu'),
     queueContextDownloads: document.getElementById('queueContextDownloads'),
+    queueContextMoveTopBtn: document.getElementById('queueContextMoveTopBtn'),
+    queueContextMoveBottomBtn: document.getElementById('queueContextMoveBottomBtn'),
+    queueContextPriorityBtn: document.getElementById('queueContextPriorityBtn'),
+    queueContextPauseBtn: document.getElementById('queueContextPauseBtn'),
+    queueContextResumeBtn: document.getElementById('queueContextResumeBtn'),
     queueContextCancelBtn: document.getElementById('queueContextCancelBtn'),
+    queueContextRequeueBtn: document.getElementById('queueContextRequeueBtn'),
     queueContextRemoveBtn: document.getElementById('queueContextRemoveBtn'),
 });
 const ytDlpLatestReleaseUrl = 'https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest';
 const linkDumpExtensionRepoUrl = 'https://github.com/oliverjessner/PineFetch-Link-Dump';
-const presetOptions = Object.freeze([
-    {
-        key: 'best',
-        selectLabel: 'Best (bestvideo+bestaudio)',
-        queueLabel: 'Best',
-        menuLabel: 'Download Best',
-        format: 'bestvideo+bestaudio/best',
-        extractAudio: false,
-        audioFormat: null,
-        transcribeText: false,
-        filenameSuffix: '_best',
-    },
-    {
-        key: '1080',
-        selectLabel: 'Max 1080p',
-        queueLabel: 'Max 1080p',
-        menuLabel: 'Download Max 1080p',
-        format: 'bv*[height<=1080]+ba/b[height<=1080]',
-        extractAudio: false,
-        audioFormat: null,
-        transcribeText: false,
-        filenameSuffix: '__max',
-    },
-    {
-        key: 'audio_mp3',
-        selectLabel: 'Audio only (mp3)',
-        queueLabel: 'Audio only (mp3)',
-        menuLabel: 'Download Audio only (mp3)',
-        format: 'ba/b',
-        extractAudio: true,
-        audioFormat: 'mp3',
-        transcribeText: false,
-        filenameSuffix: null,
-    },
-    {
-        key: 'audio_opus',
-        selectLabel: 'Audio only (opus)',
-        queueLabel: 'Audio only (opus)',
-        menuLabel: 'Download Audio only (opus)',
-        format: 'ba/b',
-        extractAudio: true,
-        audioFormat: 'opus',
-        transcribeText: false,
-        filenameSuffix: null,
-    },
-    {
-        key: 'text',
-        selectLabel: 'Text (fast-whisper)',
-        queueLabel: 'Text (fast-whisper)',
-        menuLabel: 'Download Text (fast-whisper)',
-        format: 'ba/b',
-        extractAudio: true,
-        audioFormat: 'mp3',
-        transcribeText: true,
-        filenameSuffix: null,
-    },
-]);
-const presets = Object.freeze(Object.fromEntries(presetOptions.map(preset => [preset.key, preset])));
+// Presets come from `list_presets`; built-ins only get shorter labels for the
+// queue and the context menu here.
+const builtinPresetLabels = Object.freeze({
+    best: { queueLabel: 'Best', menuLabel: 'Download Best' },
+    best_subs: { queueLabel: 'Best + subtitles', menuLabel: 'Download Best + subtitles' },
+    best_sponsorblock: { queueLabel: 'Best (SponsorBlock)' },
+});
+const indexPresetOptions = options => Object.freeze(Object.fromEntries(options.map(preset => [preset.key, preset])));
+let presetOptions = Object.freeze([]);
+let presets = indexPresetOptions(presetOptions);
+const toPresetOption = preset => ({
+    key: preset.key,
+    selectLabel: preset.label,
+    queueLabel: preset.label,
+    menuLabel: `Download ${preset.label}`,
+    format: preset.format,
+    extractAudio: Boolean(preset.extract_audio),
+    audioFormat: preset.audio_format ?? null,
+    transcribeText: Boolean(preset.transcribe_text),
+    filenameSuffix: preset.filename_suffix ?? null,
+    outputTemplate: preset.output_template ?? null,
+    subtitles: preset.subtitles?.write_subs || preset.subtitles?.write_auto_subs ? preset.subtitles : null,
+    embed: preset.embed ?? null,
+    sponsorblock: preset.sponsorblock?.mode && preset.sponsorblock.mode !== 'off' ? preset.sponsorblock : null,
+    ...(preset.builtin ? builtinPresetLabels[preset.key] : null),
+});
 const normalizePresetKey = key => (presets[key] ? key : presetOptions[0]?.key || 'best');
 const getSelectedPresetKey = () => normalizePresetKey(els.presetSelect.value);
 const findPresetForDownloadJob = job =>
@@ -164,13 +193,27 @@ const findPresetForDownloadJob = job =>
             Boolean(job?.extract_audio) === preset.extractAudio &&
             (job?.audio_format ?? null) === (preset.audioFormat ?? null) &&
             Boolean(job?.transcribe_text) === preset.transcribeText &&
-            (job?.filename_suffix ?? null) === (preset.filenameSuffix ?? null)
+            (job?.filename_suffix ?? null) === (preset.filenameSuffix ?? null) &&
+            Boolean(job?.subtitles?.write_subs) === Boolean(preset.subtitles?.write_subs) &&
+            (job?.sponsorblock?.mode ?? 'off') === (preset.sponsorblock?.mode ?? 'off')
     ) || null;
 const defaultYtDlpPath = '/opt/homebrew/bin/yt-dlp';
 const historyPageSize = 50;
-const cancellableJobStates = new Set(['downloading', 'transcribing']);
-const queueBusyJobStates = new Set(['downloading', 'transcribing', 'cancelling']);
-const removableJobStates = new Set(['queued', 'success', 'error', 'cancelled']);
+const activeJobStates = ['fetching-metadata', 'downloading', 'merging', 'cutting', 'transcribing'];
+const cancellableJobStates = new Set(activeJobStates);
+const queueBusyJobStates = new Set([...activeJobStates, 'cancelling', 'pausing']);
+const removableJobStates = new Set([
+    'queued',
+    'scheduled',
+    'paused',
+    'retrying',
+    'done',
+    'skipped',
+    'failed',
+    'cancelled',
+]);
+const pausableJobStates = new Set(['queued', 'scheduled', 'retrying', 'fetching-metadata', 'downloading']);
+const requeueableJobStates = new Set(['failed', 'cancelled']);
 let urlShakeTimer = null;
 let magicImportInFlight = false;
 
@@ -196,6 +239,19 @@ const formatFileSize = bytes => {
     return `${value.toFixed(precision)} ${units[unitIndex]}`;
 };
 
+const formatProgressDetail = progress => {
+    const parts = [];
+    if (progress.stream) parts.push(progress.stream);
+    const total = progress.total_bytes ?? progress.total_bytes_estimate;
+    if (progress.downloaded_bytes != null && total) {
+        const prefix = progress.total_bytes == null ? '~' : '';
+        parts.push(`${formatFileSize(progress.downloaded_bytes)} / ${prefix}${formatFileSize(total)}`);
+    } else if (progress.fragment_index != null && progress.fragment_count) {
+        parts.push(`frag ${progress.fragment_index}/${progress.fragment_count}`);
+    }
+    return parts.join(' · ') || null;
+};
+
 const formatCutStartLabel = seconds => {
     if (!Number.isFinite(Number(seconds)) || Number(seconds) <= 0) return null;
     return `from ${formatDuration(Number(seconds))}`;
@@ -239,12 +295,15 @@ const parseTimestampValue = raw => {
     return normalizePositiveTimestamp(total);
 };
 
+// Media fragments may carry a range such as `t=30,90` or `t=npt:30,90`.
+const timeRangeStart = value => `${value || ''}`.replace(/^npt:/, '').split(',')[0];
+
 const extractUrlStartTimestamp = url => {
     try {
         const parsed = new URL(url);
         for (const [name, value] of parsed.searchParams.entries()) {
             if (!timestampParamNames.has(name)) continue;
-            const seconds = parseTimestampValue(value);
+            const seconds = parseTimestampValue(timeRangeStart(value));
             if (seconds) return seconds;
         }
 
@@ -253,7 +312,7 @@ const extractUrlStartTimestamp = url => {
             const fragmentParams = new URLSearchParams(fragment);
             for (const [name, value] of fragmentParams.entries()) {
                 if (!timestampParamNames.has(name)) continue;
-                const seconds = parseTimestampValue(value);
+                const seconds = parseTimestampValue(timeRangeStart(value));
                 if (seconds) return seconds;
             }
             return parseTimestampValue(fragment);
@@ -760,10 +819,18 @@ const syncQueueContextMenuState = () => {
     const showCancel = Boolean(job && (canCancel || isCancelling));
     const canRemove = Boolean(job && removableJobStates.has(job.state));
 
+    const isQueued = Boolean(job && state.queueIds.includes(job.id));
+    els.queueContextMoveTopBtn.hidden = !isQueued;
+    els.queueContextMoveBottomBtn.hidden = !isQueued;
+    els.queueContextPriorityBtn.hidden = !isQueued;
+    els.queueContextPriorityBtn.textContent = job?.priority > 0 ? 'Clear priority' : 'Download next';
+    els.queueContextPauseBtn.hidden = !(job && pausableJobStates.has(job.state));
+    els.queueContextResumeBtn.hidden = job?.state !== 'paused';
     els.queueContextCancelBtn.hidden = !showCancel;
     els.queueContextCancelBtn.disabled = !canCancel;
     els.queueContextCancelBtn.textContent = isCancelling ? 'Cancelling...' : 'Cancel download';
     els.queueContextRemoveBtn.hidden = !canRemove;
+    els.queueContextRequeueBtn.hidden = !(job && requeueableJobStates.has(job.state));
 };
 
 const openQueueContextMenu = (job, x, y) => {
@@ -784,15 +851,99 @@ const openQueueContextMenu = (job, x, y) => {
     });
 };
 
+const describeFormat = format => {
+    const parts = [format.format_id];
+    if (format.vcodec && format.vcodec !== 'none') {
+        parts.push(format.height ? `${format.height}p${format.fps ? Math.round(format.fps) : ''}` : 'video');
+        if (format.dynamic_range && format.dynamic_range !== 'SDR') parts.push(format.dynamic_range);
+        parts.push(format.vcodec.split('.')[0]);
+    } else if (format.acodec && format.acodec !== 'none') {
+        parts.push(format.acodec.split('.')[0]);
+        if (format.language) parts.push(format.language);
+    }
+    if (format.ext) parts.push(format.ext);
+    const bitrate = format.vbr || format.abr || format.tbr;
+    if (bitrate) parts.push(`${Math.round(bitrate)}k`);
+    if (format.filesize) {
+        parts.push(formatFileSize(format.filesize));
+    } else if (format.filesize_approx) {
+        parts.push(`~${formatFileSize(format.filesize_approx)}`);
+    }
+    if (format.protocol && !format.protocol.startsWith('http')) parts.push(format.protocol);
+    if (format.format_note) parts.push(`(${format.format_note})`);
+    return parts.join(' · ');
+};
+
+const fillFormatSelect = (select, formats, emptyLabel) => {
+    select.replaceChildren();
+    const fallback = document.createElement('option');
+    fallback.value = '';
+    fallback.textContent = emptyLabel;
+    select.append(fallback);
+    for (const format of formats) {
+        const option = document.createElement('option');
+        option.value = format.format_id;
+        option.textContent = describeFormat(format);
+        select.append(option);
+    }
+};
+
+const renderFormatPicker = () => {
+    const formats = (state.info?.formats || []).filter(format => format.format_id);
+    const isVideo = format => format.vcodec && format.vcodec !== 'none';
+    const isAudio = format => format.acodec && format.acodec !== 'none';
+    // Muxed formats carry audio too, so they are listed as video streams only.
+    const videoFormats = formats.filter(isVideo).reverse();
+    const audioFormats = formats.filter(format => isAudio(format) && !isVideo(format)).reverse();
+    fillFormatSelect(els.videoFormatSelect, videoFormats, 'Preset format');
+    fillFormatSelect(els.audioFormatSelect, audioFormats, 'Preset format');
+    els.formatPicker.hidden = videoFormats.length === 0 && audioFormats.length === 0;
+};
+
+let filenamePreviewRequestId = 0;
+const refreshFilenamePreview = async () => {
+    const requestId = ++filenamePreviewRequestId;
+    if (!invoke || !state.info) {
+        els.infoFilename.textContent = '-';
+        els.infoFilename.title = '';
+        return;
+    }
+    try {
+        const preview = await invoke('preview_output_filename', {
+            request: {
+                info: state.info,
+                preset_key: getSelectedPresetKey(),
+                video_format_id: els.videoFormatSelect.value || null,
+                audio_format_id: els.audioFormatSelect.value || null,
+                output_dir: els.outputDir.value.trim() || null,
+            },
+        });
+        if (requestId !== filenamePreviewRequestId) return;
+        els.infoFilename.textContent = preview.path;
+        els.infoFilename.title = preview.path;
+    } catch (err) {
+        if (requestId !== filenamePreviewRequestId) return;
+        els.infoFilename.textContent = '-';
+        els.infoFilename.title = `${err}`;
+    }
+};
+
 const renderInfo = () => {
+    renderFormatPicker();
+    void refreshFilenamePreview();
     if (!state.info) {
         els.infoTitle.textContent = '-';
         els.infoUploader.textContent = '-';
         els.infoDuration.textContent = '-';
+        els.infoSubtitles.textContent = '-';
+        els.infoChapters.textContent = '-';
         els.infoThumb.style.backgroundImage = '';
         return;
     }
     const { title, uploader, duration, thumbnail, description } = state.info;
+    const subtitleLanguages = state.info.subtitle_languages || [];
+    const automaticCaptionLanguages = state.info.automatic_caption_languages || [];
+    const chapters = state.info.chapters || [];
 
     // For Instagram, use description if title is missing or generic; otherwise use title
     // For YouTube and others, always use title
@@ -809,6 +960,22 @@ const renderInfo = () => {
     els.infoTitle.textContent = displayTitle;
     els.infoUploader.textContent = uploader || '-';
     els.infoDuration.textContent = formatDuration(duration);
+    els.infoSubtitles.textContent =
+        subtitleLanguages.length > 0
+            ? subtitleLanguages.join(', ')
+            : automaticCaptionLanguages.length > 0
+              ? `Auto-generated only (${automaticCaptionLanguages.length} languages)`
+              : '-';
+    els.infoChapters.textContent =
+        chapters.length > 0
+            ? chapters
+                  .map(
+                      (chapter, index) =>
+                          `${index + 1}. ${chapter.title || 'Untitled'} (${formatDuration(chapter.start_time)})`
+                  )
+                  .join(' · ')
+            : '-';
+    els.infoChapters.title = els.infoChapters.textContent;
     if (thumbnail) {
         els.infoThumb.style.backgroundImage = `url('${thumbnail}')`;
     } else {
@@ -830,6 +997,18 @@ const renderQueueControls = () => {
     els.queueAutoStartBtn.setAttribute('aria-pressed', String(state.queueAutoStartEnabled));
 
     els.startQueueBtn.disabled = state.queueAutoStartEnabled || queuedCount === 0 || isBusy;
+    els.pauseQueueBtn.textContent = state.queuePaused ? 'Resume queue' : 'Pause queue';
+    els.pauseQueueBtn.setAttribute('aria-pressed', String(state.queuePaused));
+
+    if (state.queuePaused) {
+        setQueueModeHint('Queue is paused. Running downloads were stopped and keep their partial files.');
+        return;
+    }
+
+    if (!state.queueWindowOpen) {
+        setQueueModeHint('Outside the download window. Queued items start when it opens.');
+        return;
+    }
 
     if (state.queueAutoStartEnabled) {
         setQueueModeHint('New items start downloading as soon as they are queued.');
@@ -850,7 +1029,15 @@ const renderQueueControls = () => {
 };
 
 const renderQueue = () => {
-    const items = Array.from(state.jobs.values()).sort((a, b) => a.createdAt - b.createdAt);
+    const queuePosition = job => state.queueIds.indexOf(job.id);
+    const items = Array.from(state.jobs.values()).sort((a, b) => {
+        const aPosition = queuePosition(a);
+        const bPosition = queuePosition(b);
+        if (aPosition !== -1 && bPosition !== -1) return aPosition - bPosition;
+        if (aPosition !== -1) return 1;
+        if (bPosition !== -1) return -1;
+        return a.createdAt - b.createdAt;
+    });
     els.queueList.replaceChildren();
     items.forEach(job => {
         const item = document.createElement('div');
@@ -865,7 +1052,7 @@ const renderQueue = () => {
             hideQueueContextMenu();
             state.selectedId = job.id;
             renderQueue();
-            if (job.state === 'success' && job.outputPath && invoke) {
+            if (job.state === 'done' && job.outputPath && invoke) {
                 try {
                     await invoke('open_folder', { path: job.outputPath });
                 } catch (err) {
@@ -908,7 +1095,7 @@ const renderQueue = () => {
 
         const meta = document.createElement('div');
         meta.className = 'pf-queue-meta';
-        const metaItems = [job.speed || '-', job.eta || '-', job.formatLabel || ''];
+        const metaItems = [job.speed || '-', job.eta || '-', job.progressDetail || '', job.formatLabel || ''];
         const cutStartLabel = formatCutStartLabel(job.cutStartTime);
         if (cutStartLabel) metaItems.push(cutStartLabel);
         appendTextSpans(meta, metaItems);
@@ -1195,9 +1382,21 @@ const syncConfig = async () => {
         state.config = await invoke('get_config');
         els.outputDir.value = state.config.default_output_dir || '';
         els.ytDlpPath.value = state.config.yt_dlp_path || defaultYtDlpPath;
+        els.maxConcurrentDownloads.value = String(state.config.max_concurrent_downloads ?? 1);
+        els.retryMaxAttempts.value = String(state.config.retry_max_attempts ?? 3);
+        els.downloadWindowEnabled.checked = state.config.download_window_enabled ?? false;
+        els.downloadWindowStart.value = state.config.download_window_start || '23:00';
+        els.downloadWindowEnd.value = state.config.download_window_end || '07:00';
+        els.rateLimitKib.value = state.config.rate_limit_kib ? String(state.config.rate_limit_kib) : '';
+        els.downloadWindowRateLimitKib.value = state.config.download_window_rate_limit_kib
+            ? String(state.config.download_window_rate_limit_kib)
+            : '';
         els.presetSelect.value = normalizePresetKey(state.config.selected_preset_key);
         els.magicImportEnabled.checked = state.config.magic_import_enabled ?? true;
         els.cutAtTimestampEnabled.checked = state.config.cut_at_timestamp_enabled ?? true;
+        els.cutPrecisionAccurate.checked = state.config.cut_precision === 'accurate';
+        els.outputTemplateInput.value = state.config.output_template || '';
+        els.resumeQueueOnStartup.checked = state.config.resume_queue_on_startup ?? true;
         syncMagicImportTriggerState();
         void refreshYtDlpVersions();
     } catch (err) {
@@ -1227,6 +1426,8 @@ const syncQueueStatus = async () => {
         const status = await invoke('get_queue_status');
         state.queueAutoStartEnabled = status?.auto_start ?? true;
         state.queueWorkerRunning = Boolean(status?.worker_running);
+        state.queuePaused = Boolean(status?.paused);
+        state.queueWindowOpen = status?.window_open ?? true;
         renderQueueControls();
     } catch (err) {
         appendLog(`[queue] ${err}`, true);
@@ -1487,6 +1688,466 @@ const deleteLinkDumpSecret = async id => {
     }
 };
 
+const setPresetStatus = (message, isError = false) => {
+    if (!els.presetStatus) return;
+    els.presetStatus.textContent = message || '';
+    els.presetStatus.classList.toggle('pf-status-error', Boolean(message && isError));
+    els.presetStatus.classList.toggle('pf-status-success', Boolean(message && !isError));
+};
+
+const applyPresetList = list => {
+    presetOptions = Object.freeze((Array.isArray(list) ? list : []).map(toPresetOption));
+    presets = indexPresetOptions(presetOptions);
+    renderPresetOptions();
+    renderQueueContextMenu();
+    renderPresetList(list);
+};
+
+const resetPresetForm = () => {
+    state.editingPreset = null;
+    els.presetKeyInput.value = '';
+    els.presetKeyInput.disabled = false;
+    els.presetLabelInput.value = '';
+    els.presetFormatInput.value = '';
+    els.presetAudioFormatInput.value = '';
+    els.presetFilenameSuffixInput.value = '';
+    els.presetOutputTemplateInput.value = '';
+    els.presetTranscribeTextInput.checked = false;
+    els.savePresetBtn.textContent = 'Save preset';
+};
+
+const editPreset = preset => {
+    state.editingPreset = preset;
+    els.presetKeyInput.value = preset.key;
+    els.presetKeyInput.disabled = true;
+    els.presetLabelInput.value = preset.label || '';
+    els.presetFormatInput.value = preset.format || '';
+    els.presetAudioFormatInput.value = preset.extract_audio ? preset.audio_format || 'best' : '';
+    els.presetFilenameSuffixInput.value = preset.filename_suffix || '';
+    els.presetOutputTemplateInput.value = preset.output_template || '';
+    els.presetTranscribeTextInput.checked = Boolean(preset.transcribe_text);
+    els.savePresetBtn.textContent = 'Update preset';
+    setPresetStatus('');
+};
+
+const renderPresetList = list => {
+    if (!els.presetList) return;
+    els.presetList.replaceChildren();
+    const items = Array.isArray(list) ? list : [];
+
+    items.forEach(preset => {
+        const item = document.createElement('div');
+        item.className = 'pf-link-dump-secret-item';
+
+        const content = document.createElement('div');
+        content.className = 'pf-link-dump-secret-content';
+
+        const title = document.createElement('div');
+        title.className = 'pf-link-dump-secret-title';
+        title.textContent = preset.label || preset.key;
+
+        const meta = document.createElement('div');
+        meta.className = 'pf-link-dump-secret-meta';
+        appendTextSpans(meta, [
+            preset.key,
+            preset.format,
+            preset.extract_audio ? `Audio ${preset.audio_format || 'best'}` : null,
+            preset.transcribe_text ? 'Transcript' : null,
+        ]);
+
+        content.append(title, meta);
+
+        const actions = document.createElement('div');
+        actions.className = 'pf-row pf-link-dump-secret-actions';
+
+        if (preset.builtin) {
+            const badge = document.createElement('span');
+            badge.className = 'pf-badge pf-badge-muted';
+            badge.textContent = 'Built-in';
+            actions.appendChild(badge);
+        } else {
+            const editBtn = document.createElement('button');
+            editBtn.className = 'pf-btn pf-btn-ghost';
+            editBtn.type = 'button';
+            editBtn.textContent = 'Edit';
+            editBtn.onclick = () => editPreset(preset);
+            actions.appendChild(editBtn);
+        }
+
+        const cloneBtn = document.createElement('button');
+        cloneBtn.className = 'pf-btn pf-btn-ghost';
+        cloneBtn.type = 'button';
+        cloneBtn.textContent = 'Clone';
+        cloneBtn.onclick = () => {
+            void clonePreset(preset);
+        };
+        actions.appendChild(cloneBtn);
+
+        if (!preset.builtin) {
+            const removeBtn = document.createElement('button');
+            removeBtn.className = 'pf-btn pf-btn-danger';
+            removeBtn.type = 'button';
+            removeBtn.textContent = 'Delete';
+            removeBtn.onclick = () => {
+                if (!window.confirm(`Delete the preset "${preset.label || preset.key}"? Queued items keep it.`)) {
+                    return;
+                }
+                void deletePreset(preset.key);
+            };
+            actions.appendChild(removeBtn);
+        }
+
+        item.append(content, actions);
+        els.presetList.appendChild(item);
+    });
+};
+
+const syncPresets = async () => {
+    if (!invoke) return;
+    try {
+        applyPresetList(await invoke('list_presets'));
+    } catch (err) {
+        appendLog(`[presets] ${err}`, true);
+    }
+};
+
+const savePreset = async () => {
+    if (!invoke) return;
+    const audioFormat = els.presetAudioFormatInput.value;
+    const preset = {
+        ...(state.editingPreset || {}),
+        key: els.presetKeyInput.value.trim(),
+        label: els.presetLabelInput.value.trim(),
+        format: els.presetFormatInput.value.trim(),
+        extract_audio: Boolean(audioFormat),
+        audio_format: audioFormat || null,
+        transcribe_text: Boolean(els.presetTranscribeTextInput.checked),
+        filename_suffix: els.presetFilenameSuffixInput.value.trim() || null,
+        output_template: els.presetOutputTemplateInput.value.trim() || null,
+    };
+    try {
+        const saved = await invoke(state.editingPreset ? 'update_preset' : 'create_preset', { preset });
+        resetPresetForm();
+        setPresetStatus(`Saved ${saved.label}.`);
+    } catch (err) {
+        setPresetStatus(`${err}`, true);
+        appendLog(`[presets] ${err}`, true);
+    }
+};
+
+const clonePreset = async preset => {
+    if (!invoke) return;
+    const existingKeys = new Set(presetOptions.map(option => option.key));
+    let newKey = `${preset.key}-copy`;
+    for (let index = 2; existingKeys.has(newKey); index += 1) {
+        newKey = `${preset.key}-copy-${index}`;
+    }
+    try {
+        const cloned = await invoke('clone_preset', { key: preset.key, newKey, label: null });
+        editPreset(cloned);
+        setPresetStatus(`Cloned ${preset.label || preset.key}. Adjust it and save.`);
+    } catch (err) {
+        setPresetStatus(`${err}`, true);
+        appendLog(`[presets] ${err}`, true);
+    }
+};
+
+const deletePreset = async key => {
+    if (!invoke) return;
+    try {
+        state.config = await invoke('delete_preset', { key });
+        if (state.editingPreset?.key === key) resetPresetForm();
+        els.presetSelect.value = normalizePresetKey(state.config.selected_preset_key);
+        setPresetStatus('Preset deleted.');
+    } catch (err) {
+        setPresetStatus(`${err}`, true);
+        appendLog(`[presets] ${err}`, true);
+    }
+};
+
+const exportPresets = async () => {
+    if (!invoke) return;
+    try {
+        const path = await invoke('pick_preset_export_path');
+        if (!path) return;
+        const result = await invoke('export_presets', { path, keys: null });
+        setPresetStatus(`Exported ${pluralize(result.count, 'preset')}.`);
+    } catch (err) {
+        setPresetStatus(`${err}`, true);
+        appendLog(`[presets] ${err}`, true);
+    }
+};
+
+const importPresets = async () => {
+    if (!invoke) return;
+    try {
+        const path = await invoke('pick_preset_bundle_file');
+        if (!path) return;
+        const report = await invoke('import_presets', {
+            path,
+            conflict: els.presetConflictPolicy.value || 'skip',
+        });
+        report.rejected.forEach(rejected => {
+            appendLog(`[presets] ${rejected.key} rejected: ${rejected.reason}`, true);
+        });
+        const message = `Added ${report.added.length}, updated ${report.updated.length}, rejected ${report.rejected.length}.`;
+        setPresetStatus(message, report.rejected.length > 0 && report.added.length + report.updated.length === 0);
+    } catch (err) {
+        setPresetStatus(`${err}`, true);
+        appendLog(`[presets] ${err}`, true);
+    }
+};
+
+const setSubscriptionStatus = (message, isError = false) => {
+    if (!els.subscriptionStatus) return;
+    els.subscriptionStatus.textContent = message || '';
+    els.subscriptionStatus.classList.toggle('pf-status-error', Boolean(message && isError));
+    els.subscriptionStatus.classList.toggle('pf-status-success', Boolean(message && !isError));
+};
+
+const renderSubscriptions = subscriptions => {
+    if (!els.subscriptionList) return;
+    els.subscriptionList.replaceChildren();
+    const items = Array.isArray(subscriptions) ? subscriptions : [];
+    els.subscriptionHint.hidden = items.length > 0;
+
+    items.forEach(subscription => {
+        const item = document.createElement('div');
+        item.className = 'pf-link-dump-secret-item';
+
+        const content = document.createElement('div');
+        content.className = 'pf-link-dump-secret-content';
+
+        const title = document.createElement('div');
+        title.className = 'pf-link-dump-secret-title';
+        title.textContent = subscription.title || subscription.url;
+        title.title = subscription.url;
+
+        const meta = document.createElement('div');
+        meta.className = 'pf-link-dump-secret-meta';
+        appendTextSpans(meta, [
+            presets[subscription.preset_key]?.queueLabel || subscription.preset_key,
+            `Checked ${subscription.last_checked_at ? formatHistoryDate(subscription.last_checked_at) : 'never'}`,
+            subscription.last_error ? `Error: ${subscription.last_error}` : null,
+        ]);
+
+        content.append(title, meta);
+
+        const actions = document.createElement('div');
+        actions.className = 'pf-row pf-link-dump-secret-actions';
+
+        if (subscription.paused) {
+            const badge = document.createElement('span');
+            badge.className = 'pf-badge pf-badge-muted';
+            badge.textContent = 'Paused';
+            actions.appendChild(badge);
+        }
+
+        const checkBtn = document.createElement('button');
+        checkBtn.className = 'pf-btn pf-btn-ghost';
+        checkBtn.type = 'button';
+        checkBtn.textContent = 'Check now';
+        checkBtn.onclick = () => {
+            void checkSubscriptionNow(subscription.id);
+        };
+
+        const pauseBtn = document.createElement('button');
+        pauseBtn.className = 'pf-btn pf-btn-ghost';
+        pauseBtn.type = 'button';
+        pauseBtn.textContent = subscription.paused ? 'Resume' : 'Pause';
+        pauseBtn.onclick = () => {
+            void setSubscriptionPaused(subscription.id, !subscription.paused);
+        };
+
+        const removeBtn = document.createElement('button');
+        removeBtn.className = 'pf-btn pf-btn-danger';
+        removeBtn.type = 'button';
+        removeBtn.textContent = 'Remove';
+        removeBtn.onclick = () => {
+            if (!window.confirm('Remove this subscription? Downloaded files and queued items are kept.')) {
+                return;
+            }
+            void removeSubscription(subscription.id);
+        };
+
+        actions.append(checkBtn, pauseBtn, removeBtn);
+        item.append(content, actions);
+        els.subscriptionList.appendChild(item);
+    });
+};
+
+const syncSubscriptions = async () => {
+    if (!invoke) return;
+    try {
+        renderSubscriptions(await invoke('list_subscriptions'));
+    } catch (err) {
+        appendLog(`[subscriptions] ${err}`, true);
+    }
+};
+
+const addSubscription = async () => {
+    if (!invoke) return;
+    const url = els.subscriptionUrl.value.trim();
+    if (!isValidHttpUrl(url)) {
+        setSubscriptionStatus('Enter a channel or playlist URL.', true);
+        return;
+    }
+    try {
+        await invoke('add_subscription', {
+            request: { url, preset_key: getSelectedPresetKey() },
+        });
+        els.subscriptionUrl.value = '';
+        setSubscriptionStatus('Subscribed. Checking for new uploads...');
+    } catch (err) {
+        setSubscriptionStatus(`${err}`, true);
+        appendLog(`[subscriptions] ${err}`, true);
+    }
+};
+
+const checkSubscriptionNow = async id => {
+    if (!invoke) return;
+    setSubscriptionStatus('Checking...');
+    try {
+        const result = await invoke('check_subscription_now', { id });
+        setSubscriptionStatus(`Queued ${result.added} new item${result.added === 1 ? '' : 's'}.`);
+    } catch (err) {
+        setSubscriptionStatus(`${err}`, true);
+        appendLog(`[subscriptions] ${err}`, true);
+    }
+};
+
+const setSubscriptionPaused = async (id, paused) => {
+    if (!invoke) return;
+    try {
+        await invoke('set_subscription_paused', { id, paused });
+        setSubscriptionStatus(paused ? 'Subscription paused.' : 'Subscription resumed.');
+    } catch (err) {
+        setSubscriptionStatus(`${err}`, true);
+        appendLog(`[subscriptions] ${err}`, true);
+    }
+};
+
+const removeSubscription = async id => {
+    if (!invoke) return;
+    try {
+        await invoke('remove_subscription', { id });
+        setSubscriptionStatus('Subscription removed.');
+    } catch (err) {
+        setSubscriptionStatus(`${err}`, true);
+        appendLog(`[subscriptions] ${err}`, true);
+    }
+};
+
+const setCookieProfileStatus = (message, isError = false) => {
+    if (!els.cookieProfileStatus) return;
+    els.cookieProfileStatus.textContent = message || '';
+    els.cookieProfileStatus.classList.toggle('pf-status-error', Boolean(message && isError));
+    els.cookieProfileStatus.classList.toggle('pf-status-success', Boolean(message && !isError));
+};
+
+const renderCookieProfiles = profiles => {
+    if (!els.cookieProfileList) return;
+    els.cookieProfileList.replaceChildren();
+    const items = Array.isArray(profiles) ? profiles : [];
+    els.cookieProfileHint.hidden = items.length > 0;
+
+    items.forEach(profile => {
+        const item = document.createElement('div');
+        item.className = 'pf-link-dump-secret-item';
+
+        const content = document.createElement('div');
+        content.className = 'pf-link-dump-secret-content';
+
+        const title = document.createElement('div');
+        title.className = 'pf-link-dump-secret-title';
+        title.textContent = profile.name;
+        title.title = profile.cookies_path;
+
+        const meta = document.createElement('div');
+        meta.className = 'pf-link-dump-secret-meta';
+        appendTextSpans(meta, [(profile.host_patterns || []).join(', ') || 'No hosts']);
+
+        content.append(title, meta);
+
+        const actions = document.createElement('div');
+        actions.className = 'pf-row pf-link-dump-secret-actions';
+
+        const deleteBtn = document.createElement('button');
+        deleteBtn.className = 'pf-btn pf-btn-danger';
+        deleteBtn.type = 'button';
+        deleteBtn.textContent = 'Delete';
+        deleteBtn.onclick = () => {
+            if (!window.confirm('Delete this cookie profile? The cookies file itself is kept.')) {
+                return;
+            }
+            void deleteCookieProfile(profile.id);
+        };
+        actions.appendChild(deleteBtn);
+
+        item.append(content, actions);
+        els.cookieProfileList.appendChild(item);
+    });
+};
+
+const syncCookieProfiles = async () => {
+    if (!invoke) return;
+    try {
+        renderCookieProfiles(await invoke('list_cookie_profiles'));
+    } catch (err) {
+        appendLog(`[cookies] ${err}`, true);
+    }
+};
+
+const pickCookiesFile = async () => {
+    if (!invoke) return;
+    try {
+        const path = await invoke('pick_cookies_file');
+        if (!path) return;
+        els.cookieProfilePath.value = path;
+        const summary = await invoke('validate_cookies_file', { path });
+        setCookieProfileStatus(
+            `${pluralize(summary.cookie_count, 'cookie')} for ${summary.domains.join(', ') || 'no domains'}.`,
+        );
+    } catch (err) {
+        setCookieProfileStatus(`${err}`, true);
+    }
+};
+
+const saveCookieProfile = async () => {
+    if (!invoke) return;
+    try {
+        const profiles = await invoke('save_cookie_profile', {
+            request: {
+                name: els.cookieProfileName.value.trim(),
+                cookies_path: els.cookieProfilePath.value.trim(),
+                host_patterns: els.cookieProfileHosts.value
+                    .split(',')
+                    .map(host => host.trim())
+                    .filter(Boolean),
+            },
+        });
+        renderCookieProfiles(profiles);
+        els.cookieProfileName.value = '';
+        els.cookieProfilePath.value = '';
+        els.cookieProfileHosts.value = '';
+        setCookieProfileStatus('Cookie profile saved.');
+    } catch (err) {
+        setCookieProfileStatus(`${err}`, true);
+        appendLog(`[cookies] ${err}`, true);
+    }
+};
+
+const deleteCookieProfile = async id => {
+    if (!invoke) return;
+    try {
+        renderCookieProfiles(await invoke('delete_cookie_profile', { id }));
+        setCookieProfileStatus('Cookie profile deleted.');
+    } catch (err) {
+        setCookieProfileStatus(`${err}`, true);
+        appendLog(`[cookies] ${err}`, true);
+    }
+};
+
 let loadInfoInFlight = false;
 let loadInfoPending = false;
 let loadInfoRequestId = 0;
@@ -1563,6 +2224,7 @@ const enqueueDownloadForUrl = async (url, presetKey, options = {}) => {
     }
 
     const preset = presets[presetKey] || presets.best;
+    if (!preset) return null;
     const output_dir = els.outputDir.value.trim() || null;
     const cutAtTimestampEnabled = Boolean(els.cutAtTimestampEnabled.checked);
     const cutStartTime = cutAtTimestampEnabled ? extractUrlStartTimestamp(url) : null;
@@ -1587,25 +2249,40 @@ const enqueueDownloadForUrl = async (url, presetKey, options = {}) => {
     const uploadDateForRequest = hasLoadedInfo ? state.info?.upload_date || null : null;
     const timestampForRequest = hasLoadedInfo ? state.info?.timestamp ?? null : null;
     const durationSecondsForRequest = hasLoadedInfo ? state.info?.duration ?? null : null;
+    const videoFormatId = hasLoadedInfo ? els.videoFormatSelect.value || null : null;
+    const audioFormatId = hasLoadedInfo ? els.audioFormatSelect.value || null : null;
+    const scheduledAt = els.scheduleAtInput.value ? new Date(els.scheduleAtInput.value).getTime() : null;
+    const notBefore = scheduledAt && scheduledAt > Date.now() ? scheduledAt : null;
 
     try {
         const id = await invoke('enqueue_download', {
             request: {
                 url,
                 format: preset.format,
+                video_format_id: videoFormatId,
+                audio_format_id: audioFormatId,
                 output_dir,
                 extract_audio: preset.extractAudio,
                 audio_format: preset.audioFormat,
                 transcribe_text: preset.transcribeText,
-                cut_at_timestamp_enabled: cutAtTimestampEnabled,
+                // Explicit clip ranges apply even when URL timestamps are ignored.
+                cut_at_timestamp_enabled: cutAtTimestampEnabled || Boolean(options.ranges?.length),
                 cut_start_time: cutStartTime,
+                ranges: options.ranges ?? [],
                 filename_suffix: preset.filenameSuffix,
+                output_template: preset.outputTemplate,
                 title: titleForRequest,
                 uploader: uploaderForRequest,
                 thumbnail: thumbnailForRequest,
                 upload_date: uploadDateForRequest,
                 timestamp: timestampForRequest,
                 duration_seconds: durationSecondsForRequest,
+                not_before: notBefore,
+                force_download: Boolean(els.forceDownload.checked),
+                split_chapters: Boolean(els.splitChapters.checked),
+                subtitles: preset.subtitles ?? undefined,
+                embed: preset.embed ?? undefined,
+                sponsorblock: preset.sponsorblock ?? undefined,
             },
         });
 
@@ -1658,7 +2335,16 @@ const removeJobFromQueue = async job => {
     if (state.selectedId === job.id) state.selectedId = null;
     renderQueue();
 
-    if (job.state !== 'queued') return;
+    if (requeueableJobStates.has(job.state)) {
+        try {
+            await invoke('delete_failed_job', { id: job.id });
+        } catch (err) {
+            appendLog(`[remove] ${err}`, true);
+        }
+        return;
+    }
+
+    if (!previousQueueIds.includes(job.id)) return;
 
     try {
         await invoke('cancel_download', { id: job.id });
@@ -1675,7 +2361,105 @@ const removeJobFromQueue = async job => {
 const enqueueDownload = async () => {
     const url = els.urlInput.value.trim();
     const presetKey = getSelectedPresetKey();
-    await enqueueDownloadForUrl(url, presetKey);
+    const ranges = parseClipRanges(els.clipRangesInput.value);
+    if (!ranges) {
+        setTxtImportStatus('Clip ranges must look like 0:30-1:30, 5:00-6:10.', true);
+        return;
+    }
+    const id = await enqueueDownloadForUrl(url, presetKey, { ranges });
+    if (id && ranges.length > 0) {
+        els.clipRangesInput.value = '';
+        setTxtImportStatus(`Queued ${pluralize(ranges.length, 'clip')}.`);
+    }
+};
+
+// Ranges are comma separated `start-end` pairs; the end may be left open.
+const parseClipRanges = raw => {
+    const value = `${raw || ''}`.trim();
+    if (!value) return [];
+    const ranges = [];
+    for (const part of value.split(',')) {
+        const match = part.trim().match(/^([^-]*)-([^-]*)$/);
+        if (!match) return null;
+        const [, rawStart, rawEnd] = match.map(item => item.trim());
+        const start = /^[0:.]*$/.test(rawStart) ? 0 : parseTimestampValue(rawStart);
+        const end = rawEnd ? parseTimestampValue(rawEnd) : null;
+        if (start === null || (rawEnd && end === null) || (end !== null && end <= start)) return null;
+        ranges.push({ start, end });
+    }
+    return ranges;
+};
+
+const parsePlaylistRange = raw => {
+    const value = `${raw || ''}`.trim();
+    if (!value) return { start: null, end: null };
+    const match = value.match(/^(\d+)?\s*-\s*(\d+)?$/) || value.match(/^(\d+)$/);
+    if (!match) return null;
+    const start = match[1] ? Number(match[1]) : null;
+    const end = value.includes('-') ? (match[2] ? Number(match[2]) : null) : start;
+    return { start, end };
+};
+
+const queuePlaylist = async () => {
+    if (!invoke) return;
+    const url = els.urlInput.value.trim();
+    if (!isValidHttpUrl(url)) {
+        shakeUrlInput();
+        return;
+    }
+    const range = parsePlaylistRange(els.playlistRangeInput.value);
+    if (!range) {
+        setTxtImportStatus('Playlist items must look like 5-20.', true);
+        return;
+    }
+
+    const preset = presets[getSelectedPresetKey()] || presets.best;
+    if (!preset) {
+        setTxtImportStatus('Presets are still loading.', true);
+        return;
+    }
+    const scheduledAt = els.scheduleAtInput.value ? new Date(els.scheduleAtInput.value).getTime() : null;
+    els.queuePlaylistBtn.disabled = true;
+    setTxtImportStatus('Loading playlist...');
+    try {
+        const result = await invoke('enqueue_playlist', {
+            request: {
+                url,
+                format: preset.format,
+                output_dir: els.outputDir.value.trim() || null,
+                extract_audio: preset.extractAudio,
+                audio_format: preset.audioFormat,
+                transcribe_text: preset.transcribeText,
+                cut_at_timestamp_enabled: false,
+                filename_suffix: preset.filenameSuffix,
+                output_template: preset.outputTemplate,
+                not_before: scheduledAt && scheduledAt > Date.now() ? scheduledAt : null,
+                force_download: Boolean(els.forceDownload.checked),
+                split_chapters: Boolean(els.splitChapters.checked),
+                subtitles: preset.subtitles ?? undefined,
+                embed: preset.embed ?? undefined,
+                sponsorblock: preset.sponsorblock ?? undefined,
+            },
+            options: {
+                start: range.start,
+                end: range.end,
+                include_playlist_in_filename: Boolean(els.playlistInFilename.checked),
+            },
+        });
+        const message = `Queued ${pluralize(result.job_ids.length, 'item')} from ${result.playlist_title || 'playlist'}.`;
+        setTxtImportStatus(message);
+        appendLog(`[playlist] ${message}`);
+        void cacheLastDownloadedUrl(url);
+        els.urlInput.value = '';
+        state.info = null;
+        state.infoUrl = null;
+        renderInfo();
+    } catch (err) {
+        setTxtImportStatus(`${err}`, true);
+        appendLog(`[playlist] ${err}`, true);
+    } finally {
+        els.queuePlaylistBtn.disabled = false;
+    }
 };
 
 const toggleQueueAutoStart = async () => {
@@ -1692,6 +2476,29 @@ const toggleQueueAutoStart = async () => {
     }
 };
 
+const toggleQueuePaused = async () => {
+    if (!invoke) return;
+    try {
+        const status = await invoke('set_queue_paused', { paused: !state.queuePaused });
+        state.queuePaused = Boolean(status?.paused);
+        state.queueWindowOpen = status?.window_open ?? true;
+        state.queueWorkerRunning = Boolean(status?.worker_running);
+        renderQueueControls();
+    } catch (err) {
+        appendLog(`[queue] ${err}`, true);
+    }
+};
+
+const requeueAllFailed = async () => {
+    if (!invoke) return;
+    try {
+        const ids = await invoke('requeue_all_failed');
+        appendLog(`[requeue] ${ids.length} failed download(s) queued again`, false);
+    } catch (err) {
+        appendLog(`[requeue] ${err}`, true);
+    }
+};
+
 const startQueueProcessing = async () => {
     if (!invoke) return;
     try {
@@ -1706,14 +2513,33 @@ const startQueueProcessing = async () => {
 
 const saveSettings = async () => {
     const selectedPresetKey = getSelectedPresetKey();
+    const maxConcurrentDownloads = Math.min(Math.max(Number.parseInt(els.maxConcurrentDownloads.value, 10) || 1, 1), 8);
+    const parseRateLimit = value => {
+        const parsed = Number.parseInt(value, 10);
+        return Number.isFinite(parsed) && parsed > 0 ? parsed : null;
+    };
+    const rateLimitKib = parseRateLimit(els.rateLimitKib.value);
+    const downloadWindowRateLimitKib = parseRateLimit(els.downloadWindowRateLimitKib.value);
+    const retryMaxAttempts = Math.min(Math.max(Number.parseInt(els.retryMaxAttempts.value, 10) || 0, 0), 10);
     try {
         await invoke('set_config', {
             config: {
+                ...(state.config || {}),
                 yt_dlp_path: els.ytDlpPath.value.trim() || null,
                 default_output_dir: els.outputDir.value.trim() || null,
                 selected_preset_key: selectedPresetKey,
                 magic_import_enabled: Boolean(els.magicImportEnabled.checked),
                 cut_at_timestamp_enabled: Boolean(els.cutAtTimestampEnabled.checked),
+                cut_precision: els.cutPrecisionAccurate.checked ? 'accurate' : 'fast',
+                output_template: els.outputTemplateInput.value.trim() || null,
+                resume_queue_on_startup: Boolean(els.resumeQueueOnStartup.checked),
+                max_concurrent_downloads: maxConcurrentDownloads,
+                retry_max_attempts: retryMaxAttempts,
+                download_window_enabled: Boolean(els.downloadWindowEnabled.checked),
+                download_window_start: els.downloadWindowStart.value || '23:00',
+                download_window_end: els.downloadWindowEnd.value || '07:00',
+                rate_limit_kib: rateLimitKib,
+                download_window_rate_limit_kib: downloadWindowRateLimitKib,
                 last_download_url: state.config?.last_download_url || null,
             },
         });
@@ -1724,9 +2550,20 @@ const saveSettings = async () => {
             selected_preset_key: selectedPresetKey,
             magic_import_enabled: Boolean(els.magicImportEnabled.checked),
             cut_at_timestamp_enabled: Boolean(els.cutAtTimestampEnabled.checked),
+            cut_precision: els.cutPrecisionAccurate.checked ? 'accurate' : 'fast',
+            output_template: els.outputTemplateInput.value.trim() || null,
+            resume_queue_on_startup: Boolean(els.resumeQueueOnStartup.checked),
+            max_concurrent_downloads: maxConcurrentDownloads,
+            retry_max_attempts: retryMaxAttempts,
+            download_window_enabled: Boolean(els.downloadWindowEnabled.checked),
+            download_window_start: els.downloadWindowStart.value || '23:00',
+            download_window_end: els.downloadWindowEnd.value || '07:00',
+            rate_limit_kib: rateLimitKib,
+            download_window_rate_limit_kib: downloadWindowRateLimitKib,
             last_download_url: state.config?.last_download_url || null,
         };
         syncMagicImportTriggerState();
+        void refreshFilenamePreview();
         appendLog('[config] saved', false);
         void refreshYtDlpVersions();
     } catch (err) {
@@ -1856,9 +2693,12 @@ const clearQueue = async () => {
     state.jobs.forEach(job => {
         if (
             job.state === 'queued' ||
-            job.state === 'downloading' ||
-            job.state === 'transcribing' ||
-            job.state === 'cancelling'
+            job.state === 'paused' ||
+            job.state === 'retrying' ||
+            job.state === 'scheduled' ||
+            activeJobStates.includes(job.state) ||
+            job.state === 'cancelling' ||
+            job.state === 'pausing'
         ) {
             idsToCancel.add(job.id);
         }
@@ -1901,16 +2741,32 @@ const bindEvents = () => {
     els.startDownloadBtn.addEventListener('click', enqueueDownload);
     els.presetSelect.addEventListener('change', () => {
         void persistSelectedPresetKey();
+        void refreshFilenamePreview();
+    });
+    els.videoFormatSelect.addEventListener('change', () => {
+        void refreshFilenamePreview();
+    });
+    els.audioFormatSelect.addEventListener('change', () => {
+        void refreshFilenamePreview();
     });
     els.importTxtBtn.addEventListener('click', () => {
         void importTxtLinks();
     });
+    els.queuePlaylistBtn.addEventListener('click', () => {
+        void queuePlaylist();
+    });
     els.queueAutoStartBtn.addEventListener('click', () => {
         void toggleQueueAutoStart();
     });
     els.startQueueBtn.addEventListener('click', () => {
         void startQueueProcessing();
     });
+    els.pauseQueueBtn.addEventListener('click', () => {
+        void toggleQueuePaused();
+    });
+    els.requeueFailedBtn.addEventListener('click', () => {
+        void requeueAllFailed();
+    });
 
     let urlInputDebounceTimer = null;
     els.urlInput.addEventListener('input', () => {
@@ -1978,6 +2834,28 @@ const bindEvents = () => {
     els.copyGeneratedLinkDumpSecretBtn.addEventListener('click', () => {
         void copyGeneratedLinkDumpSecret();
     });
+    els.savePresetBtn.addEventListener('click', () => {
+        void savePreset();
+    });
+    els.exportPresetsBtn.addEventListener('click', () => {
+        void exportPresets();
+    });
+    els.importPresetsBtn.addEventListener('click', () => {
+        void importPresets();
+    });
+    els.resetPresetFormBtn.addEventListener('click', () => {
+        resetPresetForm();
+        setPresetStatus('');
+    });
+    els.addSubscriptionBtn.addEventListener('click', () => {
+        void addSubscription();
+    });
+    els.pickCookiesFileBtn.addEventListener('click', () => {
+        void pickCookiesFile();
+    });
+    els.saveCookieProfileBtn.addEventListener('click', () => {
+        void saveCookieProfile();
+    });
     els.clearQueueBtn.addEventListener('click', () => {
         void clearQueue();
     });
@@ -2034,6 +2912,45 @@ const bindEvents = () => {
             return;
         }
 
+        if (button.dataset.action === 'move-top' || button.dataset.action === 'move-bottom') {
+            try {
+                const command =
+                    button.dataset.action === 'move-top' ? 'move_queue_job_to_top' : 'move_queue_job_to_bottom';
+                await invoke(command, { id: job.id });
+            } catch (err) {
+                appendLog(`[queue] ${err}`, true);
+            }
+            return;
+        }
+
+        if (button.dataset.action === 'priority') {
+            try {
+                await invoke('set_queue_job_priority', { id: job.id, priority: job.priority > 0 ? 0 : 1 });
+            } catch (err) {
+                appendLog(`[queue] ${err}`, true);
+            }
+            return;
+        }
+
+        if (button.dataset.action === 'pause' || button.dataset.action === 'resume') {
+            try {
+                const command = button.dataset.action === 'pause' ? 'pause_download' : 'resume_download';
+                await invoke(command, { id: job.id });
+            } catch (err) {
+                appendLog(`[${button.dataset.action}] ${err}`, true);
+            }
+            return;
+        }
+
+        if (button.dataset.action === 'requeue') {
+            try {
+                await invoke('requeue_failed_job', { id: job.id });
+            } catch (err) {
+                appendLog(`[requeue] ${err}`, true);
+            }
+            return;
+        }
+
         if (button.dataset.action === 'cancel') {
             try {
                 await invoke('cancel_download', { id: job.id });
@@ -2078,59 +2995,119 @@ const bindEvents = () => {
     els.clearLogsBtn.addEventListener('click', clearLogs);
 };
 
+const isScheduledJob = job => Boolean(job.not_before && job.not_before > Date.now());
+
+const resolveQueuedJobState = job => {
+    if (job.paused) return 'paused';
+    if (job.retry_at) return 'retrying';
+    if (isScheduledJob(job)) return 'scheduled';
+    return 'queued';
+};
+
+const applyQueueUpdate = jobs => {
+    state.queueIds = jobs.map(job => job.id).filter(id => !state.suppressedJobIds.has(id));
+    jobs.forEach(job => {
+        if (state.suppressedJobIds.has(job.id)) return;
+        const existing = state.jobs.get(job.id);
+        const preset = findPresetForDownloadJob(job);
+        updateJob(job.id, {
+            url: job.url,
+            label: existing?.label || job.title || job.url,
+            thumbnail: existing?.thumbnail || job.thumbnail || resolveYouTubeThumbnail(job.url),
+            state: resolveQueuedJobState(job),
+            priority: job.priority ?? 0,
+            eta: isScheduledJob(job) ? new Date(job.not_before).toLocaleString() : existing?.eta || '-',
+            outputPath: existing?.outputPath || null,
+            cutStartTime: job.cut_start_time ?? null,
+            previewResolved: existing?.previewResolved || Boolean(resolveYouTubeThumbnail(job.url)),
+            previewLoading: existing?.previewLoading || false,
+            formatLabel: existing?.formatLabel || preset?.queueLabel || job.format,
+        });
+        maybeHydrateQueueThumbnail(job.id);
+    });
+};
+
+const syncFailedJobs = async () => {
+    if (!invoke) return;
+    try {
+        const failedJobs = await invoke('list_failed_jobs');
+        failedJobs.forEach(failed => {
+            if (state.jobs.has(failed.id) || state.suppressedJobIds.has(failed.id)) return;
+            updateJob(failed.id, {
+                url: failed.url,
+                label: failed.job?.title || failed.url,
+                thumbnail: failed.job?.thumbnail || resolveYouTubeThumbnail(failed.url),
+                state: failed.state,
+                createdAt: failed.failed_at ?? failed.cancelled_at,
+            });
+        });
+    } catch (err) {
+        appendLog(`[queue] ${err}`, true);
+    }
+};
+
+const syncQueue = async () => {
+    if (!invoke) return;
+    try {
+        applyQueueUpdate(await invoke('get_queue'));
+    } catch (err) {
+        appendLog(`[queue] ${err}`, true);
+    }
+};
+
 const bindBackendEvents = async () => {
     await listen('link-dump:server-status', event => {
         applyLinkDumpServerStatus(event.payload);
     });
 
+    await listen('presets:update', event => {
+        applyPresetList(event.payload);
+    });
+
+    await listen('subscriptions:update', event => {
+        renderSubscriptions(event.payload);
+    });
+
     await listen('queue:status', event => {
         state.queueAutoStartEnabled = event.payload?.auto_start ?? true;
         state.queueWorkerRunning = Boolean(event.payload?.worker_running);
+        state.queuePaused = Boolean(event.payload?.paused);
+        state.queueWindowOpen = event.payload?.window_open ?? true;
         renderQueueControls();
     });
 
     await listen('queue:update', event => {
-        state.queueIds = event.payload.map(job => job.id).filter(id => !state.suppressedJobIds.has(id));
-        event.payload.forEach(job => {
-            if (state.suppressedJobIds.has(job.id)) return;
-            const existing = state.jobs.get(job.id);
-            const preset = findPresetForDownloadJob(job);
-            updateJob(job.id, {
-                url: job.url,
-                label: existing?.label || job.url,
-                thumbnail: existing?.thumbnail || resolveYouTubeThumbnail(job.url),
-                state: 'queued',
-                outputPath: existing?.outputPath || null,
-                cutStartTime: job.cut_start_time ?? null,
-                previewResolved: existing?.previewResolved || Boolean(resolveYouTubeThumbnail(job.url)),
-                previewLoading: existing?.previewLoading || false,
-                formatLabel: existing?.formatLabel || preset?.queueLabel || job.format,
-            });
-            maybeHydrateQueueThumbnail(job.id);
-        });
+        applyQueueUpdate(event.payload);
     });
 
     await listen('download:state', event => {
-        const { id, state: status, output_path, exit_code, error } = event.payload;
+        const { id, state: status, output_path, error, attempt, next_retry_at } = event.payload;
         if (state.suppressedJobIds.has(id)) return;
         const patch = { state: status };
         if (output_path) patch.outputPath = output_path;
-        if (status === 'success') {
+        if (status === 'done' || status === 'skipped') {
             patch.percent = 100;
-            patch.speed = 'done';
+            patch.speed = status;
             patch.eta = '-';
         }
+        if (status === 'retrying') {
+            patch.speed = `retry ${attempt ?? 1}`;
+            patch.eta = next_retry_at ? new Date(next_retry_at).toLocaleTimeString() : '-';
+            updateJob(id, patch);
+            return;
+        }
         updateJob(id, patch);
-        if (error) appendLog(`[${id}] ${error} (${exit_code ?? '?'})`, true);
+        if (error) appendLog(`[${id}] ${error.message} (${error.exit_code ?? '?'})`, true);
     });
 
     await listen('download:progress', event => {
         const { id, percent, speed, eta } = event.payload;
         if (state.suppressedJobIds.has(id)) return;
         updateJob(id, {
-            percent: percent ?? 0,
+            percent: percent ?? state.jobs.get(id)?.percent ?? 0,
             speed: speed || '-',
             eta: eta || '-',
+            progressDetail: formatProgressDetail(event.payload),
         });
     });
 
@@ -2155,10 +3132,15 @@ const init = async () => {
         appendLog('[tauri] API not available. Start the app with `npm run dev` (Tauri), not in a browser.', true);
         return;
     }
+    await syncPresets();
     await syncConfig();
     await syncLinkDumpOverview();
+    await syncSubscriptions();
+    await syncCookieProfiles();
     await syncQueueStatus();
     await bindBackendEvents();
+    await syncQueue();
+    await syncFailedJobs();
     renderQueue();
 };
 